@@ -0,0 +1,120 @@
+use std::fmt;
+use std::str::FromStr;
+use hmac::{Hmac, Mac, NewMac};
+use rand::Rng;
+use sha2::Sha256;
+
+use crate::config::ConfigLoader;
+
+
+/// How much of a voter's identity is kept next to their ballot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnonymityMode
+{
+    /// Ballots are stored under the voter id.
+    Off,
+    /// Ballots are stored under a keyed hash of the voter id, which still lets a voter change their vote.
+    Hashed,
+    /// Who voted and what was voted are stored in separate collections with nothing linking them, so a
+    /// ballot is final once it is stored.
+    Strict,
+}
+
+
+impl fmt::Display for AnonymityMode
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            AnonymityMode::Off => write!(f, "off"),
+            AnonymityMode::Hashed => write!(f, "hashed"),
+            AnonymityMode::Strict => write!(f, "strict"),
+        }
+    }
+}
+
+
+impl FromStr for AnonymityMode
+{
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<AnonymityMode, String>
+    {
+        match mode
+        {
+            "off" => Ok(AnonymityMode::Off),
+            "hashed" => Ok(AnonymityMode::Hashed),
+            "strict" => Ok(AnonymityMode::Strict),
+            _ => Err("expected 'off', 'hashed' or 'strict'".to_owned()),
+        }
+    }
+}
+
+
+/// The `[anonymity]` table: the mode and the secret key voter ids are hashed with.
+#[derive(Clone)]
+pub struct Anonymity
+{
+    pub mode: AnonymityMode,
+    key: Vec<u8>,
+}
+
+
+impl fmt::Debug for Anonymity
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        f.debug_struct("Anonymity").field("mode", &self.mode).finish()
+    }
+}
+
+
+impl Anonymity
+{
+    /// Shortest key accepted, in bytes; the hash is only as hard to reverse as the key is to guess.
+    pub const MIN_KEY_LENGTH: usize = 32;
+
+
+    pub fn load(loader: &mut ConfigLoader) -> Anonymity
+    {
+        let mode = loader.optional("anonymity_mode", AnonymityMode::Off);
+        let key: String = loader.get("anonymity_key").unwrap_or_default();
+        if mode != AnonymityMode::Off
+        {
+            loader.check(key.len() >= Anonymity::MIN_KEY_LENGTH, ||
+                format!("anonymity_key must be at least {} characters long when anonymity_mode is '{}'",
+                    Anonymity::MIN_KEY_LENGTH, mode));
+        }
+        Anonymity { mode, key: key.into_bytes() }
+    }
+
+
+    /// HMAC-SHA256 of the voter id, hex encoded. Without the key it cannot be matched back to a voter id,
+    /// even by trying every plausible id.
+    pub fn voter_hash(&self, voter_id: &str) -> String
+    {
+        let mut mac = Hmac::<Sha256>::new_varkey(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(voter_id.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+
+    /// How the voter is identified in logs and stored documents under this mode.
+    pub fn voter_label(&self, voter_id: &str) -> String
+    {
+        match self.mode
+        {
+            AnonymityMode::Off => voter_id.to_owned(),
+            AnonymityMode::Hashed | AnonymityMode::Strict => self.voter_hash(voter_id),
+        }
+    }
+}
+
+
+/// A random id for a stored ballot, so that unlike an ObjectId it carries no creation time.
+pub fn random_id() -> String
+{
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    hex::encode(bytes)
+}
@@ -0,0 +1,233 @@
+//! The audit trail of admin and system actions.
+//!
+//! Entries are numbered from 1 and each one carries the hash of the one before it, so removing, reordering or
+//! editing an entry breaks the chain from that point on. Services append with the entry's number as the
+//! document `_id`; two writers racing for the same number cannot both succeed, and the loser builds on the
+//! winner's entry instead.
+
+use std::fmt;
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+
+/// The `prev_hash` of the first entry.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+
+/// Something that happened, before it has a place in the chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent
+{
+    /// RFC 3339, when the action was taken rather than when it was appended.
+    pub at: String,
+    /// Who did it: `admin_token`, `oidc:<subject>` or `system:<service>`.
+    pub actor: String,
+    pub action: String,
+    pub details: serde_json::Value,
+}
+
+
+impl AuditEvent
+{
+    pub fn new(actor: impl Into<String>, action: impl Into<String>, details: serde_json::Value) -> AuditEvent
+    {
+        AuditEvent
+        {
+            at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            actor: actor.into(),
+            action: action.into(),
+            details,
+        }
+    }
+}
+
+
+/// A stored entry. `details` is kept as the JSON text that was hashed, so reading it back cannot change it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry
+{
+    #[serde(rename = "_id")]
+    pub seq: i64,
+    pub at: String,
+    pub actor: String,
+    pub action: String,
+    pub details: String,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+
+impl AuditEntry
+{
+    /// The entry that follows `previous`, or the first one.
+    pub fn next(previous: Option<&AuditEntry>, event: &AuditEvent) -> AuditEntry
+    {
+        let mut entry = AuditEntry
+        {
+            seq: previous.map_or(1, |previous| previous.seq + 1),
+            at: event.at.clone(),
+            actor: event.actor.clone(),
+            action: event.action.clone(),
+            details: event.details.to_string(),
+            prev_hash: previous.map_or_else(|| GENESIS_HASH.to_owned(), |previous| previous.hash.clone()),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        entry
+    }
+
+
+    pub fn compute_hash(&self) -> String
+    {
+        let fields = (self.seq, &self.at, &self.actor, &self.action, &self.details, &self.prev_hash);
+        hex::encode(Sha256::digest(&serde_json::to_vec(&fields).unwrap()))
+    }
+}
+
+
+/// Server code MongoDB answers an insert with when another writer already took the entry's number.
+pub const DUPLICATE_KEY_CODE: i32 = 11000;
+
+
+/// How inserting an entry went, as the service's database driver reports it.
+#[derive(Debug)]
+pub enum Inserted<E>
+{
+    Stored,
+    /// Another writer appended an entry with the same number first.
+    NumberTaken,
+    Failed(E),
+}
+
+
+/// What the service does after an insert.
+#[derive(Debug)]
+pub enum AppendStep<E>
+{
+    /// The entry is in the log.
+    Done(AuditEntry),
+    /// Read the latest entry again and insert the entry `Append::entry` builds on it.
+    Retry,
+    Failed(E),
+}
+
+
+/// Appends one event to a log that other services append to as well. Each service only reads the latest entry
+/// and inserts; chaining the entry onto it and deciding what an insert leads to happen here, so every service
+/// follows the same rules. Losing the race for the next number means building on the winner's entry.
+pub struct Append<'a>
+{
+    event: &'a AuditEvent,
+}
+
+
+impl<'a> Append<'a>
+{
+    pub fn new(event: &'a AuditEvent) -> Append<'a>
+    {
+        Append { event }
+    }
+
+
+    /// The entry to insert when `last` is the latest entry in the log.
+    pub fn entry(&self, last: Option<&AuditEntry>) -> AuditEntry
+    {
+        AuditEntry::next(last, self.event)
+    }
+
+
+    pub fn inserted<E>(&self, entry: AuditEntry, inserted: Inserted<E>) -> AppendStep<E>
+    {
+        match inserted
+        {
+            Inserted::Stored =>
+                {
+                    tracing::info!(seq = entry.seq, action = %entry.action, "audit entry appended");
+                    AppendStep::Done(entry)
+                },
+            Inserted::NumberTaken =>
+                {
+                    tracing::debug!(seq = entry.seq, "audit entry number taken, appending after it");
+                    AppendStep::Retry
+                },
+            Inserted::Failed(e) => AppendStep::Failed(e),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuditProblem
+{
+    /// Entries are missing before `found`.
+    Gap { expected: i64, found: i64 },
+    /// The entry's contents no longer match its hash.
+    Modified { seq: i64 },
+    /// The entry does not point at the hash of the entry before it.
+    BrokenLink { seq: i64 },
+    /// A head hash recorded earlier is no longer in the log, so it was cut short or rewritten.
+    MissingHead { hash: String },
+}
+
+
+impl fmt::Display for AuditProblem
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            AuditProblem::Gap { expected, found } => write!(f, "entries {} to {} are missing", expected, found - 1),
+            AuditProblem::Modified { seq } => write!(f, "entry {} was modified", seq),
+            AuditProblem::BrokenLink { seq } => write!(f, "entry {} does not follow entry {}", seq, seq - 1),
+            AuditProblem::MissingHead { hash } => write!(f, "no entry has the expected head hash {}", hash),
+        }
+    }
+}
+
+
+/// What a verification found; `head` is the last entry, worth writing down to check later runs against.
+#[derive(Debug, Default)]
+pub struct Verification
+{
+    pub entries: u64,
+    pub head: Option<AuditEntry>,
+    pub problems: Vec<AuditProblem>,
+}
+
+
+/// Checks entries in `seq` order. After a problem the chain is followed on from the entry as stored, so one
+/// edit is reported once rather than for everything after it.
+pub fn verify(entries: impl IntoIterator<Item = AuditEntry>, expected_head: Option<&str>) -> Verification
+{
+    let mut verification = Verification::default();
+    let mut head_seen = expected_head.is_none();
+    for entry in entries
+    {
+        let (expected_seq, expected_prev) = match &verification.head
+        {
+            Some(previous) => (previous.seq + 1, previous.hash.as_str()),
+            None => (1, GENESIS_HASH),
+        };
+        if entry.seq != expected_seq
+        {
+            verification.problems.push(AuditProblem::Gap { expected: expected_seq, found: entry.seq });
+        }
+        else if entry.prev_hash != expected_prev
+        {
+            verification.problems.push(AuditProblem::BrokenLink { seq: entry.seq });
+        }
+        if entry.compute_hash() != entry.hash
+        {
+            verification.problems.push(AuditProblem::Modified { seq: entry.seq });
+        }
+        head_seen = head_seen || Some(entry.hash.as_str()) == expected_head;
+        verification.entries += 1;
+        verification.head = Some(entry);
+    }
+    if let (false, Some(hash)) = (head_seen, expected_head)
+    {
+        verification.problems.push(AuditProblem::MissingHead { hash: hash.to_owned() });
+    }
+    verification
+}
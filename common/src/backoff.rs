@@ -0,0 +1,49 @@
+use std::time::Duration;
+use rand::Rng;
+
+
+/// Exponential backoff with "equal jitter": each delay is picked at random between half and all of the
+/// current exponential step, so clients that failed together do not retry in lockstep.
+#[derive(Debug, Clone)]
+pub struct Backoff
+{
+    base: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+
+impl Backoff
+{
+    pub fn new(base: Duration, max: Duration) -> Backoff
+    {
+        Backoff { base, max, attempt: 0 }
+    }
+
+
+    pub fn next_delay(&mut self) -> Duration
+    {
+        let step = self.base
+            .checked_mul(2u32.saturating_pow(self.attempt))
+            .map(|step| step.min(self.max))
+            .unwrap_or(self.max);
+        self.attempt = self.attempt.saturating_add(1);
+        let half = step.as_millis() as u64 / 2;
+        Duration::from_millis(half + rand::thread_rng().gen_range(0, half + 1))
+    }
+
+
+    pub fn reset(&mut self)
+    {
+        self.attempt = 0;
+    }
+}
+
+
+impl Default for Backoff
+{
+    fn default() -> Backoff
+    {
+        Backoff::new(Duration::from_millis(500), Duration::from_secs(30))
+    }
+}
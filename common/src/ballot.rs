@@ -0,0 +1,31 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+
+/// A voter's choice, shaped by the poll's voting method:
+/// `"a"` for single choice, `["a", "c"]` for approval or ranked choice (best first) and
+/// `{"a": 5, "b": 2}` for score voting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Ballot
+{
+    Single(String),
+    List(Vec<String>),
+    Scores(BTreeMap<String, u32>),
+}
+
+
+impl Ballot
+{
+    /// Options named by the ballot, in ballot order. A bare option counts as a list of one, so ballots
+    /// cast before a poll switched to approval or ranked choice still count.
+    pub fn choices(&self) -> Vec<&str>
+    {
+        match self
+        {
+            Ballot::Single(option) => vec![option.as_str()],
+            Ballot::List(options) => options.iter().map(String::as_str).collect(),
+            Ballot::Scores(scores) => scores.keys().map(String::as_str).collect(),
+        }
+    }
+}
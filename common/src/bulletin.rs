@@ -0,0 +1,194 @@
+//! Ballot commitments and the Merkle tree they are published in.
+//!
+//! A voter gets back `commitment(ballot, nonce)` and the nonce. The worker appends every commitment to its
+//! poll's bulletin board, a Merkle tree built like a certificate transparency log (RFC 6962): leaves and inner
+//! nodes are hashed with different prefixes, and a tree of `n` leaves splits after the largest power of two
+//! below `n`. An inclusion proof lists the sibling hashes from the leaf up to the root.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::ballot::Ballot;
+
+
+type Hash = [u8; 32];
+
+
+/// Hex SHA-256 of the ballot's JSON and the nonce. Without the nonce nobody can tell which ballot it hides.
+pub fn commitment(ballot: &Ballot, nonce: &str) -> String
+{
+    let mut hasher = Sha256::new();
+    hasher.update(b"ballot\0");
+    hasher.update(serde_json::to_vec(ballot).unwrap());
+    hasher.update(b"\0");
+    hasher.update(nonce.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+
+fn leaf_hash(commitment: &str) -> Hash
+{
+    let mut hasher = Sha256::new();
+    hasher.update([0u8]);
+    hasher.update(commitment.as_bytes());
+    hasher.finalize().into()
+}
+
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash
+{
+    let mut hasher = Sha256::new();
+    hasher.update([1u8]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+
+/// Largest power of two strictly below `n`, for `n > 1`.
+fn split(n: usize) -> usize
+{
+    let mut k = 1;
+    while k * 2 < n
+    {
+        k *= 2;
+    }
+    k
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side
+{
+    Left,
+    Right,
+}
+
+
+/// One sibling on the way from a leaf to the root, and which side of the path it is on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep
+{
+    pub side: Side,
+    pub hash: String,
+}
+
+
+#[derive(Debug, Clone, Default)]
+pub struct MerkleTree
+{
+    leaves: Vec<Hash>,
+}
+
+
+impl MerkleTree
+{
+    pub fn new() -> MerkleTree
+    {
+        MerkleTree::default()
+    }
+
+
+    pub fn from_commitments<'a>(commitments: impl IntoIterator<Item = &'a str>) -> MerkleTree
+    {
+        MerkleTree { leaves: commitments.into_iter().map(leaf_hash).collect() }
+    }
+
+
+    pub fn push(&mut self, commitment: &str)
+    {
+        self.leaves.push(leaf_hash(commitment));
+    }
+
+
+    pub fn len(&self) -> usize
+    {
+        self.leaves.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.leaves.is_empty()
+    }
+
+
+    /// Hex root hash; the hash of nothing for an empty tree.
+    pub fn root(&self) -> String
+    {
+        hex::encode(MerkleTree::subtree_root(&self.leaves))
+    }
+
+
+    fn subtree_root(leaves: &[Hash]) -> Hash
+    {
+        match leaves.len()
+        {
+            0 => Sha256::digest(&[]).into(),
+            1 => leaves[0],
+            n =>
+                {
+                    let (left, right) = leaves.split_at(split(n));
+                    node_hash(&MerkleTree::subtree_root(left), &MerkleTree::subtree_root(right))
+                },
+        }
+    }
+
+
+    /// Siblings from the leaf at `index` up to the root, or `None` past the end of the tree.
+    pub fn proof(&self, index: usize) -> Option<Vec<ProofStep>>
+    {
+        if index >= self.leaves.len()
+        {
+            return None;
+        }
+        let mut steps = Vec::new();
+        let mut leaves = &self.leaves[..];
+        let mut index = index;
+        while leaves.len() > 1
+        {
+            let (left, right) = leaves.split_at(split(leaves.len()));
+            if index < left.len()
+            {
+                steps.push(ProofStep { side: Side::Right, hash: hex::encode(MerkleTree::subtree_root(right)) });
+                leaves = left;
+            }
+            else
+            {
+                steps.push(ProofStep { side: Side::Left, hash: hex::encode(MerkleTree::subtree_root(left)) });
+                index -= left.len();
+                leaves = right;
+            }
+        }
+        // Collected from the root down; a verifier starts at the leaf.
+        steps.reverse();
+        Some(steps)
+    }
+}
+
+
+/// Whether `proof` leads from `commitment` to `root`. This is all a voter needs to check their receipt.
+pub fn verify(commitment: &str, proof: &[ProofStep], root: &str) -> bool
+{
+    let mut hash = leaf_hash(commitment);
+    for step in proof
+    {
+        let sibling = match hex::decode(&step.hash)
+        {
+            Ok(sibling) if sibling.len() == 32 =>
+                {
+                    let mut bytes = [0u8; 32];
+                    bytes.copy_from_slice(&sibling);
+                    bytes
+                },
+            _ => return false,
+        };
+        hash = match step.side
+        {
+            Side::Left => node_hash(&sibling, &hash),
+            Side::Right => node_hash(&hash, &sibling),
+        };
+    }
+    hex::encode(hash) == root
+}
@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use serde::de::DeserializeOwned;
+
+
+/// Exit status used by the binaries when their configuration is invalid (`EX_CONFIG` from sysexits.h).
+pub const EXIT_INVALID_CONFIG: i32 = 78;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source
+{
+    File,
+    Env,
+    Cli,
+}
+
+
+impl fmt::Display for Source
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Source::File => write!(f, "config file"),
+            Source::Env => write!(f, "environment"),
+            Source::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+
+/// Every problem found while loading a configuration, so they can all be fixed in one go.
+#[derive(Debug)]
+pub struct ConfigError
+{
+    pub problems: Vec<String>,
+}
+
+
+impl fmt::Display for ConfigError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        writeln!(f, "Invalid configuration:")?;
+        for problem in &self.problems
+        {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+
+impl std::error::Error for ConfigError {}
+
+
+/// Merges settings from a TOML file, environment variables and command line flags, in increasing priority.
+///
+/// Settings are addressed by flat keys: `redis_addr` is `addr` in the `[redis]` table of the file,
+/// `REDIS_ADDR` in the environment and `--redis-addr` on the command line. The file is read from
+/// `--config <path>` or `CONFIG_FILE`. Values are checked as they are read and every problem is
+/// collected, then `finish` reports them all at once.
+pub struct ConfigLoader
+{
+    values: BTreeMap<String, (String, Source)>,
+    file: toml::value::Table,
+    unused_flags: Vec<String>,
+    problems: Vec<String>,
+}
+
+
+impl ConfigLoader
+{
+    /// Loads `.env`, the config file, the process environment and the process arguments.
+    pub fn load() -> ConfigLoader
+    {
+        dotenv::dotenv().ok();
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let env: Vec<(String, String)> = std::env::vars().collect();
+
+        let mut problems = Vec::new();
+        let path = config_path(&args)
+            .or_else(|| env.iter().find(|(key, _)| key == "CONFIG_FILE").map(|(_, path)| path.clone()));
+        let file = match path
+        {
+            Some(path) => match std::fs::read_to_string(&path)
+                {
+                    Ok(contents) => Some(contents),
+                    Err(e) =>
+                        {
+                            problems.push(format!("could not read config file '{}': {}", path, e));
+                            None
+                        },
+                },
+            None => None,
+        };
+        let mut loader = ConfigLoader::from_sources(file.as_deref(), env, args);
+        problems.append(&mut loader.problems);
+        loader.problems = problems;
+        loader
+    }
+
+
+    pub fn from_sources<E, A>(file: Option<&str>, env: E, args: A) -> ConfigLoader
+        where E: IntoIterator<Item = (String, String)>, A: IntoIterator<Item = String>
+    {
+        let mut loader = ConfigLoader
+        {
+            values: BTreeMap::new(),
+            file: toml::value::Table::new(),
+            unused_flags: Vec::new(),
+            problems: Vec::new(),
+        };
+        if let Some(contents) = file
+        {
+            match contents.parse::<toml::Value>()
+            {
+                Ok(toml::Value::Table(table)) =>
+                    {
+                        flatten("", &table, &mut loader.values);
+                        loader.file = table;
+                    },
+                Ok(_) => loader.problems.push("config file must be a TOML table".to_owned()),
+                Err(e) => loader.problems.push(format!("could not parse config file: {}", e)),
+            }
+        }
+        for (key, value) in env
+        {
+            loader.values.insert(key.to_lowercase(), (value, Source::Env));
+        }
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next()
+        {
+            let flag = match arg.strip_prefix("--")
+            {
+                Some(flag) => flag,
+                None =>
+                    {
+                        loader.problems.push(format!("unexpected argument '{}'", arg));
+                        continue;
+                    },
+            };
+            let (name, value) = match flag.find('=')
+            {
+                Some(index) => (flag[..index].to_owned(), Some(flag[index + 1..].to_owned())),
+                None => (flag.to_owned(), args.next()),
+            };
+            match value
+            {
+                Some(_) if name == "config" => (),
+                Some(value) =>
+                    {
+                        let key = name.replace('-', "_");
+                        loader.unused_flags.push(key.clone());
+                        loader.values.insert(key, (value, Source::Cli));
+                    },
+                None => loader.problems.push(format!("flag '--{}' needs a value", name)),
+            }
+        }
+        loader
+    }
+
+
+    fn parse<T>(&mut self, key: &str) -> Option<T>
+        where T: FromStr, T::Err: fmt::Display
+    {
+        self.unused_flags.retain(|flag| flag != key);
+        let (value, source) = self.values.get(key)?;
+        match value.parse::<T>()
+        {
+            Ok(value) => Some(value),
+            Err(e) =>
+                {
+                    let problem = format!("{} ({}): invalid value '{}': {}", key, source, value, e);
+                    self.problems.push(problem);
+                    None
+                },
+        }
+    }
+
+
+    pub fn get<T>(&mut self, key: &str) -> Option<T>
+        where T: FromStr, T::Err: fmt::Display
+    {
+        self.parse(key)
+    }
+
+
+    pub fn required<T>(&mut self, key: &str) -> Option<T>
+        where T: FromStr, T::Err: fmt::Display
+    {
+        if !self.values.contains_key(key)
+        {
+            self.problems.push(format!("{} must be set", key));
+            return None;
+        }
+        self.parse(key)
+    }
+
+
+    pub fn optional<T>(&mut self, key: &str, default: T) -> T
+        where T: FromStr, T::Err: fmt::Display
+    {
+        self.parse(key).unwrap_or(default)
+    }
+
+
+    /// Reads a comma separated list, e.g. `POLL_OPTIONS=a,b,c` or `options = ["a", "b", "c"]` in `[poll]`.
+    pub fn list(&mut self, key: &str, default: Vec<String>) -> Vec<String>
+    {
+        match self.parse::<String>(key)
+        {
+            Some(list) => list.split(',').map(|item| item.trim().to_owned()).filter(|item| !item.is_empty()).collect(),
+            None => default,
+        }
+    }
+
+
+    /// Reads a structured section of the config file, such as an array of tables.
+    pub fn section<T>(&mut self, key: &str) -> Option<T>
+        where T: DeserializeOwned
+    {
+        let value = self.file.get(key)?.clone();
+        match value.try_into::<T>()
+        {
+            Ok(value) => Some(value),
+            Err(e) =>
+                {
+                    self.problems.push(format!("{} (config file): {}", key, e));
+                    None
+                },
+        }
+    }
+
+
+    /// Records a problem found by the caller's own validation.
+    pub fn check(&mut self, is_valid: bool, problem: impl FnOnce() -> String)
+    {
+        if !is_valid
+        {
+            self.problems.push(problem());
+        }
+    }
+
+
+    pub fn finish(mut self) -> Result<(), ConfigError>
+    {
+        for flag in &self.unused_flags
+        {
+            self.problems.push(format!("unknown flag '--{}'", flag.replace('_', "-")));
+        }
+        if self.problems.is_empty()
+        {
+            Ok(())
+        }
+        else
+        {
+            Err(ConfigError { problems: self.problems })
+        }
+    }
+}
+
+
+fn config_path(args: &[String]) -> Option<String>
+{
+    let mut args = args.iter();
+    while let Some(arg) = args.next()
+    {
+        if arg == "--config"
+        {
+            return args.next().cloned();
+        }
+        if let Some(path) = arg.strip_prefix("--config=")
+        {
+            return Some(path.to_owned());
+        }
+    }
+    None
+}
+
+
+fn flatten(prefix: &str, table: &toml::value::Table, values: &mut BTreeMap<String, (String, Source)>)
+{
+    for (key, value) in table
+    {
+        let key = if prefix.is_empty() { key.clone() } else { format!("{}_{}", prefix, key) };
+        match value
+        {
+            toml::Value::Table(table) => flatten(&key, table, values),
+            // Lists of plain values become comma separated, like they would be written in the environment.
+            toml::Value::Array(array) if array.iter().all(|value| !value.is_table() && !value.is_array()) =>
+                {
+                    let items: Vec<String> = array.iter()
+                        .map(|value| value.as_str().map_or_else(|| value.to_string(), str::to_owned))
+                        .collect();
+                    values.insert(key, (items.join(","), Source::File));
+                },
+            toml::Value::Array(_) => (),
+            toml::Value::String(value) =>
+                {
+                    values.insert(key, (value.clone(), Source::File));
+                },
+            value =>
+                {
+                    values.insert(key, (value.to_string(), Source::File));
+                },
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+use serde::Serialize;
+
+use crate::backoff::Backoff;
+
+
+/// Consecutive failures after which a degraded backend is treated as lost and reconnected.
+const RECONNECT_AFTER_FAILURES: u32 = 3;
+
+
+/// Connected -> Degraded on the first failure, Degraded -> Reconnecting once failures keep coming,
+/// and back to Connected on the first success from either.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState
+{
+    Connected,
+    Degraded,
+    Reconnecting,
+}
+
+
+impl fmt::Display for ConnectionState
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            ConnectionState::Connected => write!(f, "connected"),
+            ConnectionState::Degraded => write!(f, "degraded"),
+            ConnectionState::Reconnecting => write!(f, "reconnecting"),
+        }
+    }
+}
+
+
+struct Inner
+{
+    state: ConnectionState,
+    failures: u32,
+    backoff: Backoff,
+}
+
+
+/// Tracks the health of one backend connection and hands out retry delays.
+/// Every state change is logged, and health endpoints report `state()`.
+pub struct ConnectionStatus
+{
+    backend: &'static str,
+    inner: Mutex<Inner>,
+}
+
+
+impl ConnectionStatus
+{
+    /// Backends start out reconnecting until the first successful call.
+    pub fn new(backend: &'static str) -> ConnectionStatus
+    {
+        ConnectionStatus
+        {
+            backend,
+            inner: Mutex::new(Inner
+                {
+                    state: ConnectionState::Reconnecting,
+                    failures: 0,
+                    backoff: Backoff::default(),
+                }),
+        }
+    }
+
+
+    pub fn state(&self) -> ConnectionState
+    {
+        self.inner.lock().unwrap().state
+    }
+
+
+    pub fn record_success(&self)
+    {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failures = 0;
+        inner.backoff.reset();
+        self.transition(&mut inner, ConnectionState::Connected);
+    }
+
+
+    /// Records a failed call and returns how long to wait before the next attempt.
+    pub fn record_failure(&self, error: &dyn fmt::Display) -> Duration
+    {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failures = inner.failures.saturating_add(1);
+        let state = match inner.state
+        {
+            ConnectionState::Connected if inner.failures < RECONNECT_AFTER_FAILURES => ConnectionState::Degraded,
+            ConnectionState::Degraded if inner.failures < RECONNECT_AFTER_FAILURES => ConnectionState::Degraded,
+            _ => ConnectionState::Reconnecting,
+        };
+        let delay = inner.backoff.next_delay();
+        tracing::warn!(backend = self.backend, failures = inner.failures, retry_in = ?delay, %error, "backend error");
+        self.transition(&mut inner, state);
+        delay
+    }
+
+
+    fn transition(&self, inner: &mut Inner, state: ConnectionState)
+    {
+        if inner.state != state
+        {
+            tracing::info!(backend = self.backend, from = %inner.state, to = %state, "connection state changed");
+            inner.state = state;
+        }
+    }
+}
@@ -0,0 +1,12 @@
+//! Code shared by the vote app, the worker and the result app.
+
+pub mod anonymity;
+pub mod audit;
+pub mod backoff;
+pub mod ballot;
+pub mod bulletin;
+pub mod config;
+pub mod connection;
+pub mod poll;
+pub mod telemetry;
+pub mod vote_status;
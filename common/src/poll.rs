@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, UNIX_EPOCH};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigLoader;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VotingMethod
+{
+    /// One option per voter.
+    Single,
+    /// Any non-empty subset of the options.
+    Approval,
+    /// Options ordered from most to least preferred, tallied by instant runoff.
+    Ranked,
+    /// A score between 0 and `max_score` for each option; unscored options get 0.
+    Score,
+}
+
+
+impl fmt::Display for VotingMethod
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            VotingMethod::Single => write!(f, "single"),
+            VotingMethod::Approval => write!(f, "approval"),
+            VotingMethod::Ranked => write!(f, "ranked"),
+            VotingMethod::Score => write!(f, "score"),
+        }
+    }
+}
+
+
+impl FromStr for VotingMethod
+{
+    type Err = String;
+
+    fn from_str(method: &str) -> Result<VotingMethod, String>
+    {
+        match method
+        {
+            "single" => Ok(VotingMethod::Single),
+            "approval" => Ok(VotingMethod::Approval),
+            "ranked" => Ok(VotingMethod::Ranked),
+            "score" => Ok(VotingMethod::Score),
+            _ => Err("expected 'single', 'approval', 'ranked' or 'score'".to_owned()),
+        }
+    }
+}
+
+
+/// The title and labels for voters reading another language, from `[poll.translations.<locale>]`. Either may be
+/// left out to keep the untranslated one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PollTranslation
+{
+    pub title: Option<String>,
+    pub labels: Option<Vec<String>>,
+}
+
+
+/// The part of the `[poll]` table that is not flat keys.
+#[derive(Deserialize)]
+struct PollTable
+{
+    #[serde(default)]
+    translations: BTreeMap<String, PollTranslation>,
+}
+
+
+/// What is being voted on and how, read from the `[poll]` table.
+#[derive(Debug, Clone)]
+pub struct Poll
+{
+    /// Names the poll in URLs such as `/api/polls/{id}/proof/{commitment}`.
+    pub id: String,
+    /// Shown above the options; defaults to the labels joined with " vs ".
+    pub title: String,
+    pub method: VotingMethod,
+    pub options: Vec<String>,
+    /// What voters see for each option, in the same order; defaults to the option ids.
+    pub labels: Vec<String>,
+    /// By BCP 47 locale, e.g. `de` or `ar`; the front-ends pick the one matching the reader's language.
+    pub translations: BTreeMap<String, PollTranslation>,
+    pub max_score: u32,
+    pub window: PollWindow,
+}
+
+
+impl Poll
+{
+    pub fn load(loader: &mut ConfigLoader) -> Poll
+    {
+        let id = loader.optional("poll_id", "default".to_owned());
+        let method = loader.optional("poll_method", VotingMethod::Single);
+        let options = loader.list("poll_options", vec!["a".to_owned(), "b".to_owned()]);
+        let labels = loader.list("poll_labels", options.clone());
+        let title = loader.optional("poll_title", labels.join(" vs "));
+        let translations = loader.section::<PollTable>("poll").map(|poll| poll.translations).unwrap_or_default();
+        let max_score = loader.optional("poll_max_score", 5);
+        let window = PollWindow::load(loader);
+
+        loader.check(!id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'), ||
+            format!("poll_id may only use letters, digits, '-' and '_', got '{}'", id));
+        loader.check(options.len() >= 2, || "poll_options must list at least two options".to_owned());
+        let mut unique = options.clone();
+        unique.sort();
+        unique.dedup();
+        loader.check(unique.len() == options.len(), || "poll_options must not repeat an option".to_owned());
+        loader.check(labels.len() == options.len(), || "poll_labels must have one label per option".to_owned());
+        for (locale, translation) in &translations
+        {
+            loader.check(!matches!(&translation.labels, Some(labels) if labels.len() != options.len()), ||
+                format!("poll.translations.{}.labels must have one label per option", locale));
+        }
+        loader.check(max_score > 0, || "poll_max_score must be positive".to_owned());
+        Poll { id, title, method, options, labels, translations, max_score, window }
+    }
+}
+
+
+/// Redis hash of the last vote a voter queued in a poll and the last one the worker applied, which is how the
+/// vote app answers `GET /my-vote` without reading the ballots. `voter_label` comes from
+/// `Anonymity::voter_label`, so under hashed anonymity the key does not name the voter either.
+pub fn choice_key(poll_id: &str, voter_label: &str) -> String
+{
+    format!("my_vote:{}:{}", poll_id, voter_label)
+}
+
+
+/// Both services reset it whenever they write the hash, so a voter's record outlives the poll but not for ever.
+pub const CHOICE_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+
+
+/// Lua script that drops the pending vote from the choice hash `KEYS[1]` when it is still request `ARGV[1]`,
+/// for a vote that never reached the queue or that the worker gave up on. A later vote is left alone.
+pub const CLEAR_PENDING_SCRIPT: &str = "if redis.call('HGET', KEYS[1], 'pending_request_id') == ARGV[1] then \
+    return redis.call('HDEL', KEYS[1], 'pending_request_id', 'pending_vote') end return 0";
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollStatus
+{
+    NotOpen,
+    Open,
+    Closed,
+}
+
+
+/// When votes are accepted. Both ends are optional; a poll without `closes_at` never ends.
+///
+/// Read from `opens_at` and `closes_at` in the `[poll]` table as RFC 3339 timestamps,
+/// e.g. `closes_at = "2020-11-03T20:00:00Z"`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PollWindow
+{
+    pub opens_at: Option<DateTime<Utc>>,
+    pub closes_at: Option<DateTime<Utc>>,
+}
+
+
+impl PollWindow
+{
+    pub fn load(loader: &mut ConfigLoader) -> PollWindow
+    {
+        let opens_at: Option<DateTime<Utc>> = loader.get("poll_opens_at");
+        let closes_at: Option<DateTime<Utc>> = loader.get("poll_closes_at");
+        if let (Some(opens_at), Some(closes_at)) = (opens_at, closes_at)
+        {
+            loader.check(opens_at < closes_at, || "poll_opens_at must be before poll_closes_at".to_owned());
+        }
+        PollWindow { opens_at, closes_at }
+    }
+
+
+    pub fn status_at(&self, at: DateTime<Utc>) -> PollStatus
+    {
+        if matches!(self.opens_at, Some(opens_at) if at < opens_at)
+        {
+            PollStatus::NotOpen
+        }
+        else if matches!(self.closes_at, Some(closes_at) if at >= closes_at)
+        {
+            PollStatus::Closed
+        }
+        else
+        {
+            PollStatus::Open
+        }
+    }
+
+
+    pub fn status(&self) -> PollStatus
+    {
+        self.status_at(Utc::now())
+    }
+
+
+    /// Whether the poll closed at least `delay` ago.
+    pub fn closed_for(&self, delay: Duration) -> bool
+    {
+        let delay = chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+        self.status_at(Utc::now() - delay) == PollStatus::Closed
+    }
+
+
+    /// Status at a millisecond unix timestamp, as carried by queued votes.
+    pub fn status_at_millis(&self, millis: u64) -> PollStatus
+    {
+        self.status_at(DateTime::from(UNIX_EPOCH + Duration::from_millis(millis)))
+    }
+}
@@ -0,0 +1,141 @@
+use std::str::FromStr;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::config::ConfigLoader;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat
+{
+    Text,
+    Json,
+}
+
+
+impl FromStr for LogFormat
+{
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<LogFormat, String>
+    {
+        match format
+        {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("expected 'text' or 'json'".to_owned()),
+        }
+    }
+}
+
+
+pub struct TelemetryConfig
+{
+    pub log_format: LogFormat,
+    /// gRPC endpoint of an OpenTelemetry collector, e.g. `http://localhost:4317`.
+    pub otlp_endpoint: Option<String>,
+}
+
+
+impl TelemetryConfig
+{
+    pub fn load(loader: &mut ConfigLoader) -> TelemetryConfig
+    {
+        TelemetryConfig
+        {
+            log_format: loader.optional("log_format", LogFormat::Text),
+            otlp_endpoint: loader.get("otlp_endpoint"),
+        }
+    }
+}
+
+
+/// Keeps the exporter alive; spans still buffered are flushed when it is dropped at the end of `main`.
+pub struct Telemetry
+{
+    #[cfg(feature = "otlp")]
+    _runtime: Option<tokio::runtime::Runtime>,
+}
+
+
+impl Drop for Telemetry
+{
+    fn drop(&mut self)
+    {
+        #[cfg(feature = "otlp")]
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
+
+/// Installs the global `tracing` subscriber. `RUST_LOG` filters events and defaults to `info`.
+pub fn init(service: &'static str, config: &TelemetryConfig) -> Telemetry
+{
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let (text, json) = match config.log_format
+    {
+        LogFormat::Text => (Some(fmt::layer()), None),
+        LogFormat::Json => (None, Some(fmt::layer().json())),
+    };
+    let registry = tracing_subscriber::registry().with(filter).with(text).with(json);
+
+    #[cfg(feature = "otlp")]
+    {
+        let (runtime, tracer) = match &config.otlp_endpoint
+        {
+            Some(endpoint) => match otlp::tracer(service, endpoint)
+                {
+                    Ok((runtime, tracer)) => (Some(runtime), Some(tracer)),
+                    Err(e) =>
+                        {
+                            eprintln!("Could not start the OTLP exporter: {}", e);
+                            (None, None)
+                        },
+                },
+            None => (None, None),
+        };
+        registry.with(tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer))).init();
+        tracing::info!(service, otlp_endpoint = ?config.otlp_endpoint, "telemetry initialized");
+        Telemetry { _runtime: runtime }
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    {
+        registry.init();
+        if config.otlp_endpoint.is_some()
+        {
+            tracing::warn!(service, "otlp_endpoint is set but this binary was built without the `otlp` feature");
+        }
+        Telemetry {}
+    }
+}
+
+
+#[cfg(feature = "otlp")]
+mod otlp
+{
+    use opentelemetry::{sdk, KeyValue};
+    use opentelemetry::sdk::trace::Tracer;
+
+    /// The exporter speaks tonic, which needs a tokio 1 runtime; the services themselves run on older
+    /// runtimes, so the batch exporter gets a runtime of its own.
+    pub fn tracer(service: &'static str, endpoint: &str)
+        -> Result<(tokio::runtime::Runtime, Tracer), Box<dyn std::error::Error>>
+    {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("otlp-exporter")
+            .enable_all()
+            .build()?;
+        let tracer =
+            {
+                let _context = runtime.enter();
+                let resource = sdk::Resource::new(vec![KeyValue::new("service.name", service)]);
+                opentelemetry_otlp::new_pipeline()
+                    .with_endpoint(endpoint)
+                    .with_trace_config(sdk::trace::config().with_resource(resource))
+                    .with_tonic()
+                    .install_batch(opentelemetry::runtime::Tokio)?
+            };
+        Ok((runtime, tracer))
+    }
+}
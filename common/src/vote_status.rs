@@ -0,0 +1,54 @@
+//! Where a vote is between the vote app accepting it and the worker being done with it. The vote app writes
+//! `queued` under `status_key(request_id)` before queueing the vote, and the worker overwrites it with the
+//! outcome. Both set `STATUS_TTL_SECS`, so statuses of old votes go away by themselves.
+
+use serde::{Deserialize, Serialize};
+
+
+pub const STATUS_TTL_SECS: u64 = 24 * 60 * 60;
+
+
+pub fn status_key(request_id: &str) -> String
+{
+    format!("vote_status:{}", request_id)
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteState
+{
+    /// Waiting in the queue, or put back in it after a failed attempt.
+    Queued,
+    /// Stored; the voter's ballot is now this one.
+    Applied,
+    /// Dropped on purpose, see the reason.
+    Rejected,
+    /// Moved to the dead-letter queue after too many failed attempts. It is stored if an operator replays it.
+    Failed,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteStatus
+{
+    pub state: VoteState,
+    /// Why the vote was rejected or failed, e.g. `poll_closed`, `already_voted` or `dead_lettered`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+
+impl VoteStatus
+{
+    pub fn new(state: VoteState) -> VoteStatus
+    {
+        VoteStatus { state, reason: None }
+    }
+
+
+    pub fn with_reason(state: VoteState, reason: &str) -> VoteStatus
+    {
+        VoteStatus { state, reason: Some(reason.to_owned()) }
+    }
+}
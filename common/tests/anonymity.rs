@@ -0,0 +1,65 @@
+use common::anonymity::{random_id, Anonymity, AnonymityMode};
+use common::config::ConfigLoader;
+
+
+const KEY: &str = "0123456789abcdef0123456789abcdef";
+
+
+fn load(file: &str) -> (Anonymity, Result<(), Vec<String>>)
+{
+    let mut loader = ConfigLoader::from_sources(Some(file), Vec::new(), Vec::new());
+    let anonymity = Anonymity::load(&mut loader);
+    (anonymity, loader.finish().map_err(|e| e.problems))
+}
+
+
+#[test]
+fn off_is_the_default_and_needs_no_key()
+{
+    let (anonymity, result) = load("");
+    assert_eq!(anonymity.mode, AnonymityMode::Off);
+    assert!(result.is_ok());
+    assert_eq!(anonymity.voter_label("voter-1"), "voter-1");
+}
+
+
+#[test]
+fn hashing_modes_need_a_long_enough_key()
+{
+    for mode in &["hashed", "strict"]
+    {
+        let (_, result) = load(&format!("[anonymity]\nmode = \"{}\"\nkey = \"short\"\n", mode));
+        let problems = result.unwrap_err();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("anonymity_key must be at least 32 characters"), "{}", problems[0]);
+    }
+    let (_, result) = load("[anonymity]\nmode = \"paranoid\"\n");
+    assert!(result.unwrap_err()[0].starts_with("anonymity_mode (config file): invalid value 'paranoid'"));
+}
+
+
+#[test]
+fn voter_hashes_are_keyed_and_stable()
+{
+    let (hashed, result) = load(&format!("[anonymity]\nmode = \"hashed\"\nkey = \"{}\"\n", KEY));
+    assert!(result.is_ok());
+    let hash = hashed.voter_hash("voter-1");
+    assert_eq!(hash.len(), 64);
+    assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(hashed.voter_hash("voter-1"), hash);
+    assert_ne!(hashed.voter_hash("voter-2"), hash);
+    assert_eq!(hashed.voter_label("voter-1"), hash);
+
+    let (other_key, _) = load(&format!("[anonymity]\nmode = \"hashed\"\nkey = \"{}\"\n", KEY.to_uppercase()));
+    assert_ne!(other_key.voter_hash("voter-1"), hash);
+}
+
+
+#[test]
+fn random_ids_are_hex_and_distinct()
+{
+    let (first, second) = (random_id(), random_id());
+    assert_eq!(first.len(), 32);
+    assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(first, second);
+}
@@ -0,0 +1,138 @@
+use serde_json::json;
+use common::audit::{verify, Append, AppendStep, AuditEntry, AuditEvent, AuditProblem, Inserted, GENESIS_HASH};
+
+
+fn chain(n: usize) -> Vec<AuditEntry>
+{
+    let mut entries: Vec<AuditEntry> = Vec::new();
+    for i in 0..n
+    {
+        let event = AuditEvent
+        {
+            at: format!("2020-11-03T08:00:0{}.000Z", i % 10),
+            actor: "admin_token".to_owned(),
+            action: "poll.update".to_owned(),
+            details: json!({ "step": i }),
+        };
+        let entry = AuditEntry::next(entries.last(), &event);
+        entries.push(entry);
+    }
+    entries
+}
+
+
+#[test]
+fn a_clean_chain_verifies()
+{
+    let entries = chain(5);
+    assert_eq!(entries[0].seq, 1);
+    assert_eq!(entries[0].prev_hash, GENESIS_HASH);
+    assert_eq!(entries[4].prev_hash, entries[3].hash);
+
+    let verification = verify(entries.clone(), None);
+    assert!(verification.problems.is_empty(), "{:?}", verification.problems);
+    assert_eq!(verification.entries, 5);
+    assert_eq!(verification.head.as_ref(), entries.last());
+
+    // A head written down earlier is still in the chain once more entries follow it.
+    let verification = verify(entries.clone(), Some(&entries[2].hash));
+    assert!(verification.problems.is_empty(), "{:?}", verification.problems);
+
+    let verification = verify(Vec::new(), None);
+    assert!(verification.problems.is_empty());
+    assert_eq!(verification.entries, 0);
+    assert!(verification.head.is_none());
+}
+
+
+#[test]
+fn a_removed_entry_is_a_gap()
+{
+    let mut entries = chain(5);
+    entries.remove(2);
+    let problems = verify(entries, None).problems;
+    assert_eq!(problems, vec![AuditProblem::Gap { expected: 3, found: 4 }]);
+    assert_eq!(problems[0].to_string(), "entries 3 to 3 are missing");
+
+    let mut entries = chain(3);
+    entries.remove(0);
+    assert_eq!(verify(entries, None).problems, vec![AuditProblem::Gap { expected: 1, found: 2 }]);
+}
+
+
+#[test]
+fn an_edited_entry_is_reported_once()
+{
+    let mut entries = chain(5);
+    entries[1].actor = "system:worker".to_owned();
+    let problems = verify(entries, None).problems;
+    assert_eq!(problems, vec![AuditProblem::Modified { seq: 2 }]);
+    assert_eq!(problems[0].to_string(), "entry 2 was modified");
+}
+
+
+#[test]
+fn an_entry_pointing_elsewhere_breaks_the_link()
+{
+    let mut entries = chain(5);
+    entries[4].prev_hash = entries[2].hash.clone();
+    entries[4].hash = entries[4].compute_hash();
+    let problems = verify(entries, None).problems;
+    assert_eq!(problems, vec![AuditProblem::BrokenLink { seq: 5 }]);
+    assert_eq!(problems[0].to_string(), "entry 5 does not follow entry 4");
+
+    // Rehashing a middle entry also breaks the link of the one after it, which still names the old hash.
+    let mut entries = chain(5);
+    entries[2].prev_hash = entries[0].hash.clone();
+    entries[2].hash = entries[2].compute_hash();
+    assert_eq!(verify(entries, None).problems,
+        vec![AuditProblem::BrokenLink { seq: 3 }, AuditProblem::BrokenLink { seq: 4 }]);
+}
+
+
+#[test]
+fn a_head_that_is_gone_is_reported()
+{
+    let entries = chain(5);
+    let head = entries[4].hash.clone();
+
+    let verification = verify(entries[..3].to_vec(), Some(&head));
+    assert_eq!(verification.problems, vec![AuditProblem::MissingHead { hash: head.clone() }]);
+    assert_eq!(verification.problems[0].to_string(), format!("no entry has the expected head hash {}", head));
+
+    // Rewriting the last entry consistently keeps the chain intact but loses the head.
+    let mut rewritten = entries.clone();
+    rewritten[4].details = json!({ "step": 40 }).to_string();
+    rewritten[4].hash = rewritten[4].compute_hash();
+    assert!(verify(rewritten.clone(), None).problems.is_empty());
+    assert_eq!(verify(rewritten, Some(&head)).problems, vec![AuditProblem::MissingHead { hash: head.clone() }]);
+
+    assert_eq!(verify(Vec::new(), Some(&head)).problems, vec![AuditProblem::MissingHead { hash: head }]);
+}
+
+
+#[test]
+fn appends_chain_onto_the_latest_entry_and_retry_when_the_number_is_taken()
+{
+    let entries = chain(2);
+    let event = AuditEvent::new("system:worker", "vote.dead_lettered", json!({}));
+    let append = Append::new(&event);
+
+    let first = append.entry(None);
+    assert_eq!((first.seq, first.prev_hash.as_str()), (1, GENESIS_HASH));
+    assert!(matches!(append.inserted(first, Inserted::<()>::NumberTaken), AppendStep::Retry));
+
+    let entry = append.entry(entries.last());
+    assert_eq!((entry.seq, &entry.prev_hash), (3, &entries[1].hash));
+    assert_eq!(entry.action, "vote.dead_lettered");
+    match append.inserted(entry.clone(), Inserted::<()>::Stored)
+    {
+        AppendStep::Done(done) => assert_eq!(done, entry),
+        step => panic!("expected the append to be done, got {:?}", step),
+    }
+    assert!(matches!(append.inserted(entry, Inserted::Failed("unreachable")), AppendStep::Failed("unreachable")));
+
+    let mut appended = entries;
+    appended.push(append.entry(appended.last()));
+    assert!(verify(appended, None).problems.is_empty());
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+use common::backoff::Backoff;
+
+
+#[test]
+fn delays_double_with_equal_jitter_up_to_the_maximum()
+{
+    let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(1000));
+    for step in &[100, 200, 400, 800, 1000, 1000]
+    {
+        let delay = backoff.next_delay();
+        assert!(delay >= Duration::from_millis(step / 2) && delay <= Duration::from_millis(*step),
+            "{:?} outside {}ms step", delay, step);
+    }
+}
+
+
+#[test]
+fn reset_starts_over_from_the_base_delay()
+{
+    let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(30));
+    for _ in 0..10
+    {
+        backoff.next_delay();
+    }
+    backoff.reset();
+    assert!(backoff.next_delay() <= Duration::from_millis(100));
+}
+
+
+#[test]
+fn many_attempts_neither_overflow_nor_pass_the_maximum()
+{
+    let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(30));
+    for _ in 0..200
+    {
+        assert!(backoff.next_delay() <= Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,36 @@
+use std::collections::BTreeMap;
+use common::ballot::Ballot;
+
+
+#[test]
+fn each_method_has_its_own_shape()
+{
+    assert_eq!(serde_json::from_str::<Ballot>(r#""a""#).unwrap(), Ballot::Single("a".to_owned()));
+    let list = Ballot::List(vec!["c".to_owned(), "a".to_owned()]);
+    assert_eq!(serde_json::from_str::<Ballot>(r#"["c", "a"]"#).unwrap(), list);
+    let scores: BTreeMap<String, u32> = vec![("a".to_owned(), 5), ("b".to_owned(), 2)].into_iter().collect();
+    assert_eq!(serde_json::from_str::<Ballot>(r#"{"a": 5, "b": 2}"#).unwrap(), Ballot::Scores(scores));
+    assert!(serde_json::from_str::<Ballot>("5").is_err());
+    assert!(serde_json::from_str::<Ballot>(r#"{"a": -1}"#).is_err());
+}
+
+
+#[test]
+fn ballots_serialize_back_to_the_same_shape()
+{
+    for json in &[r#""a""#, r#"["c","a"]"#, r#"{"a":5,"b":2}"#]
+    {
+        let ballot: Ballot = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&ballot).unwrap(), *json);
+    }
+}
+
+
+#[test]
+fn choices_are_in_ballot_order()
+{
+    assert_eq!(Ballot::Single("a".to_owned()).choices(), vec!["a"]);
+    assert_eq!(Ballot::List(vec!["c".to_owned(), "a".to_owned()]).choices(), vec!["c", "a"]);
+    let scores = vec![("b".to_owned(), 1), ("a".to_owned(), 3)].into_iter().collect();
+    assert_eq!(Ballot::Scores(scores).choices(), vec!["a", "b"]);
+}
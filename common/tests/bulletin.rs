@@ -0,0 +1,161 @@
+use sha2::{Digest, Sha256};
+use common::bulletin::{verify, MerkleTree, ProofStep, Side};
+
+
+type Hash = [u8; 32];
+
+
+fn leaf(commitment: &str) -> Hash
+{
+    let mut hasher = Sha256::new();
+    hasher.update([0u8]);
+    hasher.update(commitment.as_bytes());
+    hasher.finalize().into()
+}
+
+
+fn node(left: Hash, right: Hash) -> Hash
+{
+    let mut hasher = Sha256::new();
+    hasher.update([1u8]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+
+fn commitments(n: usize) -> Vec<String>
+{
+    (0..n).map(|i| format!("commitment-{}", i)).collect()
+}
+
+
+fn tree(commitments: &[String]) -> MerkleTree
+{
+    MerkleTree::from_commitments(commitments.iter().map(String::as_str))
+}
+
+
+#[test]
+fn roots_split_after_the_largest_power_of_two_below_the_size()
+{
+    let c = commitments(7);
+    let l: Vec<Hash> = c.iter().map(|commitment| leaf(commitment)).collect();
+    let empty: Hash = Sha256::digest(&[]).into();
+    let four = node(node(l[0], l[1]), node(l[2], l[3]));
+    let expected = [
+        empty,
+        l[0],
+        node(l[0], l[1]),
+        node(node(l[0], l[1]), l[2]),
+        four,
+        node(four, l[4]),
+        node(four, node(l[4], l[5])),
+        node(four, node(node(l[4], l[5]), l[6])),
+    ];
+    for (n, expected) in expected.iter().enumerate()
+    {
+        assert_eq!(tree(&c[..n]).root(), hex::encode(expected), "root of {} leaves", n);
+    }
+}
+
+
+#[test]
+fn pushing_builds_the_same_tree()
+{
+    let c = commitments(5);
+    let mut pushed = MerkleTree::new();
+    assert!(pushed.is_empty());
+    for commitment in &c
+    {
+        pushed.push(commitment);
+    }
+    assert_eq!(pushed.len(), 5);
+    assert_eq!(pushed.root(), tree(&c).root());
+}
+
+
+#[test]
+fn every_leaf_has_a_proof_that_verifies()
+{
+    for n in &[1, 2, 3, 4, 5, 7, 8, 13, 16]
+    {
+        let c = commitments(*n);
+        let tree = tree(&c);
+        let root = tree.root();
+        for (index, commitment) in c.iter().enumerate()
+        {
+            let proof = tree.proof(index).unwrap();
+            assert!(verify(commitment, &proof, &root), "leaf {} of {}", index, n);
+        }
+        assert_eq!(tree.proof(*n), None);
+    }
+    assert_eq!(MerkleTree::new().proof(0), None);
+}
+
+
+#[test]
+fn proofs_list_siblings_from_the_leaf_up()
+{
+    let c = commitments(5);
+    let l: Vec<Hash> = c.iter().map(|commitment| leaf(commitment)).collect();
+    let step = |side: Side, hash: Hash| ProofStep { side, hash: hex::encode(hash) };
+    let tree = tree(&c);
+    assert_eq!(tree.proof(2).unwrap(), vec![
+        step(Side::Right, l[3]),
+        step(Side::Left, node(l[0], l[1])),
+        step(Side::Right, l[4]),
+    ]);
+    assert_eq!(tree.proof(4).unwrap(), vec![step(Side::Left, node(node(l[0], l[1]), node(l[2], l[3])))]);
+    assert!(MerkleTree::from_commitments(vec!["alone"]).proof(0).unwrap().is_empty());
+}
+
+
+#[test]
+fn a_proof_for_another_commitment_fails()
+{
+    let c = commitments(5);
+    let tree = tree(&c);
+    let proof = tree.proof(1).unwrap();
+    assert!(!verify(&c[2], &proof, &tree.root()));
+    assert!(!verify("commitment-x", &proof, &tree.root()));
+}
+
+
+#[test]
+fn a_tampered_proof_fails()
+{
+    let c = commitments(5);
+    let tree = tree(&c);
+    let root = tree.root();
+    let proof = tree.proof(1).unwrap();
+
+    let mut flipped = proof.clone();
+    flipped[0].side = Side::Right;
+    assert!(!verify(&c[1], &flipped, &root));
+
+    let mut altered = proof.clone();
+    altered[1].hash = hex::encode(leaf("commitment-x"));
+    assert!(!verify(&c[1], &altered, &root));
+
+    let mut not_hex = proof.clone();
+    not_hex[0].hash = "zz".repeat(32);
+    assert!(!verify(&c[1], &not_hex, &root));
+
+    let mut short = proof.clone();
+    short[0].hash.truncate(62);
+    assert!(!verify(&c[1], &short, &root));
+
+    assert!(!verify(&c[1], &proof[..2], &root));
+}
+
+
+#[test]
+fn a_proof_against_another_root_fails()
+{
+    let c = commitments(5);
+    let proof = tree(&c).proof(3).unwrap();
+    assert!(!verify(&c[3], &proof, &tree(&c[..4]).root()));
+    assert!(!verify(&c[3], &proof, &tree(&commitments(6)).root()));
+    assert!(!verify(&c[3], &proof, ""));
+}
@@ -0,0 +1,151 @@
+use serde::Deserialize;
+use common::config::ConfigLoader;
+
+
+fn env(pairs: &[(&str, &str)]) -> Vec<(String, String)>
+{
+    pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+}
+
+
+fn args(args: &[&str]) -> Vec<String>
+{
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+
+const FILE: &str = r#"
+bind = "0.0.0.0:8080"
+poll_interval_ms = 100
+
+[redis]
+addr = "redis://file:6379"
+key = "votes"
+
+[mongodb]
+addr = "mongodb://file:27017"
+"#;
+
+
+#[test]
+fn env_overrides_the_file_and_flags_override_both()
+{
+    let mut loader = ConfigLoader::from_sources(
+        Some(FILE),
+        env(&[("REDIS_ADDR", "redis://env:6379"), ("MONGODB_ADDR", "mongodb://env:27017")]),
+        args(&["--mongodb-addr", "mongodb://cli:27017"]),
+    );
+    assert_eq!(loader.get::<String>("bind").as_deref(), Some("0.0.0.0:8080"));
+    assert_eq!(loader.get::<String>("redis_addr").as_deref(), Some("redis://env:6379"));
+    assert_eq!(loader.get::<String>("mongodb_addr").as_deref(), Some("mongodb://cli:27017"));
+    assert!(loader.finish().is_ok());
+}
+
+
+#[test]
+fn flags_take_the_value_after_them_or_after_an_equals_sign()
+{
+    let mut loader = ConfigLoader::from_sources(None, Vec::new(),
+        args(&["--config", "ignored.toml", "--redis-key=queue", "--poll-interval-ms", "250"]));
+    assert_eq!(loader.get::<String>("redis_key").as_deref(), Some("queue"));
+    assert_eq!(loader.optional("poll_interval_ms", 100_u64), 250);
+    assert!(loader.finish().is_ok());
+}
+
+
+#[test]
+fn nested_tables_become_flat_keys()
+{
+    let file = r#"
+        [poll]
+        options = ["a", "b", "c"]
+        max_score = 7
+
+        [poll.window]
+        opens_at = "2020-11-03T08:00:00Z"
+    "#;
+    let mut loader = ConfigLoader::from_sources(Some(file), Vec::new(), Vec::new());
+    assert_eq!(loader.list("poll_options", Vec::new()), vec!["a", "b", "c"]);
+    assert_eq!(loader.optional("poll_max_score", 5_u32), 7);
+    assert_eq!(loader.get::<String>("poll_window_opens_at").as_deref(), Some("2020-11-03T08:00:00Z"));
+    assert_eq!(loader.list("poll_labels", vec!["x".to_owned()]), vec!["x"]);
+    assert!(loader.finish().is_ok());
+}
+
+
+#[test]
+fn lists_from_the_environment_are_comma_separated()
+{
+    let mut loader = ConfigLoader::from_sources(None, env(&[("POLL_OPTIONS", "a, b,,c")]), Vec::new());
+    assert_eq!(loader.list("poll_options", Vec::new()), vec!["a", "b", "c"]);
+}
+
+
+#[test]
+fn sections_deserialize_from_the_file()
+{
+    #[derive(Deserialize)]
+    struct Group
+    {
+        name: String,
+        weight: u64,
+    }
+
+    let file = r#"
+        [[groups]]
+        name = "member"
+        weight = 1
+
+        [[groups]]
+        name = "lead"
+        weight = 3
+    "#;
+    let mut loader = ConfigLoader::from_sources(Some(file), Vec::new(), Vec::new());
+    let groups: Vec<Group> = loader.section("groups").unwrap();
+    let groups: Vec<(&str, u64)> = groups.iter().map(|group| (group.name.as_str(), group.weight)).collect();
+    assert_eq!(groups, vec![("member", 1), ("lead", 3)]);
+    assert!(loader.section::<Vec<Group>>("missing").is_none());
+    assert!(loader.finish().is_ok());
+}
+
+
+#[test]
+fn every_problem_is_reported_at_once()
+{
+    let mut loader = ConfigLoader::from_sources(
+        Some("poll_interval_ms = \"soon\"\n[groups]\nweight = 1\n"),
+        env(&[("DRAIN_TIMEOUT_SECS", "-1")]),
+        args(&["stray", "--unknown-flag", "1", "--redis-addr"]),
+    );
+    loader.optional("poll_interval_ms", 100_u64);
+    loader.optional("drain_timeout_secs", 10_u64);
+    loader.required::<String>("bind");
+    loader.section::<Vec<String>>("groups");
+    loader.check(false, || "custom check failed".to_owned());
+    let problems = loader.finish().unwrap_err().problems;
+    let expected = [
+        "unexpected argument 'stray'",
+        "flag '--redis-addr' needs a value",
+        "poll_interval_ms (config file): invalid value 'soon'",
+        "drain_timeout_secs (environment): invalid value '-1'",
+        "bind must be set",
+        "groups (config file)",
+        "custom check failed",
+        "unknown flag '--unknown-flag'",
+    ];
+    assert_eq!(problems.len(), expected.len(), "{:?}", problems);
+    for expected in &expected
+    {
+        assert!(problems.iter().any(|problem| problem.starts_with(expected)), "{} not in {:?}", expected, problems);
+    }
+}
+
+
+#[test]
+fn a_file_that_does_not_parse_is_a_problem()
+{
+    let loader = ConfigLoader::from_sources(Some("[redis"), Vec::new(), Vec::new());
+    let problems = loader.finish().unwrap_err().problems;
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("could not parse config file"));
+}
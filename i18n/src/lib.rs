@@ -0,0 +1,107 @@
+//! Translation of the front-ends: picking a locale from what the browser asks for, CLDR plural categories for
+//! the languages they ship, and catalogues of messages with `{name}` placeholders.
+//!
+//! A catalogue maps keys to messages. A message that depends on a count has one key per plural category,
+//! `votes.one`, `votes.other` and so on; `other` is the one every language has and the one used when the
+//! category a count falls in is missing. Keys missing from a catalogue come from the default catalogue.
+
+mod locale;
+mod plural;
+
+pub use locale::{is_rtl, language, negotiate};
+pub use plural::{plural_category, PluralCategory};
+
+
+/// The messages of one locale, with the locale's BCP 47 tag, e.g. `en` or `pt-br`.
+pub struct Catalogue
+{
+    pub locale: &'static str,
+    pub messages: &'static [(&'static str, &'static str)],
+}
+
+
+impl Catalogue
+{
+    fn get(&self, key: &str) -> Option<&'static str>
+    {
+        self.messages.iter().find(|(k, _)| *k == key).map(|(_, message)| *message)
+    }
+}
+
+
+/// Messages of the locale negotiated from the requested ones, falling back to the default catalogue.
+pub struct Translator
+{
+    catalogue: &'static Catalogue,
+    default: &'static Catalogue,
+}
+
+
+fn fill(message: &str, args: &[(&str, &str)]) -> String
+{
+    args.iter().fold(message.to_owned(), |message, (name, value)| message.replace(&format!("{{{}}}", name), value))
+}
+
+
+impl Translator
+{
+    /// `requested` is in order of preference. The first catalogue is the default, used when none of the
+    /// requested locales is available.
+    pub fn new(requested: &[String], catalogues: &'static [Catalogue]) -> Translator
+    {
+        let locales: Vec<&str> = catalogues.iter().map(|catalogue| catalogue.locale).collect();
+        let locale = negotiate(requested, &locales, catalogues[0].locale);
+        let catalogue = catalogues.iter().find(|catalogue| catalogue.locale == locale).unwrap_or(&catalogues[0]);
+        Translator { catalogue, default: &catalogues[0] }
+    }
+
+
+    pub fn locale(&self) -> &'static str
+    {
+        self.catalogue.locale
+    }
+
+
+    /// `rtl` or `ltr`, for the `dir` attribute.
+    pub fn direction(&self) -> &'static str
+    {
+        if is_rtl(self.catalogue.locale) { "rtl" } else { "ltr" }
+    }
+
+
+    fn lookup(&self, key: &str) -> Option<&'static str>
+    {
+        self.catalogue.get(key).or_else(|| self.default.get(key))
+    }
+
+
+    /// The message, or the key itself when no catalogue has it, so a missing message shows up on the page.
+    pub fn text(&self, key: &str) -> String
+    {
+        self.lookup(key).unwrap_or(key).to_owned()
+    }
+
+
+    /// The message with every `{name}` replaced by its value.
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String
+    {
+        fill(&self.text(key), args)
+    }
+
+
+    /// The form of `key` for `count` in the translator's language, with `{n}` replaced by the count and
+    /// every other `{name}` by its value.
+    pub fn plural(&self, key: &str, count: u64, args: &[(&str, &str)]) -> String
+    {
+        let category = plural_category(self.catalogue.locale, count);
+        let with_category = |catalogue: &Catalogue|
+            {
+                catalogue.get(&format!("{}.{}", key, category.name()))
+                    .or_else(|| catalogue.get(&format!("{}.other", key)))
+            };
+        let message = with_category(self.catalogue)
+            .or_else(|| with_category(self.default))
+            .unwrap_or(key);
+        fill(&fill(message, &[("n", &count.to_string())]), args)
+    }
+}
@@ -0,0 +1,37 @@
+/// The language subtag of a BCP 47 tag, lowercased: `en` for `en-US`, `pt` for `pt_BR`.
+pub fn language(tag: &str) -> String
+{
+    tag.split(&['-', '_'][..]).next().unwrap_or("").to_lowercase()
+}
+
+
+/// Whether the language is written right to left.
+pub fn is_rtl(tag: &str) -> bool
+{
+    matches!(language(tag).as_str(), "ar" | "he" | "fa" | "ur" | "yi")
+}
+
+
+fn normalize(tag: &str) -> String
+{
+    tag.trim().replace('_', "-").to_lowercase()
+}
+
+
+/// The first of `requested`, in order of preference, that is `supported`: exactly, or failing that by its
+/// language alone, so `de-AT` gets `de` and `pt` gets `pt-br`. Tags compare case-insensitively.
+pub fn negotiate<'a>(requested: &[String], supported: &[&'a str], default: &'a str) -> &'a str
+{
+    for tag in requested.iter().map(|tag| normalize(tag)).filter(|tag| !tag.is_empty())
+    {
+        if let Some(locale) = supported.iter().find(|locale| normalize(locale) == tag)
+        {
+            return locale;
+        }
+        if let Some(locale) = supported.iter().find(|locale| language(locale) == language(&tag))
+        {
+            return locale;
+        }
+    }
+    default
+}
@@ -0,0 +1,78 @@
+use crate::language;
+
+
+/// The CLDR plural categories. Which of them a language uses, and for which counts, is up to the language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory
+{
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+
+impl PluralCategory
+{
+    /// As used in message keys, e.g. `votes.few`.
+    pub fn name(self) -> &'static str
+    {
+        match self
+        {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+
+/// The category `count` falls in for the language of `locale`, by the CLDR rules for whole numbers. Languages
+/// without rules here get the English ones.
+pub fn plural_category(locale: &str, count: u64) -> PluralCategory
+{
+    let (last_digit, last_two_digits) = (count % 10, count % 100);
+    match language(locale).as_str()
+    {
+        "ja" | "ko" | "zh" | "th" | "vi" | "id" => PluralCategory::Other,
+        "fr" | "pt" if count <= 1 => PluralCategory::One,
+        "fr" | "pt" => PluralCategory::Other,
+        "ru" | "uk" | "be" => match (last_digit, last_two_digits)
+            {
+                (1, 11) => PluralCategory::Many,
+                (1, _) => PluralCategory::One,
+                (2..=4, 12..=14) => PluralCategory::Many,
+                (2..=4, _) => PluralCategory::Few,
+                _ => PluralCategory::Many,
+            },
+        "pl" => match (count, last_digit, last_two_digits)
+            {
+                (1, _, _) => PluralCategory::One,
+                (_, 2..=4, 12..=14) => PluralCategory::Many,
+                (_, 2..=4, _) => PluralCategory::Few,
+                _ => PluralCategory::Many,
+            },
+        "ar" => match (count, last_two_digits)
+            {
+                (0, _) => PluralCategory::Zero,
+                (1, _) => PluralCategory::One,
+                (2, _) => PluralCategory::Two,
+                (_, 3..=10) => PluralCategory::Few,
+                (_, 11..=99) => PluralCategory::Many,
+                _ => PluralCategory::Other,
+            },
+        "he" => match count
+            {
+                1 => PluralCategory::One,
+                2 => PluralCategory::Two,
+                _ => PluralCategory::Other,
+            },
+        _ if count == 1 => PluralCategory::One,
+        _ => PluralCategory::Other,
+    }
+}
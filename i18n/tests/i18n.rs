@@ -0,0 +1,126 @@
+use i18n::{is_rtl, negotiate, plural_category, Catalogue, PluralCategory, Translator};
+
+
+static CATALOGUES: [Catalogue; 3] = [
+    Catalogue
+    {
+        locale: "en",
+        messages: &[
+            ("title", "Results"),
+            ("greeting", "Hello {name}"),
+            ("votes.zero", "No votes yet"),
+            ("votes.one", "1 vote"),
+            ("votes.other", "{n} votes"),
+        ],
+    },
+    Catalogue
+    {
+        locale: "de",
+        messages: &[("votes.one", "1 Stimme"), ("votes.other", "{n} Stimmen")],
+    },
+    Catalogue
+    {
+        locale: "ar",
+        messages: &[
+            ("title", "النتائج"),
+            ("votes.zero", "لا أصوات"),
+            ("votes.one", "صوت واحد"),
+            ("votes.two", "صوتان"),
+            ("votes.few", "{n} أصوات"),
+            ("votes.other", "{n} صوت"),
+        ],
+    },
+];
+
+
+fn translator(requested: &[&str]) -> Translator
+{
+    let requested: Vec<String> = requested.iter().map(|tag| tag.to_string()).collect();
+    Translator::new(&requested, &CATALOGUES)
+}
+
+
+#[test]
+fn english_has_one_and_other()
+{
+    let categories: Vec<PluralCategory> = (0..3).map(|n| plural_category("en-US", n)).collect();
+    assert_eq!(categories, vec![PluralCategory::Other, PluralCategory::One, PluralCategory::Other]);
+}
+
+
+#[test]
+fn arabic_has_all_six_categories()
+{
+    let categories: Vec<PluralCategory> = [0, 1, 2, 3, 10, 11, 99, 100, 102, 103].iter()
+        .map(|n| plural_category("ar", *n))
+        .collect();
+    assert_eq!(categories, vec![
+        PluralCategory::Zero, PluralCategory::One, PluralCategory::Two, PluralCategory::Few, PluralCategory::Few,
+        PluralCategory::Many, PluralCategory::Many, PluralCategory::Other, PluralCategory::Other,
+        PluralCategory::Few,
+    ]);
+}
+
+
+#[test]
+fn russian_follows_the_last_digits()
+{
+    let categories: Vec<PluralCategory> = [1, 2, 5, 11, 12, 21, 22, 25].iter()
+        .map(|n| plural_category("ru", *n))
+        .collect();
+    assert_eq!(categories, vec![
+        PluralCategory::One, PluralCategory::Few, PluralCategory::Many, PluralCategory::Many, PluralCategory::Many,
+        PluralCategory::One, PluralCategory::Few, PluralCategory::Many,
+    ]);
+}
+
+
+#[test]
+fn negotiation_prefers_exact_tags_then_languages()
+{
+    assert_eq!(negotiate(&["de-AT".to_owned(), "en".to_owned()], &["en", "de"], "en"), "de");
+    assert_eq!(negotiate(&["pt_BR".to_owned()], &["en", "pt", "pt-br"], "en"), "pt-br");
+    assert_eq!(negotiate(&["fr".to_owned(), "AR".to_owned()], &["en", "ar"], "en"), "ar");
+    assert_eq!(negotiate(&["fr".to_owned()], &["en", "de"], "en"), "en");
+    assert_eq!(negotiate(&[], &["en", "de"], "en"), "en");
+}
+
+
+#[test]
+fn plurals_fall_back_to_other_then_to_the_default_catalogue()
+{
+    let german = translator(&["de-DE"]);
+    assert_eq!(german.plural("votes", 1, &[]), "1 Stimme");
+    // German has no zero form, so 0 is "other".
+    assert_eq!(german.plural("votes", 0, &[]), "0 Stimmen");
+    assert_eq!(german.text("title"), "Results");
+
+    let english = translator(&["en"]);
+    // English has no zero category either, whatever the catalogue holds.
+    assert_eq!(english.plural("votes", 0, &[]), "0 votes");
+    assert_eq!(english.plural("votes", 7, &[]), "7 votes");
+}
+
+
+#[test]
+fn arabic_uses_its_own_forms_and_is_right_to_left()
+{
+    let arabic = translator(&["ar-EG"]);
+    assert_eq!(arabic.locale(), "ar");
+    assert_eq!(arabic.direction(), "rtl");
+    assert_eq!(arabic.plural("votes", 0, &[]), "لا أصوات");
+    assert_eq!(arabic.plural("votes", 2, &[]), "صوتان");
+    assert_eq!(arabic.plural("votes", 5, &[]), "5 أصوات");
+    assert_eq!(arabic.plural("votes", 100, &[]), "100 صوت");
+    assert_eq!(translator(&["en"]).direction(), "ltr");
+    assert!(is_rtl("he-IL") && !is_rtl("en"));
+}
+
+
+#[test]
+fn placeholders_and_missing_keys()
+{
+    let english = translator(&["en"]);
+    assert_eq!(english.format("greeting", &[("name", "Ada")]), "Hello Ada");
+    assert_eq!(english.text("missing"), "missing");
+}
@@ -0,0 +1,58 @@
+use common::audit::{Append, AppendStep, AuditEntry, AuditEvent, Inserted, DUPLICATE_KEY_CODE};
+
+
+/// The hash-chained audit log the worker also appends to. Entries are documents with their number as `_id`.
+pub struct AuditLog
+{
+    entries: mongodb::sync::Collection,
+}
+
+
+fn inserted(result: mongodb::error::Result<mongodb::results::InsertOneResult>) -> Inserted<mongodb::error::Error>
+{
+    match result
+    {
+        Ok(_) => Inserted::Stored,
+        Err(e) if matches!(e.kind.as_ref(),
+                mongodb::error::ErrorKind::WriteError(mongodb::error::WriteFailure::WriteError(e))
+                    if e.code == DUPLICATE_KEY_CODE) => Inserted::NumberTaken,
+        Err(e) => Inserted::Failed(e),
+    }
+}
+
+
+impl AuditLog
+{
+    pub fn new(entries: mongodb::sync::Collection) -> AuditLog
+    {
+        AuditLog { entries }
+    }
+
+
+    fn last(&self) -> mongodb::error::Result<Option<AuditEntry>>
+    {
+        let options = mongodb::options::FindOneOptions::builder().sort(mongodb::bson::doc! { "_id": -1 }).build();
+        let last = self.entries.find_one(None, options)?;
+        Ok(last.map(mongodb::bson::from_document).transpose()?)
+    }
+
+
+    /// Chains the event onto the latest entry; `common::audit::Append` decides when another writer got there
+    /// first.
+    pub fn append(&self, event: &AuditEvent) -> mongodb::error::Result<AuditEntry>
+    {
+        let append = Append::new(event);
+        loop
+        {
+            let entry = append.entry(self.last()?.as_ref());
+            // Strings and an i64 map directly to BSON, so this cannot fail.
+            let document = mongodb::bson::to_document(&entry).unwrap();
+            match append.inserted(entry, inserted(self.entries.insert_one(document, None)))
+            {
+                AppendStep::Done(entry) => return Ok(entry),
+                AppendStep::Retry => continue,
+                AppendStep::Failed(e) => return Err(e),
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use actix_web::{error::BlockingError, web, HttpResponse};
+use serde::Serialize;
+use common::bulletin::{MerkleTree, ProofStep};
+use common::connection::ConnectionStatus;
+
+
+/// The collections the worker publishes commitments to, and the ballots to tell whether one is counted.
+#[derive(Clone)]
+pub struct Board
+{
+    pub leaves: mongodb::sync::Collection,
+    pub roots: mongodb::sync::Collection,
+    pub ballots: mongodb::sync::Collection,
+}
+
+
+#[derive(Serialize)]
+struct ErrorResponse
+{
+    code: &'static str,
+    message: &'static str,
+}
+
+
+#[derive(Serialize)]
+struct Root
+{
+    poll_id: String,
+    size: u64,
+    root: String,
+}
+
+
+/// Everything a voter needs to check with `common::bulletin::verify` that their commitment is under `root`.
+#[derive(Serialize)]
+struct InclusionProof
+{
+    poll_id: String,
+    commitment: String,
+    index: u64,
+    size: u64,
+    root: String,
+    proof: Vec<ProofStep>,
+    /// Whether the ballot behind the commitment is the one counted for its voter. A ballot replaced by a
+    /// later vote, or refused under strict anonymity, stays on the board but is not counted.
+    counted: bool,
+}
+
+
+enum Lookup
+{
+    Found(InclusionProof),
+    UnknownPoll,
+    UnknownCommitment,
+    /// On the board but not yet under the published root.
+    Pending,
+    /// The published root does not match the commitments it claims to cover.
+    Inconsistent,
+}
+
+
+fn error(code: &'static str, message: &'static str) -> ErrorResponse
+{
+    ErrorResponse { code, message }
+}
+
+
+fn unavailable<E: std::fmt::Display>(mongodb_status: &ConnectionStatus, e: BlockingError<E>) -> HttpResponse
+{
+    mongodb_status.record_failure(&e);
+    HttpResponse::ServiceUnavailable().json(error("backend_unavailable", "The bulletin board is unavailable"))
+}
+
+
+fn find_root(board: &Board, poll_id: &str) -> mongodb::error::Result<Option<Root>>
+{
+    let root = board.roots.find_one(mongodb::bson::doc! { "_id": poll_id }, None)?;
+    Ok(root.map(|root| Root
+        {
+            poll_id: poll_id.to_owned(),
+            size: root.get_i64("size").unwrap_or(0).max(0) as u64,
+            root: root.get_str("root").unwrap_or_default().to_owned(),
+        }))
+}
+
+
+/// `GET /api/polls/{id}/root`: the latest root of the poll's bulletin board and how many commitments it covers.
+pub async fn root(
+        path: web::Path<String>, board: web::Data<Board>, mongodb_status: web::Data<ConnectionStatus>,
+    )
+    -> HttpResponse
+{
+    let poll_id = path.into_inner();
+    let board = board.get_ref().clone();
+    match web::block(move || find_root(&board, &poll_id)).await
+    {
+        Ok(Some(root)) =>
+            {
+                mongodb_status.record_success();
+                HttpResponse::Ok().json(root)
+            },
+        Ok(None) => HttpResponse::NotFound().json(error("unknown_poll", "No ballots were published for this poll")),
+        Err(e) => unavailable(&mongodb_status, e),
+    }
+}
+
+
+fn prove(board: &Board, poll_id: &str, commitment: &str) -> mongodb::error::Result<Lookup>
+{
+    let root = match find_root(board, poll_id)?
+    {
+        Some(root) => root,
+        None => return Ok(Lookup::UnknownPoll),
+    };
+    let leaf = board.leaves.find_one(mongodb::bson::doc! { "_id": commitment, "poll_id": poll_id }, None)?;
+    let index = match leaf.and_then(|leaf| leaf.get_i64("index").ok())
+    {
+        Some(index) => index.max(0) as u64,
+        None => return Ok(Lookup::UnknownCommitment),
+    };
+    if index >= root.size
+    {
+        return Ok(Lookup::Pending);
+    }
+
+    let filter = mongodb::bson::doc! { "poll_id": poll_id, "index": { "$lt": root.size as i64 } };
+    let options = mongodb::options::FindOptions::builder().sort(mongodb::bson::doc! { "index": 1 }).build();
+    let mut commitments = Vec::new();
+    for leaf in board.leaves.find(filter, options)?
+    {
+        if let Ok(commitment) = leaf?.get_str("_id")
+        {
+            commitments.push(commitment.to_owned());
+        }
+    }
+    let tree = MerkleTree::from_commitments(commitments.iter().map(String::as_str));
+    if tree.len() as u64 != root.size || tree.root() != root.root
+    {
+        tracing::error!(poll_id, size = root.size, leaves = tree.len(), "bulletin board does not match its published root");
+        return Ok(Lookup::Inconsistent);
+    }
+    let counted = board.ballots.find_one(mongodb::bson::doc! { "commitment": commitment }, None)?.is_some();
+    Ok(Lookup::Found(InclusionProof
+    {
+        poll_id: root.poll_id,
+        commitment: commitment.to_owned(),
+        index,
+        size: root.size,
+        root: root.root,
+        proof: tree.proof(index as usize).unwrap_or_default(),
+        counted,
+    }))
+}
+
+
+/// `GET /api/polls/{id}/proof/{commitment}`: an inclusion proof for a commitment from a vote receipt.
+pub async fn proof(
+        path: web::Path<(String, String)>, board: web::Data<Board>, mongodb_status: web::Data<ConnectionStatus>,
+    )
+    -> HttpResponse
+{
+    let (poll_id, commitment) = path.into_inner();
+    let board = board.get_ref().clone();
+    match web::block(move || prove(&board, &poll_id, &commitment)).await
+    {
+        Ok(lookup) =>
+            {
+                mongodb_status.record_success();
+                match lookup
+                {
+                    Lookup::Found(proof) => HttpResponse::Ok().json(proof),
+                    Lookup::UnknownPoll =>
+                        HttpResponse::NotFound().json(error("unknown_poll", "No ballots were published for this poll")),
+                    Lookup::UnknownCommitment =>
+                        HttpResponse::NotFound().json(error("unknown_commitment", "This commitment is not on the board")),
+                    Lookup::Pending =>
+                        HttpResponse::Accepted().json(error("pending", "This commitment is not under the published root yet")),
+                    Lookup::Inconsistent =>
+                        HttpResponse::InternalServerError()
+                            .json(error("inconsistent_board", "The bulletin board does not match its published root")),
+                }
+            },
+        Err(e) => unavailable(&mongodb_status, e),
+    }
+}
@@ -0,0 +1,52 @@
+use actix_web::{web, HttpResponse};
+use std::collections::BTreeMap;
+use serde::Serialize;
+use common::poll::{Poll, PollTranslation};
+
+
+#[derive(Serialize, Clone)]
+pub struct PollOption
+{
+    pub id: String,
+    pub label: String,
+}
+
+
+/// What the dashboard reads from `GET /config.json` before it connects.
+#[derive(Serialize, Clone)]
+pub struct ClientConfig
+{
+    /// `None` lets the dashboard derive the URL from the page address: `ws://` or `wss://`, same host, `/ws/`.
+    pub websocket_url: Option<String>,
+    pub poll_id: String,
+    pub title: String,
+    /// In the order the tally lists them.
+    pub options: Vec<PollOption>,
+    /// Titles and labels by locale, for the dashboard to pick from by the reader's language.
+    pub translations: BTreeMap<String, PollTranslation>,
+}
+
+
+impl ClientConfig
+{
+    pub fn new(websocket_url: Option<String>, poll: &Poll) -> ClientConfig
+    {
+        let options = poll.options.iter().zip(&poll.labels)
+            .map(|(id, label)| PollOption { id: id.clone(), label: label.clone() })
+            .collect();
+        ClientConfig
+        {
+            websocket_url,
+            poll_id: poll.id.clone(),
+            title: poll.title.clone(),
+            options,
+            translations: poll.translations.clone(),
+        }
+    }
+}
+
+
+pub async fn client_config(config: web::Data<ClientConfig>) -> HttpResponse
+{
+    HttpResponse::Ok().json(config.get_ref())
+}
@@ -0,0 +1,90 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+use common::config::{ConfigError, ConfigLoader};
+use common::poll::Poll;
+use common::telemetry::TelemetryConfig;
+
+
+pub struct Config
+{
+    pub bind: SocketAddr,
+    pub mongodb_addr: String,
+    pub mongodb_db_name: String,
+    pub mongodb_collection_name: String,
+    /// Where the worker publishes commitments; the roots are in the same name with `_roots` appended.
+    pub mongodb_bulletin_board_collection_name: String,
+    /// The audit log, where freezing the final tally is recorded.
+    pub mongodb_audit_collection_name: String,
+    /// Where the dashboard connects, for deployments where the websocket is not on the page's own host.
+    pub websocket_url: Option<String>,
+    /// Where the tally is sampled over time for the dashboard's history chart.
+    pub mongodb_history_collection_name: String,
+    /// Shortest time between two samples.
+    pub history_interval: Duration,
+    pub poll: Poll,
+    /// How long after `closes_at` the tally is frozen, so the worker can apply votes queued just before close.
+    pub poll_freeze_delay: Duration,
+    pub telemetry: TelemetryConfig,
+}
+
+
+impl Config
+{
+    pub fn load() -> Result<Config, ConfigError>
+    {
+        let mut loader = ConfigLoader::load();
+        let bind = loader.optional("bind", SocketAddr::from(([0, 0, 0, 0], 8080)));
+        let mongodb_addr: Option<String> = loader.required("mongodb_addr");
+        let mongodb_db_name: Option<String> = loader.required("mongodb_db_name");
+        let mongodb_collection_name: Option<String> = loader.required("mongodb_collection_name");
+        let mongodb_bulletin_board_collection_name: String =
+            loader.optional("mongodb_bulletin_board_collection_name", "bulletin_board".to_owned());
+        let mongodb_audit_collection_name: String =
+            loader.optional("mongodb_audit_collection_name", "audit_log".to_owned());
+        let mongodb_history_collection_name: String =
+            loader.optional("mongodb_history_collection_name", "tally_history".to_owned());
+        let history_interval_secs = loader.optional("history_interval_secs", 10);
+        let websocket_url: Option<String> = loader.get("websocket_url");
+        let poll = Poll::load(&mut loader);
+        let poll_freeze_delay_secs = loader.optional("poll_freeze_delay_secs", 5);
+        let telemetry = TelemetryConfig::load(&mut loader);
+
+        if let Some(mongodb_addr) = &mongodb_addr
+        {
+            loader.check(mongodb_addr.starts_with("mongodb://") || mongodb_addr.starts_with("mongodb+srv://"), ||
+                format!("mongodb_addr must be a mongodb:// url, got '{}'", mongodb_addr));
+        }
+        for (key, value) in &[("mongodb_db_name", &mongodb_db_name), ("mongodb_collection_name", &mongodb_collection_name)]
+        {
+            loader.check(!matches!(value, Some(value) if value.is_empty()), || format!("{} must not be empty", key));
+        }
+        if let Some(websocket_url) = &websocket_url
+        {
+            loader.check(websocket_url.starts_with("ws://") || websocket_url.starts_with("wss://"), ||
+                format!("websocket_url must be a ws:// or wss:// url, got '{}'", websocket_url));
+        }
+        loader.check(!mongodb_bulletin_board_collection_name.is_empty(), ||
+            "mongodb_bulletin_board_collection_name must not be empty".to_owned());
+        loader.check(!mongodb_audit_collection_name.is_empty(), ||
+            "mongodb_audit_collection_name must not be empty".to_owned());
+        loader.check(!mongodb_history_collection_name.is_empty(), ||
+            "mongodb_history_collection_name must not be empty".to_owned());
+        loader.finish()?;
+
+        Ok(Config
+        {
+            bind,
+            mongodb_addr: mongodb_addr.unwrap(),
+            mongodb_db_name: mongodb_db_name.unwrap(),
+            mongodb_collection_name: mongodb_collection_name.unwrap(),
+            mongodb_bulletin_board_collection_name,
+            mongodb_audit_collection_name,
+            websocket_url,
+            mongodb_history_collection_name,
+            history_interval: Duration::from_secs(history_interval_secs),
+            poll,
+            poll_freeze_delay: Duration::from_secs(poll_freeze_delay_secs),
+            telemetry,
+        })
+    }
+}
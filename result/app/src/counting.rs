@@ -0,0 +1,82 @@
+use common::ballot::Ballot;
+use tally::Weighted;
+
+use crate::models::{RankedSummary, Round, VoteStats};
+
+
+/// A ballot as stored by the worker, with the weight of the voter's group.
+pub struct StoredBallot
+{
+    pub ballot: Ballot,
+    pub weight: u64,
+}
+
+
+pub fn stats(options: &[String], quantities: &[u64], weighted: &[u64]) -> Vec<VoteStats>
+{
+    options.iter().zip(quantities).zip(weighted)
+        .map(|((vote, quantity), weighted)| VoteStats { vote: vote.clone(), quantity: *quantity, weighted: *weighted })
+        .collect()
+}
+
+
+/// Sum of the scores given to each option, as given and multiplied by the voters' weights.
+pub fn scores(options: &[String], ballots: &[StoredBallot]) -> Vec<VoteStats>
+{
+    let mut totals = vec![0; options.len()];
+    let mut weighted = vec![0; options.len()];
+    for stored in ballots
+    {
+        if let Ballot::Scores(scores) = &stored.ballot
+        {
+            for (index, option) in options.iter().enumerate()
+            {
+                let score = u64::from(scores.get(option).copied().unwrap_or(0));
+                totals[index] += score;
+                weighted[index] += score * stored.weight;
+            }
+        }
+    }
+    stats(options, &totals, &weighted)
+}
+
+
+/// Ballots as positions in the poll's option list, which is what the tally library works with.
+pub fn rankings(options: &[String], ballots: &[StoredBallot]) -> Vec<Weighted>
+{
+    ballots.iter()
+        .map(|stored| Weighted
+            {
+                ranking: stored.ballot.choices().into_iter()
+                    .filter_map(|choice| options.iter().position(|option| option == choice))
+                    .collect(),
+                weight: stored.weight,
+            })
+        .collect()
+}
+
+
+pub fn instant_runoff(options: &[String], rankings: &[Weighted]) -> Vec<Round>
+{
+    tally::instant_runoff(options.len(), rankings).rounds.into_iter()
+        .map(|round| Round
+            {
+                counts: stats(options, &round.ballots, &round.counts),
+                exhausted: round.exhausted,
+                eliminated: round.eliminated.map(|option| options[option].clone()),
+            })
+        .collect()
+}
+
+
+pub fn summary(options: &[String], rankings: &[Weighted]) -> RankedSummary
+{
+    let schulze = tally::schulze(options.len(), rankings);
+    let unweighted: Vec<Vec<usize>> = rankings.iter().map(|ranking| ranking.ranking.clone()).collect();
+    RankedSummary
+    {
+        condorcet_winner: tally::condorcet_winner(&schulze.pairwise).map(|option| options[option].clone()),
+        schulze: schulze.ranking.iter().map(|option| options[*option].clone()).collect(),
+        borda: stats(options, &tally::borda(options.len(), &unweighted), &tally::borda(options.len(), rankings)),
+    }
+}
@@ -0,0 +1,44 @@
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use common::connection::{ConnectionState, ConnectionStatus};
+
+
+#[derive(Serialize)]
+struct HealthReport
+{
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mongodb: Option<ConnectionState>,
+}
+
+
+pub async fn healthz() -> HttpResponse
+{
+    HttpResponse::Ok().json(HealthReport { status: "ok", mongodb: None })
+}
+
+
+pub async fn readyz(client: web::Data<mongodb::sync::Client>, mongodb_status: web::Data<ConnectionStatus>)
+    -> HttpResponse
+{
+    let client = client.get_ref().clone();
+    let ping = web::block(move ||
+        {
+            client.database("admin").run_command(mongodb::bson::doc! { "ping": 1 }, None)
+        })
+        .await;
+    match ping
+    {
+        Ok(_) =>
+            {
+                mongodb_status.record_success();
+                HttpResponse::Ok().json(HealthReport { status: "ready", mongodb: Some(mongodb_status.state()) })
+            },
+        Err(e) =>
+            {
+                mongodb_status.record_failure(&e);
+                HttpResponse::ServiceUnavailable()
+                    .json(HealthReport { status: "unavailable", mongodb: Some(mongodb_status.state()) })
+            },
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use common::connection::ConnectionStatus;
+
+use crate::models::{Sample, VoteStats};
+use crate::server::to_u64;
+
+
+/// Most samples `GET /api/polls/{id}/history` returns, the latest ones.
+const MAX_SAMPLES: i64 = 2000;
+
+
+/// The totals of the poll over time, sampled by the websocket server as votes come in.
+#[derive(Clone)]
+pub struct History
+{
+    pub samples: mongodb::sync::Collection,
+    pub poll_id: String,
+}
+
+
+#[derive(Serialize)]
+struct ErrorResponse
+{
+    code: &'static str,
+    message: &'static str,
+}
+
+
+fn now_millis() -> i64
+{
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_millis() as i64)
+}
+
+
+fn to_sample(document: &mongodb::bson::Document) -> Sample
+{
+    let totals = document.get_array("totals").map(|totals| totals.iter()
+            .filter_map(|stats| stats.as_document())
+            .map(|stats| VoteStats
+                {
+                    vote: stats.get_str("vote").unwrap_or_default().to_owned(),
+                    quantity: stats.get("quantity").map_or(0, to_u64),
+                    weighted: stats.get("weighted").map_or(0, to_u64),
+                })
+            .collect())
+        .unwrap_or_default();
+    Sample { at: document.get_i64("at").unwrap_or(0), totals }
+}
+
+
+impl History
+{
+    pub fn record(&self, totals: &[VoteStats]) -> mongodb::error::Result<Sample>
+    {
+        let sample = Sample { at: now_millis(), totals: totals.to_vec() };
+        let totals: Vec<mongodb::bson::Bson> = totals.iter()
+            .map(|stats| mongodb::bson::Bson::Document(mongodb::bson::doc!
+                {
+                    "vote": stats.vote.as_str(), "quantity": stats.quantity as i64, "weighted": stats.weighted as i64
+                }))
+            .collect();
+        let document = mongodb::bson::doc! { "poll_id": self.poll_id.as_str(), "at": sample.at, "totals": totals };
+        self.samples.insert_one(document, None)?;
+        Ok(sample)
+    }
+
+
+    /// Oldest first.
+    fn load(&self) -> mongodb::error::Result<Vec<Sample>>
+    {
+        let options = mongodb::options::FindOptions::builder()
+            .sort(mongodb::bson::doc! { "at": -1 })
+            .limit(MAX_SAMPLES)
+            .build();
+        let mut samples = Vec::new();
+        for document in self.samples.find(mongodb::bson::doc! { "poll_id": self.poll_id.as_str() }, options)?
+        {
+            samples.push(to_sample(&document?));
+        }
+        samples.reverse();
+        Ok(samples)
+    }
+}
+
+
+/// `GET /api/polls/{id}/history`: the poll's totals over time, oldest first, with `at` in unix milliseconds.
+pub async fn history(
+        path: web::Path<String>, history: web::Data<History>, mongodb_status: web::Data<ConnectionStatus>,
+    )
+    -> HttpResponse
+{
+    if path.into_inner() != history.poll_id
+    {
+        return HttpResponse::NotFound().json(ErrorResponse { code: "unknown_poll", message: "No such poll" });
+    }
+    let history = history.get_ref().clone();
+    match web::block(move || history.load()).await
+    {
+        Ok(samples) =>
+            {
+                mongodb_status.record_success();
+                HttpResponse::Ok().json(samples)
+            },
+        Err(e) =>
+            {
+                mongodb_status.record_failure(&e);
+                HttpResponse::ServiceUnavailable()
+                    .json(ErrorResponse { code: "backend_unavailable", message: "The tally history is unavailable" })
+            },
+    }
+}
@@ -1,31 +1,107 @@
-use actix_web::{HttpServer, App, web, middleware};
+use std::time::Instant;
+use actix_web::{HttpServer, App, web, middleware, dev::Service};
 use actix_files::Files;
 use actix::*;
+use common::connection::ConnectionStatus;
 
+mod audit;
+mod bulletin;
+mod client_config;
+mod config;
 mod server;
 mod session;
 mod models;
+mod results;
+mod counting;
+mod health;
+mod history;
+mod metrics;
+
+use audit::AuditLog;
 
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()>
 {
-    std::env::set_var("RUST_LOG", "actix_web=info");
-    env_logger::init();
+    let config = match config::Config::load()
+    {
+        Ok(config) => config,
+        Err(e) =>
+            {
+                eprintln!("{}", e);
+                std::process::exit(common::config::EXIT_INVALID_CONFIG);
+            },
+    };
+    let _telemetry = common::telemetry::init("result", &config.telemetry);
+    let bind = config.bind;
+    tracing::info!(%bind, "starting server");
 
-    let bind = "0.0.0.0:8080";
-    println!("Starting server at: {}", &bind);
+    let mongodb_status = web::Data::new(ConnectionStatus::new("mongodb"));
+    let client = loop
+    {
+        match mongodb::sync::Client::with_uri_str(&config.mongodb_addr)
+        {
+            Ok(client) => break client,
+            Err(e) => std::thread::sleep(mongodb_status.record_failure(&e)),
+        }
+    };
 
-    let server = server::WebsocketServer::default().start();
+    let database = client.database(&config.mongodb_db_name);
+    let collection = database.collection(&config.mongodb_collection_name);
+    let board_name = &config.mongodb_bulletin_board_collection_name;
+    let board = web::Data::new(bulletin::Board
+    {
+        leaves: database.collection(board_name),
+        roots: database.collection(&format!("{}_roots", board_name)),
+        ballots: collection.clone(),
+    });
+    let client_config = web::Data::new(client_config::ClientConfig::new(config.websocket_url.clone(), &config.poll));
+    let poll = web::Data::new(config.poll.clone());
+    let history = history::History
+    {
+        samples: database.collection(&config.mongodb_history_collection_name),
+        poll_id: config.poll.id.clone(),
+    };
+    let server = server::WebsocketServer::new(
+            collection, mongodb_status.clone().into_inner(), config.poll.clone(), config.poll_freeze_delay,
+            AuditLog::new(database.collection(&config.mongodb_audit_collection_name)),
+            history.clone(), config.history_interval,
+        )
+        .start();
     HttpServer::new(move ||
         {
             App::new()
                 .data(server.clone())
+                .data(client.clone())
+                .app_data(mongodb_status.clone())
+                .app_data(board.clone())
+                .app_data(client_config.clone())
+                .app_data(poll.clone())
+                .data(history.clone())
                 .wrap(middleware::Logger::default())
+                .wrap_fn(|request, service|
+                    {
+                        let started = Instant::now();
+                        let response = service.call(request);
+                        async move
+                        {
+                            let response = response.await?;
+                            metrics::observe_request(&response, started);
+                            Ok(response)
+                        }
+                    })
                 .service(web::resource("/ws/").to(session::start_ws))
+                .route("/api/polls/{id}/root", web::get().to(bulletin::root))
+                .route("/api/polls/{id}/proof/{commitment}", web::get().to(bulletin::proof))
+                .route("/api/polls/{id}/history", web::get().to(history::history))
+                .route("/api/polls/{id}/results", web::get().to(results::results))
+                .route("/config.json", web::get().to(client_config::client_config))
+                .route("/healthz", web::get().to(health::healthz))
+                .route("/readyz", web::get().to(health::readyz))
+                .route("/metrics", web::get().to(metrics::metrics))
                 .service(Files::new("", "./web_layout").index_file("index.html"))
         })
-    .bind(&bind)?
+    .bind(bind)?
     .run()
     .await
 }
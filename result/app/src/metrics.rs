@@ -0,0 +1,70 @@
+use std::time::Instant;
+use actix_web::{dev::ServiceResponse, HttpResponse};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter_vec, register_int_gauge, Encoder, Histogram,
+    HistogramVec, IntCounterVec, IntGauge, TextEncoder,
+};
+
+
+lazy_static!
+{
+    static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "result_http_requests_total", "HTTP requests handled by the result app", &["method", "path", "status"]
+    ).unwrap();
+    static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "result_http_request_duration_seconds", "HTTP request latency of the result app", &["method", "path"]
+    ).unwrap();
+    static ref WEBSOCKET_SESSIONS: IntGauge = register_int_gauge!(
+        "result_websocket_sessions", "Connected websocket sessions"
+    ).unwrap();
+    static ref BROADCAST_DURATION: Histogram = register_histogram!(
+        "result_broadcast_duration_seconds", "Time spent collecting and broadcasting statistics"
+    ).unwrap();
+    static ref MONGODB_QUERY_DURATION: Histogram = register_histogram!(
+        "result_mongodb_query_duration_seconds", "Latency of statistics queries to mongodb"
+    ).unwrap();
+}
+
+
+pub fn observe_request<B>(response: &ServiceResponse<B>, started: Instant)
+{
+    let request = response.request();
+    let method = request.method().as_str();
+    let path = request.match_pattern().unwrap_or_else(|| "static".to_owned());
+    HTTP_REQUESTS
+        .with_label_values(&[method, &path, response.status().as_str()])
+        .inc();
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[method, &path])
+        .observe(started.elapsed().as_secs_f64());
+}
+
+
+pub fn observe_sessions(sessions: usize)
+{
+    WEBSOCKET_SESSIONS.set(sessions as i64);
+}
+
+
+pub fn observe_broadcast(started: Instant)
+{
+    BROADCAST_DURATION.observe(started.elapsed().as_secs_f64());
+}
+
+
+pub fn observe_mongodb_query(started: Instant)
+{
+    MONGODB_QUERY_DURATION.observe(started.elapsed().as_secs_f64());
+}
+
+
+pub async fn metrics() -> HttpResponse
+{
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    encoder.encode(&prometheus::gather(), &mut buffer).unwrap();
+    HttpResponse::Ok()
+        .content_type(encoder.format_type())
+        .body(buffer)
+}
@@ -5,7 +5,43 @@ use serde::Serialize;
 pub struct VoteStats
 {
     pub vote: String,
+    /// Number of ballots, or points for score and Borda tallies.
     pub quantity: u64,
+    /// The same with every ballot multiplied by its voter's weight.
+    pub weighted: u64,
+}
+
+
+/// One instant-runoff round: first preferences among the options still running, and who was eliminated.
+#[derive(Debug, Serialize, Clone)]
+pub struct Round
+{
+    pub counts: Vec<VoteStats>,
+    /// Weight of the ballots with no option left running.
+    pub exhausted: u64,
+    pub eliminated: Option<String>,
+}
+
+
+/// How the Condorcet methods and the Borda count see a ranked poll, next to the instant-runoff rounds.
+/// The Condorcet methods use the weighted ballots.
+#[derive(Debug, Serialize, Clone)]
+pub struct RankedSummary
+{
+    pub condorcet_winner: Option<String>,
+    /// Every option, best first, by the Schulze method.
+    pub schulze: Vec<String>,
+    pub borda: Vec<VoteStats>,
+}
+
+
+/// The totals at one point in time, for the votes-over-time chart.
+#[derive(Debug, Serialize, Clone)]
+pub struct Sample
+{
+    /// Unix time in milliseconds.
+    pub at: i64,
+    pub totals: Vec<VoteStats>,
 }
 
 
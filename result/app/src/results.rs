@@ -0,0 +1,95 @@
+use actix::Addr;
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use common::poll::Poll;
+
+use crate::server::{GetTotals, WebsocketServer};
+
+
+#[derive(Serialize)]
+struct ErrorResponse
+{
+    code: &'static str,
+    message: &'static str,
+}
+
+
+#[derive(Serialize)]
+struct OptionResult<'a>
+{
+    id: &'a str,
+    label: &'a str,
+    /// Ballots, or points for score and Borda tallies.
+    votes: u64,
+    weighted: u64,
+    /// Share of the weighted totals; the shares of all options add up to 100 unless there are no votes.
+    percent: u64,
+    leader: bool,
+}
+
+
+#[derive(Serialize)]
+struct Results<'a>
+{
+    poll_id: &'a str,
+    method: String,
+    /// Set once the totals are the frozen final tally.
+    closed: bool,
+    total_votes: u64,
+    total_weighted: u64,
+    options: Vec<OptionResult<'a>>,
+    /// The options with the highest weighted total; more than one is a tie, none means no votes yet.
+    leaders: Vec<&'a str>,
+    tie: bool,
+}
+
+
+/// `GET /api/polls/{id}/results`: the totals the dashboard shows, with the same rounding and leaders.
+pub async fn results(path: web::Path<String>, server: web::Data<Addr<WebsocketServer>>, poll: web::Data<Poll>)
+    -> HttpResponse
+{
+    if path.into_inner() != poll.id
+    {
+        return HttpResponse::NotFound().json(ErrorResponse { code: "unknown_poll", message: "No such poll" });
+    }
+    let totals = match server.send(GetTotals).await
+    {
+        Ok(Some(totals)) => totals,
+        _ =>
+            {
+                return HttpResponse::ServiceUnavailable()
+                    .json(ErrorResponse { code: "tally_unavailable", message: "The poll has not been tallied yet" });
+            },
+    };
+
+    let counts: Vec<(u64, u64)> = poll.options.iter()
+        .map(|option| totals.statistics.iter()
+            .filter(|stats| &stats.vote == option)
+            .fold((0, 0), |(votes, weighted), stats| (votes + stats.quantity, weighted + stats.weighted)))
+        .collect();
+    let weighted: Vec<u64> = counts.iter().map(|(_, weighted)| *weighted).collect();
+    let percentages = tally::percentages(&weighted);
+    let leaders = tally::leaders(&weighted);
+    let options = poll.options.iter().zip(&poll.labels).enumerate()
+        .map(|(index, (id, label))| OptionResult
+            {
+                id,
+                label,
+                votes: counts[index].0,
+                weighted: counts[index].1,
+                percent: percentages[index],
+                leader: leaders.contains(&index),
+            })
+        .collect();
+    HttpResponse::Ok().json(Results
+    {
+        poll_id: &poll.id,
+        method: poll.method.to_string(),
+        closed: totals.closed,
+        total_votes: counts.iter().map(|(votes, _)| votes).sum(),
+        total_weighted: weighted.iter().sum(),
+        options,
+        leaders: leaders.iter().map(|index| poll.options[*index].as_str()).collect(),
+        tie: leaders.len() > 1,
+    })
+}
@@ -1,16 +1,35 @@
 use actix::prelude::*;
 use rand::{self, rngs::ThreadRng, Rng};
 use std::collections::HashMap;
-use actix_web::web;
+use std::sync::Arc;
 use mongodb;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use serde_json;
+use common::audit::AuditEvent;
+use common::connection::ConnectionStatus;
+use common::ballot::Ballot;
+use common::poll::{Poll, VotingMethod};
 
+use crate::audit::AuditLog;
+use crate::history::History;
+use crate::metrics;
+use crate::counting::{self, StoredBallot};
 use crate::models::{VoteStats, WsResponse};
 
 
 const STATS_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
-const VOTE_VARIANTS: [&'static str; 2] = ["a", "b"];
+
+
+pub fn to_u64(value: &mongodb::bson::Bson) -> u64
+{
+    match value
+    {
+        mongodb::bson::Bson::Int32(value) => (*value).max(0) as u64,
+        mongodb::bson::Bson::Int64(value) => (*value).max(0) as u64,
+        mongodb::bson::Bson::Double(value) => value.max(0.0) as u64,
+        _ => 0,
+    }
+}
 
 
 #[derive(Message)]
@@ -34,6 +53,28 @@ pub struct Disconnect
 }
 
 
+/// Asks for the latest tally again, sent by clients whenever their socket (re)opens.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Subscribe
+{
+    pub id: usize,
+}
+
+
+/// The latest totals and whether they are the final tally; `None` until the first tally.
+#[derive(Message)]
+#[rtype(result = "Option<Totals>")]
+pub struct GetTotals;
+
+
+pub struct Totals
+{
+    pub statistics: Vec<VoteStats>,
+    pub closed: bool,
+}
+
+
 #[derive(Clone)]
 struct SessionData
 {
@@ -45,54 +86,241 @@ pub struct WebsocketServer
 {
     sessions: HashMap<usize, SessionData>,
     rng: ThreadRng,
-    client: web::Data<mongodb::sync::Client>,
+    collection: mongodb::sync::Collection,
+    mongodb_status: Arc<ConnectionStatus>,
+    retry_at: Option<Instant>,
+    poll: Poll,
+    freeze_delay: Duration,
+    /// The last messages broadcast, ending with `poll_closed`, kept once the poll is over so late joiners
+    /// get the same final tally.
+    final_tally: Option<Vec<String>>,
+    /// The last messages broadcast, for clients that subscribe between two broadcasts.
+    latest: Vec<String>,
+    /// The totals in the last broadcast.
+    latest_totals: Option<Vec<VoteStats>>,
+    audit_log: AuditLog,
+    /// The freeze, until it is in the audit log. Broadcasting the final tally does not wait for it.
+    pending_audit: Option<AuditEvent>,
+    history: History,
+    history_interval: Duration,
+    /// When the last sample was recorded, and the ballot counts and weights it recorded.
+    last_sample: Option<(Instant, Vec<(u64, u64)>)>,
 }
 
 
-impl Default for WebsocketServer
+impl WebsocketServer
 {
-    fn default() -> WebsocketServer
+    pub fn new(
+            collection: mongodb::sync::Collection, mongodb_status: Arc<ConnectionStatus>,
+            poll: Poll, freeze_delay: Duration, audit_log: AuditLog, history: History, history_interval: Duration,
+        )
+        -> WebsocketServer
     {
-        dotenv::dotenv().ok();
-        let mongodb_addr = std::env::var("MONGODB_ADDR").expect("MONGODB_ADDR must be set");
-        let client = web::Data::new(mongodb::sync::Client::with_uri_str(&mongodb_addr).unwrap());
-
         WebsocketServer
         {
             sessions: HashMap::new(),
             rng: rand::thread_rng(),
-            client,
+            collection,
+            mongodb_status,
+            retry_at: None,
+            poll,
+            freeze_delay,
+            final_tally: None,
+            latest: Vec::new(),
+            latest_totals: None,
+            audit_log,
+            pending_audit: None,
+            history,
+            history_interval,
+            last_sample: None,
+        }
+    }
+
+
+    fn record_pending_audit(&mut self)
+    {
+        if let Some(event) = &self.pending_audit
+        {
+            match self.audit_log.append(event)
+            {
+                Ok(_) => self.pending_audit = None,
+                Err(e) => tracing::warn!(error = %e, "could not append to the audit log, will retry"),
+            }
+        }
+    }
+
+
+    /// Records the totals in the history when they changed, at most once per `history_interval` unless `force`d,
+    /// and sends the sample to the dashboards for their votes-over-time chart. A failed sample is retried on
+    /// the next tick.
+    fn sample_history(&mut self, statistics: &[VoteStats], force: bool)
+    {
+        let counts: Vec<(u64, u64)> = statistics.iter().map(|stats| (stats.quantity, stats.weighted)).collect();
+        if let Some((at, last)) = &self.last_sample
+        {
+            if *last == counts || (!force && at.elapsed() < self.history_interval)
+            {
+                return;
+            }
+        }
+        match self.history.record(statistics)
+        {
+            Ok(sample) =>
+                {
+                    self.last_sample = Some((Instant::now(), counts));
+                    let response = WsResponse
+                    {
+                        action: "received_sample".to_owned(),
+                        data: serde_json::to_string(&sample).unwrap(),
+                    };
+                    let message = serde_json::to_string(&response).unwrap();
+                    for session_data in self.sessions.values()
+                    {
+                        let _ = session_data.recipient.do_send(Message(message.clone()));
+                    }
+                },
+            Err(e) => tracing::warn!(error = %e, "could not record a tally sample, will retry"),
+        }
+    }
+
+
+    /// Ballots and weights per option, counted by mongodb. Unwinding leaves a single choice ballot as it is
+    /// and splits an approval ballot into the options it approves.
+    fn count_options(&self) -> mongodb::error::Result<Vec<VoteStats>>
+    {
+        let pipeline = vec![
+            mongodb::bson::doc! { "$unwind": "$vote" },
+            mongodb::bson::doc!
+            {
+                "$group":
+                {
+                    "_id": "$vote",
+                    "quantity": { "$sum": 1 },
+                    "weighted": { "$sum": { "$ifNull": ["$weight", 1] } }
+                }
+            },
+        ];
+        let query_started = Instant::now();
+        let mut totals = HashMap::new();
+        for document in self.collection.aggregate(pipeline, None)?
+        {
+            let document = document?;
+            if let Ok(option) = document.get_str("_id")
+            {
+                let quantity = document.get("quantity").map_or(0, to_u64);
+                let weighted = document.get("weighted").map_or(0, to_u64);
+                totals.insert(option.to_owned(), (quantity, weighted));
+            }
+        }
+        metrics::observe_mongodb_query(query_started);
+        let (quantities, weighted): (Vec<u64>, Vec<u64>) = self.poll.options.iter()
+            .map(|option| totals.get(option).copied().unwrap_or((0, 0)))
+            .unzip();
+        Ok(counting::stats(&self.poll.options, &quantities, &weighted))
+    }
+
+
+    fn load_ballots(&self) -> mongodb::error::Result<Vec<StoredBallot>>
+    {
+        let query_started = Instant::now();
+        let projection = mongodb::bson::doc! { "vote": 1, "weight": 1 };
+        let options = mongodb::options::FindOptions::builder().projection(projection).build();
+        let mut ballots = Vec::new();
+        for document in self.collection.find(None, options)?
+        {
+            let mut document = document?;
+            let weight = document.get("weight").map_or(1, to_u64);
+            if let Some(vote) = document.remove("vote")
+            {
+                if let Ok(ballot) = mongodb::bson::from_bson::<Ballot>(vote)
+                {
+                    ballots.push(StoredBallot { ballot, weight });
+                }
+            }
+        }
+        metrics::observe_mongodb_query(query_started);
+        Ok(ballots)
+    }
+
+
+    /// Current totals, plus the messages that go out before them: for ranked polls the instant-runoff
+    /// rounds and the Condorcet and Borda view of the same ballots.
+    fn tally(&self) -> mongodb::error::Result<(Vec<VoteStats>, Vec<WsResponse>)>
+    {
+        let options = &self.poll.options;
+        match self.poll.method
+        {
+            VotingMethod::Single | VotingMethod::Approval => Ok((self.count_options()?, Vec::new())),
+            VotingMethod::Score => Ok((counting::scores(options, &self.load_ballots()?), Vec::new())),
+            VotingMethod::Ranked =>
+                {
+                    let rankings = counting::rankings(options, &self.load_ballots()?);
+                    let rounds = counting::instant_runoff(options, &rankings);
+                    let totals = rounds.last().map(|round| round.counts.clone()).unwrap_or_default();
+                    let breakdown = vec![
+                        WsResponse { action: "received_rounds".to_owned(), data: serde_json::to_string(&rounds).unwrap() },
+                        WsResponse
+                        {
+                            action: "received_ranking".to_owned(),
+                            data: serde_json::to_string(&counting::summary(options, &rankings)).unwrap(),
+                        },
+                    ];
+                    Ok((totals, breakdown))
+                },
         }
     }
-}
 
 
-impl WebsocketServer
-{
     fn get_statistics(&self, ctx: &mut Context<Self>)
     {
         ctx.run_interval(STATS_UPDATE_INTERVAL, |act, _ctx|
             {
-                dotenv::dotenv().ok();
-                let mongodb_db_name = std::env::var("MONGODB_DB_NAME").expect("MONGODB_DB_NAME must be set");
-                let mongodb_collection_name = std::env::var("MONGODB_COLLECTION_NAME").expect("MONGODB_COLLECTION_NAME must be set");
-                let collection = act.client.database(&mongodb_db_name).collection(&mongodb_collection_name);
-                let mut statistics = Vec::new();
-                for vote_variant in VOTE_VARIANTS.iter()
+                act.record_pending_audit();
+                if act.final_tally.is_some() || matches!(act.retry_at, Some(retry_at) if Instant::now() < retry_at)
                 {
-                    let filter = mongodb::bson::doc! { "vote": vote_variant };
-                    if let Ok(quantity) = collection.count_documents(filter, None)
+                    return;
+                }
+                // Checked before counting so the frozen tally includes everything stored up to now.
+                let is_closed = act.poll.window.closed_for(act.freeze_delay);
+                let started = Instant::now();
+                let (statistics, mut responses) = match act.tally()
+                {
+                    Ok(tally) => tally,
+                    Err(e) =>
+                        {
+                            let delay = act.mongodb_status.record_failure(&e);
+                            act.retry_at = Some(Instant::now() + delay);
+                            return;
+                        },
+                };
+                act.mongodb_status.record_success();
+                act.retry_at = None;
+
+                let action = if is_closed { "poll_closed" } else { "received_statistics" };
+                responses.push(WsResponse { action: action.to_owned(), data: serde_json::to_string(&statistics).unwrap() });
+                let messages: Vec<String> = responses.iter().map(|response| serde_json::to_string(response).unwrap()).collect();
+                for (_id, session_data) in act.sessions.iter()
+                {
+                    for m in &messages
                     {
-                        let vote_stats = VoteStats { vote: vote_variant.to_string(), quantity: quantity as u64 };
-                        statistics.push(vote_stats);
+                        let _ = session_data.recipient.do_send(Message(m.clone()));
                     }
                 }
-                for (_id, session_data) in act.sessions.iter()
+                act.latest = messages.clone();
+                act.latest_totals = Some(statistics.clone());
+                act.sample_history(&statistics, is_closed);
+                if is_closed
                 {
-                    let response = WsResponse { action: "received_statistics".to_owned(), data: serde_json::to_string(&statistics).unwrap() };
-                    let m = serde_json::to_string(&response).unwrap();
-                    let _ = session_data.recipient.do_send(Message(m));
+                    tracing::info!(method = %act.poll.method, tally = %serde_json::to_string(&statistics).unwrap(),
+                        "poll closed, final tally frozen");
+                    act.final_tally = Some(messages);
+                    let details = serde_json::json!({
+                        "poll_id": act.poll.id, "method": act.poll.method.to_string(), "totals": statistics,
+                    });
+                    act.pending_audit = Some(AuditEvent::new("system:result", "tally_frozen", details));
+                    act.record_pending_audit();
                 }
+                metrics::observe_broadcast(started);
             });
     }
 }
@@ -115,13 +343,17 @@ impl Handler<Connect> for WebsocketServer
 
     fn handle(&mut self, msg: Connect, _: &mut Context<Self>) -> Self::Result
     {
-        println!("Someone connected");
-
         let id = self.rng.gen::<usize>();
+        tracing::info!(session = id, "websocket session connected");
+        for m in self.final_tally.iter().flatten()
+        {
+            let _ = msg.addr.do_send(Message(m.clone()));
+        }
         self.sessions.insert(
             id,
             SessionData { recipient: msg.addr }
         );
+        metrics::observe_sessions(self.sessions.len());
         id
     }
 }
@@ -133,7 +365,39 @@ impl Handler<Disconnect> for WebsocketServer
 
     fn handle(&mut self, msg: Disconnect, _: &mut Context<Self>)
     {
-        println!("Someone disconnected");
         self.sessions.remove(&msg.id);
+        tracing::info!(session = msg.id, "websocket session disconnected");
+        metrics::observe_sessions(self.sessions.len());
+    }
+}
+
+
+impl Handler<Subscribe> for WebsocketServer
+{
+    type Result = ();
+
+    fn handle(&mut self, msg: Subscribe, _: &mut Context<Self>)
+    {
+        let session = match self.sessions.get(&msg.id)
+        {
+            Some(session) => session,
+            None => return,
+        };
+        for m in &self.latest
+        {
+            let _ = session.recipient.do_send(Message(m.clone()));
+        }
+    }
+}
+
+
+impl Handler<GetTotals> for WebsocketServer
+{
+    type Result = Option<Totals>;
+
+    fn handle(&mut self, _: GetTotals, _: &mut Context<Self>) -> Self::Result
+    {
+        let statistics = self.latest_totals.clone()?;
+        Some(Totals { statistics, closed: self.final_tally.is_some() })
     }
 }
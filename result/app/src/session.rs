@@ -3,6 +3,7 @@ use std::time::{Duration, Instant};
 use actix::*;
 use actix_web::{web, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
+use serde::Deserialize;
 
 
 use crate::server;
@@ -31,6 +32,14 @@ pub async fn start_ws(
 }
 
 
+/// A message from the dashboard. `{"action": "subscribe"}` asks for the latest tally straight away.
+#[derive(Deserialize)]
+struct ClientRequest
+{
+    action: String,
+}
+
+
 struct WsChatSession
 {
     id: usize,
@@ -99,7 +108,7 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsChatSession
             Ok(msg) => msg,
         };
 
-        println!("WEBSOCKET MESSAGE: {:?}", msg);
+        tracing::trace!(session = self.id, message = ?msg, "websocket message");
         match msg
         {
             ws::Message::Ping(msg) =>
@@ -111,8 +120,12 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsChatSession
                 {
                     self.hb = Instant::now();
                 },
-            ws::Message::Text(_) => println!("Stats was sent"),
-            ws::Message::Binary(_) => println!("Unexpected binary"),
+            ws::Message::Text(text) => match serde_json::from_str::<ClientRequest>(&text)
+                {
+                    Ok(request) if request.action == "subscribe" => self.addr.do_send(server::Subscribe { id: self.id }),
+                    _ => tracing::debug!(session = self.id, "unexpected text message"),
+                },
+            ws::Message::Binary(_) => tracing::debug!(session = self.id, "unexpected binary message"),
             ws::Message::Close(reason) =>
                 {
                     ctx.close(reason);
@@ -136,7 +149,7 @@ impl WsChatSession
             {
                 if Instant::now().duration_since(act.hb) > CLIENT_TIMEOUT
                 {
-                    println!("Websocket Client heartbeat failed, disconnecting!");
+                    tracing::info!(session = act.id, "websocket heartbeat failed, disconnecting");
                     act.addr.do_send(server::Disconnect { id: act.id });
                     ctx.stop();
                     return;
@@ -0,0 +1,62 @@
+/// How long the charts take to move to new totals, in milliseconds.
+pub const TRANSITION_MILLIS: f64 = 400.0;
+
+
+/// Values easing from what the charts showed towards the latest totals. Times are in milliseconds.
+#[derive(Debug, Default)]
+pub struct Transition
+{
+    from: Vec<f64>,
+    to: Vec<f64>,
+    started_at: f64,
+}
+
+
+/// Cubic ease-in-out of `progress`, which runs from 0 to 1.
+fn ease(progress: f64) -> f64
+{
+    if progress < 0.5
+    {
+        4.0 * progress * progress * progress
+    }
+    else
+    {
+        1.0 - (2.0 - 2.0 * progress).powi(3) / 2.0
+    }
+}
+
+
+impl Transition
+{
+    /// What to draw at `now`. Options that were not shown before grow from zero.
+    pub fn values(&self, now: f64) -> Vec<f64>
+    {
+        if !self.is_running(now)
+        {
+            return self.to.clone();
+        }
+        let progress = ease(((now - self.started_at) / TRANSITION_MILLIS).max(0.0));
+        self.to.iter().enumerate()
+            .map(|(index, to)|
+                {
+                    let from = self.from.get(index).copied().unwrap_or(0.0);
+                    from + (to - from) * progress
+                })
+            .collect()
+    }
+
+
+    pub fn is_running(&self, now: f64) -> bool
+    {
+        now - self.started_at < TRANSITION_MILLIS
+    }
+
+
+    /// Starts from the values drawn at `now`, so totals arriving mid-transition do not make the charts jump.
+    pub fn retarget(&mut self, to: Vec<f64>, now: f64)
+    {
+        self.from = self.values(now);
+        self.to = to;
+        self.started_at = now;
+    }
+}
@@ -0,0 +1,241 @@
+use std::f64::consts::PI;
+use yew::prelude::*;
+use i18n::Translator;
+
+
+/// Option colours, in poll order; the first two are the ones the page always had.
+pub const COLOURS: [&str; 8] = ["#2196f3", "#00cbca", "#ff9800", "#e91e63", "#8bc34a", "#9c27b0", "#795548", "#607d8b"];
+
+const BAR_WIDTH: f64 = 480.0;
+const BAR_HEIGHT: f64 = 240.0;
+/// Room for the values above the bars and the labels below them.
+const BAR_MARGIN: f64 = 24.0;
+const PIE_SIZE: f64 = 240.0;
+const PIE_RADIUS: f64 = 110.0;
+const LINE_WIDTH: f64 = 480.0;
+const LINE_HEIGHT: f64 = 240.0;
+/// Room for the axis labels.
+const LINE_MARGIN: f64 = 36.0;
+
+
+pub fn colour(index: usize) -> &'static str
+{
+    COLOURS[index % COLOURS.len()]
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartKind
+{
+    Bar,
+    Pie,
+    Line,
+}
+
+
+impl ChartKind
+{
+    pub const ALL: [ChartKind; 3] = [ChartKind::Bar, ChartKind::Pie, ChartKind::Line];
+
+
+    /// Key of the chart's name in the message catalogues.
+    pub fn name_key(self) -> &'static str
+    {
+        match self
+        {
+            ChartKind::Bar => "chart.bar",
+            ChartKind::Pie => "chart.pie",
+            ChartKind::Line => "chart.line",
+        }
+    }
+}
+
+
+/// One option as the bar and pie charts draw it.
+pub struct Slice<'a>
+{
+    pub label: &'a str,
+    /// Mid-transition, so it need not be a whole number.
+    pub value: f64,
+    /// Share of the latest weighted totals, not of `value`; the shares of all slices add up to 100.
+    pub percent: u64,
+    pub votes: u64,
+    pub weighted: u64,
+    /// Has the highest weighted total, alone or tied.
+    pub leader: bool,
+    pub colour: &'static str,
+}
+
+
+/// One option's totals over time, as (unix milliseconds, total) points in time order.
+pub struct Series
+{
+    pub colour: &'static str,
+    pub points: Vec<(f64, f64)>,
+}
+
+
+fn count(slice: &Slice, translator: &Translator) -> String
+{
+    let votes = translator.plural("votes", slice.votes, &[]);
+    if slice.weighted == slice.votes
+    {
+        format!(" ({})", votes)
+    }
+    else
+    {
+        let weighted = translator.plural("weighted", slice.weighted, &[]);
+        format!(" ({}{}{})", votes, translator.text("separator"), weighted)
+    }
+}
+
+
+pub fn legend(slices: &[Slice], translator: &Translator) -> Html
+{
+    html!
+    {
+        <ul class="legend">
+            {
+                for slices.iter().map(|slice| html!
+                    {
+                        <li class=if slice.leader { "leader" } else { "" }>
+                            <span class="swatch" style={ format!("background-color: {}", slice.colour) }></span>
+                            { format!("{} {}%", slice.label, slice.percent) }
+                            <span class="count">{ count(slice, translator) }</span>
+                        </li>
+                    })
+            }
+        </ul>
+    }
+}
+
+
+fn bar(index: usize, count: usize, max: f64, slice: &Slice) -> Html
+{
+    let slot = BAR_WIDTH / count as f64;
+    let height = (BAR_HEIGHT - 2.0 * BAR_MARGIN) * slice.value / max;
+    let x = slot * index as f64 + slot * 0.2;
+    let y = BAR_HEIGHT - BAR_MARGIN - height;
+    html!
+    {
+        <g class=if slice.leader { "leader" } else { "" }>
+            <rect x={ x } y={ y } width={ slot * 0.6 } height={ height } fill={ slice.colour }></rect>
+            <text x={ x + slot * 0.3 } y={ y - 6.0 } text-anchor="middle" class="value">{ slice.value.round() }</text>
+            <text x={ x + slot * 0.3 } y={ BAR_HEIGHT - 6.0 } text-anchor="middle" class="label">{ slice.label }</text>
+        </g>
+    }
+}
+
+
+pub fn bar_chart(slices: &[Slice]) -> Html
+{
+    let max = slices.iter().map(|slice| slice.value).fold(1.0, f64::max);
+    html!
+    {
+        <svg class="chart" viewBox={ format!("0 0 {} {}", BAR_WIDTH, BAR_HEIGHT) }>
+            <line class="axis" x1="0" y1={ BAR_HEIGHT - BAR_MARGIN } x2={ BAR_WIDTH } y2={ BAR_HEIGHT - BAR_MARGIN }></line>
+            { for slices.iter().enumerate().map(|(index, slice)| bar(index, slices.len(), max, slice)) }
+        </svg>
+    }
+}
+
+
+fn point_on_circle(angle: f64) -> (f64, f64)
+{
+    let centre = PIE_SIZE / 2.0;
+    (centre + PIE_RADIUS * angle.cos(), centre + PIE_RADIUS * angle.sin())
+}
+
+
+/// The wedge from `start` to `end`, in radians clockwise from twelve o'clock.
+fn wedge(start: f64, end: f64) -> String
+{
+    let (x1, y1) = point_on_circle(start - PI / 2.0);
+    let (x2, y2) = point_on_circle(end - PI / 2.0);
+    let large_arc = if end - start > PI { 1 } else { 0 };
+    let centre = PIE_SIZE / 2.0;
+    format!("M {} {} L {} {} A {} {} 0 {} 1 {} {} Z", centre, centre, x1, y1, PIE_RADIUS, PIE_RADIUS, large_arc, x2, y2)
+}
+
+
+pub fn pie_chart(slices: &[Slice]) -> Html
+{
+    let total: f64 = slices.iter().map(|slice| slice.value).sum();
+    let centre = PIE_SIZE / 2.0;
+    let mut start = 0.0;
+    let wedges = slices.iter()
+        .filter(|slice| slice.value > 0.0)
+        .map(|slice|
+            {
+                let angle = 2.0 * PI * slice.value / total;
+                let drawn = if angle > 2.0 * PI - 1e-6
+                {
+                    // An arc cannot end where it starts, so a single option takes the whole circle.
+                    html! { <circle cx={ centre } cy={ centre } r={ PIE_RADIUS } fill={ slice.colour }></circle> }
+                }
+                else
+                {
+                    html! { <path d={ wedge(start, start + angle) } fill={ slice.colour }></path> }
+                };
+                start += angle;
+                drawn
+            })
+        .collect::<Html>();
+    html!
+    {
+        <svg class="chart" viewBox={ format!("0 0 {} {}", PIE_SIZE, PIE_SIZE) }>
+            <circle class="empty" cx={ centre } cy={ centre } r={ PIE_RADIUS }></circle>
+            { wedges }
+        </svg>
+    }
+}
+
+
+/// `hh:mm` in the viewer's time zone.
+fn time_of_day(millis: f64) -> String
+{
+    let date = js_sys::Date::new(&millis.into());
+    format!("{:02}:{:02}", date.get_hours(), date.get_minutes())
+}
+
+
+pub fn line_chart(series: &[Series], translator: &Translator) -> Html
+{
+    let points = series.iter().flat_map(|series| series.points.iter());
+    let (first, last, max) = points.fold((f64::MAX, f64::MIN, 1.0), |(first, last, max), &(at, value)|
+        {
+            (first.min(at), last.max(at), f64::max(max, value))
+        });
+    if last <= first
+    {
+        return html! { <p class="chart-empty">{ translator.text("chart.no_history") }</p> };
+    }
+    let (width, height) = (LINE_WIDTH - 2.0 * LINE_MARGIN, LINE_HEIGHT - 2.0 * LINE_MARGIN);
+    let polyline = |series: &Series|
+        {
+            let points: Vec<String> = series.points.iter()
+                .map(|&(at, value)|
+                    {
+                        let x = LINE_MARGIN + width * (at - first) / (last - first);
+                        let y = LINE_MARGIN + height * (1.0 - value / max);
+                        format!("{:.1},{:.1}", x, y)
+                    })
+                .collect();
+            html! { <polyline points={ points.join(" ") } fill="none" stroke={ series.colour } stroke-width="3"></polyline> }
+        };
+    let bottom = LINE_HEIGHT - LINE_MARGIN;
+    html!
+    {
+        <svg class="chart" viewBox={ format!("0 0 {} {}", LINE_WIDTH, LINE_HEIGHT) }>
+            <line class="axis" x1={ LINE_MARGIN } y1={ LINE_MARGIN } x2={ LINE_MARGIN } y2={ bottom }></line>
+            <line class="axis" x1={ LINE_MARGIN } y1={ bottom } x2={ LINE_WIDTH - LINE_MARGIN } y2={ bottom }></line>
+            <text x={ LINE_MARGIN - 6.0 } y={ LINE_MARGIN + 4.0 } text-anchor="end" class="value">{ max.round() }</text>
+            <text x={ LINE_MARGIN - 6.0 } y={ bottom + 4.0 } text-anchor="end" class="value">{ 0 }</text>
+            <text x={ LINE_MARGIN } y={ LINE_HEIGHT - 12.0 } text-anchor="start" class="label">{ time_of_day(first) }</text>
+            <text x={ LINE_WIDTH - LINE_MARGIN } y={ LINE_HEIGHT - 12.0 } text-anchor="end" class="label">
+                { time_of_day(last) }
+            </text>
+            { for series.iter().map(polyline) }
+        </svg>
+    }
+}
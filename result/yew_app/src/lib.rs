@@ -1,36 +1,134 @@
 #![recursion_limit="512"]
 use wasm_bindgen::prelude::*;
 use yew::prelude::*;
-use lazy_static;
-use std::collections::HashMap;
 use yew::format::Json;
 use anyhow::Error;
 use serde::Deserialize;
+use std::time::Duration;
+use yew::format::Nothing;
+use yew::services::fetch::{FetchService, FetchTask, Request, Response};
+use yew::services::render::{RenderService, RenderTask};
+use yew::services::timeout::{TimeoutService, TimeoutTask};
 use yew::services::websocket::{WebSocketService, WebSocketStatus, WebSocketTask};
-use dotenv_codegen::dotenv;
-use serde_json;
 
+mod animation;
+mod charts;
+mod messages;
+use animation::Transition;
+use charts::{ChartKind, Series, Slice};
+use i18n::Translator;
+use std::collections::HashMap;
+
+
+/// Wait before the first reconnect; it doubles with every failed attempt up to `RECONNECT_MAX_DELAY`.
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
 
-pub const WEBSOCKET_URL: &str = dotenv!("WEBSOCKET_URL");
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct VoteStats
+{
+    pub vote: String,
+    pub quantity: u64,
+    /// Total with every ballot multiplied by its voter's weight.
+    #[serde(default)]
+    pub weighted: u64,
+}
 
 
-lazy_static::lazy_static!
+/// One instant-runoff round of a ranked poll.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct Round
 {
-    static ref VOTE_VARIANTS: HashMap<&'static str, &'static str> =
+    pub counts: Vec<VoteStats>,
+    pub eliminated: Option<String>,
+}
+
+
+/// The Condorcet and Borda view of a ranked poll.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct RankedSummary
+{
+    pub condorcet_winner: Option<String>,
+    pub schulze: Vec<String>,
+    pub borda: Vec<VoteStats>,
+}
+
+
+/// The totals at one point in time, from `GET /api/polls/{id}/history` or broadcast as they are sampled.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct Sample
+{
+    /// Unix time in milliseconds.
+    pub at: f64,
+    pub totals: Vec<VoteStats>,
+}
+
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct PollOption
+{
+    pub id: String,
+    pub label: String,
+}
+
+
+/// A poll's title and option labels in one locale; either may be missing.
+#[derive(Debug, Deserialize)]
+pub struct PollTranslation
+{
+    pub title: Option<String>,
+    pub labels: Option<Vec<String>>,
+}
+
+
+/// Served by the result app at `/config.json`.
+#[derive(Debug, Deserialize)]
+pub struct ClientConfig
+{
+    pub websocket_url: Option<String>,
+    pub poll_id: String,
+    pub title: String,
+    pub options: Vec<PollOption>,
+    /// By locale.
+    #[serde(default)]
+    pub translations: HashMap<String, PollTranslation>,
+}
+
+
+impl ClientConfig
+{
+    /// Takes the title and labels from the translation closest to the `requested` locales, if there is one.
+    /// This need not be the locale of the page: a poll may be translated into languages the page is not.
+    fn localize(&mut self, requested: &[String])
+    {
+        let locales: Vec<&str> = self.translations.keys().map(String::as_str).collect();
+        let locale = i18n::negotiate(requested, &locales, "").to_owned();
+        let translation = match self.translations.get(&locale)
         {
-            let mut h_m = HashMap::new();
-            h_m.insert("a", "Cats");
-            h_m.insert("b", "Dogs");
-            h_m
+            Some(translation) => translation,
+            None => return,
         };
+        if let Some(title) = &translation.title
+        {
+            self.title = title.clone();
+        }
+        for (option, label) in self.options.iter_mut().zip(translation.labels.iter().flatten())
+        {
+            option.label = label.clone();
+        }
+    }
 }
 
 
-#[derive(Debug, Deserialize, PartialEq, Clone)]
-pub struct VoteStats
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Connection
 {
-    pub vote: String,
-    pub quantity: u64,
+    Connecting,
+    Connected,
+    /// The socket was lost; `attempt` counts the reconnects tried since it was last open.
+    Reconnecting { attempt: u32 },
+    Disconnected,
 }
 
 
@@ -38,7 +136,21 @@ struct State
 {
     data: Vec<VoteStats>,
     total_votes: u64,
-    is_connected: bool,
+    total_weighted: u64,
+    connection: Connection,
+    /// Set once the server sends the frozen final tally.
+    is_closed: bool,
+    rounds: Vec<Round>,
+    ranking: Option<RankedSummary>,
+    /// From the config, or from the first totals when it could not be loaded.
+    options: Vec<PollOption>,
+    chart: ChartKind,
+    /// Oldest first.
+    history: Vec<Sample>,
+    /// The weighted total of each option as the charts currently draw it.
+    shown: Vec<f64>,
+    /// When the totals last changed, in unix milliseconds.
+    changed_at: f64,
 }
 
 
@@ -46,7 +158,17 @@ struct Model
 {
     link: ComponentLink<Self>,
     state: State,
+    translator: Translator,
+    /// The locales the reader asked for, most preferred first.
+    locales: Vec<String>,
+    websocket_url: String,
+    poll_id: Option<String>,
+    config_task: Option<FetchTask>,
+    history_task: Option<FetchTask>,
+    transition: Transition,
+    frame_task: Option<RenderTask>,
     websocket_task: Option<WebSocketTask>,
+    reconnect_task: Option<TimeoutTask>,
 }
 
 
@@ -62,12 +184,17 @@ pub enum WsAction
 {
     Connect,
     Disconnect,
+    Opened,
     Lost,
 }
 
 
 pub enum Msg
 {
+    ConfigLoaded(Option<ClientConfig>),
+    HistoryLoaded(Vec<Sample>),
+    ShowChart(ChartKind),
+    Frame,
     WsAction(WsAction),
     WsReady(Result<WsResponse, Error>),
     Ignore,
@@ -86,6 +213,10 @@ impl From<WsAction> for Msg
 enum WsResponseAction
 {
     ReceivedStatistics,
+    ReceivedRounds,
+    ReceivedRanking,
+    ReceivedSample,
+    PollClosed,
 }
 
 
@@ -96,6 +227,10 @@ impl WsResponseAction
         match self
         {
             WsResponseAction::ReceivedStatistics => String::from("received_statistics"),
+            WsResponseAction::ReceivedRounds => String::from("received_rounds"),
+            WsResponseAction::ReceivedRanking => String::from("received_ranking"),
+            WsResponseAction::ReceivedSample => String::from("received_sample"),
+            WsResponseAction::PollClosed => String::from("poll_closed"),
         }
     }
 }
@@ -103,24 +238,345 @@ impl WsResponseAction
 
 
 
+/// The websocket of the server the page was loaded from, over TLS when the page was.
+fn default_websocket_url() -> String
+{
+    let location = web_sys::window().unwrap().location();
+    let scheme = match location.protocol()
+    {
+        Ok(protocol) if protocol == "https:" => "wss",
+        _ => "ws",
+    };
+    format!("{}://{}/ws/", scheme, location.host().unwrap_or_default())
+}
+
+
+/// Exponential backoff with equal jitter, so dashboards dropped together do not all come back at once.
+fn reconnect_delay(attempt: u32) -> Duration
+{
+    let step = RECONNECT_BASE_DELAY.checked_mul(2u32.saturating_pow(attempt)).unwrap_or(RECONNECT_MAX_DELAY);
+    let step = step.min(RECONNECT_MAX_DELAY).as_millis() as f64;
+    Duration::from_millis((step / 2.0 + js_sys::Math::random() * step / 2.0) as u64)
+}
+
+
 impl Model
 {
-    fn votes_percent(&self, vote: &str) -> u64
+    /// Fetches the websocket URL the server wants the dashboard to use; failures fall back to the default.
+    fn load_config(&self) -> FetchTask
+    {
+        let callback = self.link.callback(
+            move |response: Response<Json<Result<ClientConfig, Error>>>|
+                {
+                    let (meta, Json(config)) = response.into_parts();
+                    match config
+                    {
+                        Ok(config) if meta.status.is_success() => Msg::ConfigLoaded(Some(config)),
+                        _ => Msg::ConfigLoaded(None),
+                    }
+                },
+            );
+        let request = Request::get("/config.json").body(Nothing).unwrap();
+        FetchService::fetch(request, callback).unwrap()
+    }
+
+
+    /// Nothing to fetch until the config names the poll.
+    fn load_history(&self) -> Option<FetchTask>
+    {
+        let poll_id = self.poll_id.as_ref()?;
+        let callback = self.link.batch_callback(
+            move |response: Response<Json<Result<Vec<Sample>, Error>>>|
+                {
+                    let (meta, Json(samples)) = response.into_parts();
+                    match samples
+                    {
+                        Ok(samples) if meta.status.is_success() => vec![Msg::HistoryLoaded(samples)],
+                        _ => Vec::new(),
+                    }
+                },
+            );
+        let request = Request::get(format!("/api/polls/{}/history", poll_id)).body(Nothing).unwrap();
+        FetchService::fetch(request, callback).ok()
+    }
+
+
+    fn connect(&mut self) -> Result<(), String>
+    {
+        let callback = self.link.callback(|Json(data)| Msg::WsReady(data));
+        let notification = self.link.callback(|status| match status
+        {
+            WebSocketStatus::Opened => WsAction::Opened,
+            WebSocketStatus::Closed | WebSocketStatus::Error => WsAction::Lost,
+        });
+        let task = WebSocketService::connect(&self.websocket_url, callback, notification).map_err(str::to_owned)?;
+        self.websocket_task = Some(task);
+        Ok(())
+    }
+
+
+    /// Drops the dead socket and schedules the next attempt. Errors are usually followed by a close, so a loss
+    /// reported while a reconnect is already scheduled is ignored.
+    fn schedule_reconnect(&mut self)
+    {
+        if self.state.connection == Connection::Disconnected || self.reconnect_task.is_some()
+        {
+            return;
+        }
+        self.websocket_task = None;
+        let attempt = match self.state.connection
+        {
+            Connection::Reconnecting { attempt } => attempt + 1,
+            _ => 0,
+        };
+        self.state.connection = Connection::Reconnecting { attempt };
+        let delay = reconnect_delay(attempt);
+        let callback = self.link.callback(|_| WsAction::Connect);
+        self.reconnect_task = Some(TimeoutService::spawn(delay, callback));
+    }
+
+
+    fn request_frame(&mut self)
+    {
+        if self.frame_task.is_none()
+        {
+            self.frame_task = Some(RenderService::request_animation_frame(self.link.callback(|_| Msg::Frame)));
+        }
+    }
+
+
+    /// Eases the charts towards the latest totals.
+    fn animate_to_totals(&mut self)
+    {
+        let totals = self.option_totals().iter().map(|(_, weighted)| *weighted as f64).collect();
+        self.transition.retarget(totals, js_sys::Date::now());
+        self.request_frame();
+    }
+
+
+    fn weighted(totals: &[VoteStats], vote: &str) -> u64
+    {
+        totals.iter()
+            .filter(|stats| stats.vote == vote)
+            .fold(0, |acc, stats| acc + stats.weighted)
+    }
+
+
+    /// The latest ballot count and weighted total of each option, in option order.
+    fn option_totals(&self) -> Vec<(u64, u64)>
+    {
+        self.state.options.iter()
+            .map(|option| self.state.data.iter()
+                .filter(|stats| stats.vote == option.id)
+                .fold((0, 0), |(quantity, weighted), stats| (quantity + stats.quantity, weighted + stats.weighted)))
+            .collect()
+    }
+
+
+    /// Shares of the weighted totals, adding up to 100 unless there are no votes.
+    fn percentages(&self) -> Vec<u64>
+    {
+        let weighted: Vec<u64> = self.option_totals().iter().map(|(_, weighted)| *weighted).collect();
+        tally::percentages(&weighted)
+    }
+
+
+    fn label(&self, vote: &str) -> String
+    {
+        self.state.options.iter()
+            .find(|option| option.id == vote)
+            .map_or(vote, |option| &option.label)
+            .to_owned()
+    }
+
+
+    fn view_counts(&self, counts: &[VoteStats]) -> String
+    {
+        let counts: Vec<String> = counts.iter()
+            .map(|stats|
+                {
+                    if stats.quantity == stats.weighted
+                    {
+                        format!("{} {}", self.label(&stats.vote), stats.quantity)
+                    }
+                    else
+                    {
+                        let weighted = self.translator.plural("weighted", stats.weighted, &[]);
+                        format!("{} {} ({})", self.label(&stats.vote), stats.quantity, weighted)
+                    }
+                })
+            .collect();
+        counts.join(&self.translator.text("separator"))
+    }
+
+
+    fn view_connection(&self) -> Html
     {
+        match self.state.connection
+        {
+            Connection::Reconnecting { .. } =>
+                html!
+                {
+                    <div id="connection" class="reconnecting">{ self.translator.text("connection.reconnecting") }</div>
+                },
+            Connection::Connecting =>
+                html! { <div id="connection">{ self.translator.text("connection.connecting") }</div> },
+            Connection::Connected | Connection::Disconnected => html! {},
+        }
+    }
 
-        let votes_quantity =
-            {
-                self.state.data.iter()
-                    .filter(|data| data.vote == vote)
-                    .fold(0, |acc, x| acc + x.quantity )
-            };
-        if self.state.total_votes != 0
+
+    fn view_round(&self, number: usize, round: &Round) -> Html
+    {
+        html!
         {
-            votes_quantity * 100 / self.state.total_votes
+            <li>
+                {
+                    self.translator.format("round",
+                        &[("n", &number.to_string()), ("counts", &self.view_counts(&round.counts))])
+                }
+                {
+                    match &round.eliminated
+                    {
+                        Some(eliminated) =>
+                            self.translator.format("round.eliminated", &[("name", &self.label(eliminated))]),
+                        None => String::new(),
+                    }
+                }
+            </li>
         }
-        else
+    }
+
+
+    fn view_ranking(&self, ranking: &RankedSummary) -> Html
+    {
+        let schulze: Vec<String> = ranking.schulze.iter().map(|vote| self.label(vote)).collect();
+        let condorcet_winner = match &ranking.condorcet_winner
+        {
+            Some(winner) => self.label(winner),
+            None => self.translator.text("ranking.none"),
+        };
+        html!
         {
-            50
+            <div id="ranking">
+                <div>{ self.translator.format("ranking.condorcet", &[("name", &condorcet_winner)]) }</div>
+                <div>{ self.translator.format("ranking.schulze", &[("ranking", &schulze.join(" > "))]) }</div>
+                <div>{ self.translator.format("ranking.borda", &[("counts", &self.view_counts(&ranking.borda))]) }</div>
+            </div>
+        }
+    }
+
+
+    fn slices(&self) -> Vec<Slice<'_>>
+    {
+        let totals = self.option_totals();
+        let weighted: Vec<u64> = totals.iter().map(|(_, weighted)| *weighted).collect();
+        let percentages = tally::percentages(&weighted);
+        let leaders = tally::leaders(&weighted);
+        self.state.options.iter().enumerate()
+            .map(|(index, option)| Slice
+                {
+                    label: &option.label,
+                    value: self.state.shown.get(index).copied().unwrap_or(0.0),
+                    percent: percentages[index],
+                    votes: totals[index].0,
+                    weighted: totals[index].1,
+                    leader: leaders.contains(&index),
+                    colour: charts::colour(index),
+                })
+            .collect()
+    }
+
+
+    /// Who is ahead, or who won once the poll is closed. Nothing before the first vote.
+    fn view_leader(&self, slices: &[Slice]) -> Html
+    {
+        let leaders: Vec<&str> = slices.iter().filter(|slice| slice.leader).map(|slice| slice.label).collect();
+        let text = match (leaders.as_slice(), self.state.is_closed)
+        {
+            ([], _) => return html! {},
+            ([leader], false) => self.translator.format("standing.leads", &[("name", leader)]),
+            ([winner], true) => self.translator.format("standing.wins", &[("name", winner)]),
+            (tied, _) =>
+                {
+                    let names = tied.join(&self.translator.text("separator"));
+                    self.translator.format("standing.tie", &[("names", &names)])
+                },
+        };
+        let class = if leaders.len() > 1 { "standing tied" } else { "standing" };
+        html! { <div class=class>{ text }</div> }
+    }
+
+
+    /// Each option's weighted total at every sample, and where the charts are now at the time the totals last
+    /// changed, so the end of each line moves with the other charts.
+    fn series(&self) -> Vec<Series>
+    {
+        let last_sample = self.state.history.last().map_or(f64::MIN, |sample| sample.at);
+        self.state.options.iter().enumerate()
+            .map(|(index, option)|
+                {
+                    let mut points: Vec<(f64, f64)> = self.state.history.iter()
+                        .map(|sample| (sample.at, Model::weighted(&sample.totals, &option.id) as f64))
+                        .collect();
+                    if self.state.changed_at > last_sample
+                    {
+                        points.push((self.state.changed_at, self.state.shown.get(index).copied().unwrap_or(0.0)));
+                    }
+                    Series { colour: charts::colour(index), points }
+                })
+            .collect()
+    }
+
+
+    fn view_charts(&self) -> Html
+    {
+        let slices = self.slices();
+        let chart = match self.state.chart
+        {
+            ChartKind::Bar => charts::bar_chart(&slices),
+            ChartKind::Pie => charts::pie_chart(&slices),
+            ChartKind::Line => charts::line_chart(&self.series(), &self.translator),
+        };
+        html!
+        {
+            <div id="charts">
+                <div class="chart-kinds">
+                    {
+                        for ChartKind::ALL.iter().map(|&kind| html!
+                            {
+                                <button class=if kind == self.state.chart { "selected" } else { "" }
+                                    onclick=self.link.callback(move |_| Msg::ShowChart(kind))>
+                                    { self.translator.text(kind.name_key()) }
+                                </button>
+                            })
+                    }
+                </div>
+                { chart }
+                { charts::legend(&slices, &self.translator) }
+                { self.view_leader(&slices) }
+            </div>
+        }
+    }
+
+
+    /// The page background, split between the options by their share of the vote, or evenly before any votes.
+    fn view_background(&self) -> Html
+    {
+        let even = 100.0 / self.state.options.len().max(1) as f64;
+        let percentages = self.percentages();
+        let counted = self.state.total_weighted > 0;
+        html!
+        {
+            <div id="background-stats">
+                {
+                    for percentages.iter().enumerate().map(|(index, percent)| html!
+                        {
+                            <div style=format!("width: {}%; background-color: {}",
+                                if counted { *percent as f64 } else { even }, charts::colour(index))></div>
+                        })
+                }
+            </div>
         }
     }
 }
@@ -133,13 +589,21 @@ impl Component for Model
 
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self
     {
-        let mut data = Vec::new();
-        for vote_variant_key in VOTE_VARIANTS.keys()
+        let state = State
+        {
+            data: Vec::new(), total_votes: 0, total_weighted: 0, connection: Connection::Connecting, is_closed: false,
+            rounds: Vec::new(), ranking: None, options: Vec::new(), chart: ChartKind::Bar, history: Vec::new(),
+            shown: Vec::new(), changed_at: 0.0,
+        };
+        let locales = messages::requested_locales();
+        let translator = Translator::new(&locales, &messages::CATALOGUES);
+        // Without it the page is still readable, only laid out left to right.
+        let _ = messages::apply_to_document(&translator);
+        Self
         {
-            let vote_stats = VoteStats { vote: vote_variant_key.to_string(), quantity: 0 };
-            data.push(vote_stats);
+            link, state, translator, locales, websocket_url: default_websocket_url(), poll_id: None, config_task: None, history_task: None,
+            transition: Transition::default(), frame_task: None, websocket_task: None, reconnect_task: None,
         }
-        Self { link, state: State { data, total_votes: 0, is_connected: false } , websocket_task: None }
     }
 
 
@@ -153,46 +617,127 @@ impl Component for Model
                     {
                         WsAction::Connect =>
                             {
-                                let callback = self.link.callback(|Json(data)| Msg::WsReady(data));
-                                let notification = self.link.callback(|status| match status
+                                self.reconnect_task = None;
+                                if self.connect().is_err()
                                 {
-                                    WebSocketStatus::Opened => Msg::Ignore,
-                                    WebSocketStatus::Closed | WebSocketStatus::Error => WsAction::Lost.into(),
-                                });
-                                let task =
-                                    WebSocketService::connect(WEBSOCKET_URL, callback, notification)
-                                        .unwrap();
-                                self.websocket_task = Some(task);
-                                self.state.is_connected = true;
+                                    self.schedule_reconnect();
+                                }
                             },
                         WsAction::Disconnect =>
                             {
-                                self.websocket_task.take();
-                                self.state.is_connected = false;
+                                self.websocket_task = None;
+                                self.reconnect_task = None;
+                                self.state.connection = Connection::Disconnected;
                             },
-                        WsAction::Lost => self.websocket_task = None,
+                        WsAction::Opened =>
+                            {
+                                self.state.connection = Connection::Connected;
+                                // Asks for the current tally instead of waiting for the next broadcast, which
+                                // never comes once the poll is closed.
+                                if let Some(task) = self.websocket_task.as_mut()
+                                {
+                                    task.send(Json(&serde_json::json!({ "action": "subscribe" })));
+                                }
+                                // Samples taken while the socket was down are only in the history.
+                                self.history_task = self.load_history();
+                            },
+                        WsAction::Lost => self.schedule_reconnect(),
+                    }
+                },
+            Msg::ConfigLoaded(config) =>
+                {
+                    self.config_task = None;
+                    if let Some(mut config) = config
+                    {
+                        config.localize(&self.locales);
+                        if let Some(websocket_url) = config.websocket_url
+                        {
+                            self.websocket_url = websocket_url;
+                        }
+                        if let Some(document) = web_sys::window().and_then(|window| window.document())
+                        {
+                            document.set_title(&self.translator.format("title", &[("title", &config.title)]));
+                        }
+                        self.poll_id = Some(config.poll_id);
+                        self.state.options = config.options;
+                    }
+                    self.link.send_message(Msg::WsAction(WsAction::Connect));
+                },
+            Msg::HistoryLoaded(samples) =>
+                {
+                    self.history_task = None;
+                    // Samples broadcast while the history was on its way are newer than any in it.
+                    let last = samples.last().map_or(f64::MIN, |sample| sample.at);
+                    let live = std::mem::replace(&mut self.state.history, samples);
+                    self.state.history.extend(live.into_iter().filter(|sample| sample.at > last));
+                },
+            Msg::ShowChart(chart) =>
+                {
+                    if chart == self.state.chart { return false; }
+                    self.state.chart = chart;
+                },
+            Msg::Frame =>
+                {
+                    self.frame_task = None;
+                    let now = js_sys::Date::now();
+                    self.state.shown = self.transition.values(now);
+                    if self.transition.is_running(now)
+                    {
+                        self.request_frame();
                     }
                 },
             Msg::Ignore => return false,
             Msg::WsReady(response) =>
                 {
-                    if let Some(received_data) = response.ok()
+                    if let Ok(received_data) = response
                     {
-                        if received_data.action == WsResponseAction::ReceivedStatistics.as_str()
+                        let is_closed = received_data.action == WsResponseAction::PollClosed.as_str();
+                        if received_data.action == WsResponseAction::ReceivedRounds.as_str()
+                        {
+                            let rounds: Vec<Round> = serde_json::from_str(&received_data.data).unwrap();
+                            if rounds == self.state.rounds { return false; }
+                            self.state.rounds = rounds;
+                        }
+                        else if received_data.action == WsResponseAction::ReceivedSample.as_str()
+                        {
+                            let sample: Sample = serde_json::from_str(&received_data.data).unwrap();
+                            if matches!(self.state.history.last(), Some(last) if last.at >= sample.at) { return false; }
+                            self.state.history.push(sample);
+                            if self.state.chart != ChartKind::Line { return false; }
+                        }
+                        else if received_data.action == WsResponseAction::ReceivedRanking.as_str()
                         {
+                            let ranking: RankedSummary = serde_json::from_str(&received_data.data).unwrap();
+                            if Some(&ranking) == self.state.ranking.as_ref() { return false; }
+                            self.state.ranking = Some(ranking);
+                        }
+                        else if is_closed || received_data.action == WsResponseAction::ReceivedStatistics.as_str()
+                        {
+                            self.state.is_closed = is_closed;
 
                             let data: Vec<VoteStats> = serde_json::from_str(&received_data.data).unwrap();
                             if data != self.state.data
                             {
                                 self.state.data = data.clone();
                                 let mut total_votes = 0;
+                                let mut total_weighted = 0;
                                 for vote_stats in data
                                 {
                                     total_votes += vote_stats.quantity;
+                                    total_weighted += vote_stats.weighted;
                                 }
                                 self.state.total_votes = total_votes;
+                                self.state.total_weighted = total_weighted;
+                                if self.state.options.is_empty()
+                                {
+                                    self.state.options = self.state.data.iter()
+                                        .map(|stats| PollOption { id: stats.vote.clone(), label: stats.vote.clone() })
+                                        .collect();
+                                }
+                                self.state.changed_at = js_sys::Date::now();
+                                self.animate_to_totals();
                             }
-                            else { return false; }
+                            else if !is_closed { return false; }
                         }
                         else { return false; }
                     }
@@ -214,50 +759,70 @@ impl Component for Model
         html!
         {
             <>
-                <div id="background-stats">
-                   <div id="background-stats-1"></div>
-                   <div id="background-stats-2"></div>
-                </div>
+                { self.view_background() }
                 <div id="content-container">
                     <div id="content-container-center">
-                        <div id="choice">
-                            <div class="choice cats">
-                                <div class="label">{ VOTE_VARIANTS.get("a").unwrap() }</div>
-                                <div class="stat">{ self.votes_percent("a") }{ "%" }</div>
-                            </div>
-                            <div class="divider"></div>
-                            <div class="choice dogs">
-                                <div class="label">{ VOTE_VARIANTS.get("b").unwrap() }</div>
-                                <div class="stat">{ self.votes_percent("b") }{ "%" }</div>
-                            </div>
-                        </div>
+                        { self.view_charts() }
                     </div>
                 </div>
+                { self.view_connection() }
                 <div id="result">
+                    {
+                        if self.state.is_closed
+                        {
+                            html! { <span class="closed">{ self.translator.text("closed") }</span> }
+                        }
+                        else
+                        {
+                            html! {}
+                        }
+                    }
                     {
                         if self.state.total_votes == 0
                         {
                             html!
                             {
-                                <span>{ "No votes yet" }</span>
+                                <span>{ self.translator.text("no_votes") }</span>
                             }
                         }
-                        else if self.state.total_votes == 1
+                        else
                         {
                             html!
                             {
-                                <span>{ "1 vote" }</span>
+                                <span>{ self.translator.plural("votes", self.state.total_votes, &[]) }</span>
                             }
                         }
+                    }
+                    {
+                        if self.state.total_weighted != self.state.total_votes
+                        {
+                            let weighted = self.translator.plural("weighted", self.state.total_weighted, &[]);
+                            html! { <span class="weighted">{ format!(" ({})", weighted) }</span> }
+                        }
                         else
                         {
-                            html!
-                            {
-                                <span>{ self.state.total_votes }{ " votes" }</span>
-                            }
+                            html! {}
                         }
                     }
                 </div>
+                {
+                    if self.state.rounds.len() > 1 || self.state.ranking.is_some()
+                    {
+                        html!
+                        {
+                            <div id="breakdown">
+                                <ol id="rounds">
+                                    { for self.state.rounds.iter().enumerate().map(|(index, round)| self.view_round(index + 1, round)) }
+                                </ol>
+                                { for self.state.ranking.iter().map(|ranking| self.view_ranking(ranking)) }
+                            </div>
+                        }
+                    }
+                    else
+                    {
+                        html! {}
+                    }
+                }
             </>
         }
     }
@@ -267,7 +832,7 @@ impl Component for Model
     {
         if first_render
         {
-            self.link.send_message(Msg::WsAction(WsAction::Connect));
+            self.config_task = Some(self.load_config());
         }
     }
 
@@ -0,0 +1,129 @@
+use i18n::{Catalogue, Translator};
+use wasm_bindgen::JsValue;
+
+
+/// The dashboard's messages; English is the default and fills in anything another catalogue lacks.
+pub static CATALOGUES: [Catalogue; 3] = [
+    Catalogue
+    {
+        locale: "en",
+        messages: &[
+            ("title", "{title} -- Result"),
+            ("separator", ", "),
+            ("no_votes", "No votes yet"),
+            ("votes.one", "1 vote"),
+            ("votes.other", "{n} votes"),
+            ("weighted.other", "{n} weighted"),
+            ("closed", "Poll closed, final result: "),
+            ("connection.reconnecting", "Reconnecting…"),
+            ("connection.connecting", "Connecting…"),
+            ("chart.bar", "Bars"),
+            ("chart.pie", "Pie"),
+            ("chart.line", "Over time"),
+            ("chart.no_history", "Not enough history yet"),
+            ("standing.leads", "{name} leads"),
+            ("standing.wins", "{name} wins"),
+            ("standing.tie", "Tie between {names}"),
+            ("round", "Round {n}: {counts}"),
+            ("round.eliminated", ", {name} eliminated"),
+            ("ranking.condorcet", "Condorcet winner: {name}"),
+            ("ranking.schulze", "Schulze: {ranking}"),
+            ("ranking.borda", "Borda: {counts}"),
+            ("ranking.none", "none"),
+        ],
+    },
+    Catalogue
+    {
+        locale: "de",
+        messages: &[
+            ("title", "{title} -- Ergebnis"),
+            ("no_votes", "Noch keine Stimmen"),
+            ("votes.one", "1 Stimme"),
+            ("votes.other", "{n} Stimmen"),
+            ("weighted.other", "{n} gewichtet"),
+            ("closed", "Umfrage beendet, Endergebnis: "),
+            ("connection.reconnecting", "Verbindung wird wiederhergestellt…"),
+            ("connection.connecting", "Verbinde…"),
+            ("chart.bar", "Balken"),
+            ("chart.pie", "Torte"),
+            ("chart.line", "Im Verlauf"),
+            ("chart.no_history", "Noch nicht genug Verlauf"),
+            ("standing.leads", "{name} liegt vorn"),
+            ("standing.wins", "{name} gewinnt"),
+            ("standing.tie", "Gleichstand zwischen {names}"),
+            ("round", "Runde {n}: {counts}"),
+            ("round.eliminated", ", {name} ausgeschieden"),
+            ("ranking.condorcet", "Condorcet-Sieger: {name}"),
+            ("ranking.none", "keiner"),
+        ],
+    },
+    Catalogue
+    {
+        locale: "ar",
+        messages: &[
+            ("title", "{title} -- النتيجة"),
+            ("separator", "، "),
+            ("no_votes", "لا أصوات بعد"),
+            ("votes.zero", "لا أصوات"),
+            ("votes.one", "صوت واحد"),
+            ("votes.two", "صوتان"),
+            ("votes.few", "{n} أصوات"),
+            ("votes.many", "{n} صوتاً"),
+            ("votes.other", "{n} صوت"),
+            ("weighted.other", "{n} بعد الترجيح"),
+            ("closed", "أُغلق الاستطلاع، النتيجة النهائية: "),
+            ("connection.reconnecting", "جارٍ إعادة الاتصال…"),
+            ("connection.connecting", "جارٍ الاتصال…"),
+            ("chart.bar", "أعمدة"),
+            ("chart.pie", "دائرة"),
+            ("chart.line", "عبر الزمن"),
+            ("chart.no_history", "لا يوجد سجل كافٍ بعد"),
+            ("standing.leads", "{name} في المقدمة"),
+            ("standing.wins", "الفائز: {name}"),
+            ("standing.tie", "تعادل بين {names}"),
+            ("round", "الجولة {n}: {counts}"),
+            ("round.eliminated", "، أُقصي {name}"),
+            ("ranking.condorcet", "فائز كوندورسيه: {name}"),
+            ("ranking.schulze", "شولتسه: {ranking}"),
+            ("ranking.borda", "بوردا: {counts}"),
+            ("ranking.none", "لا أحد"),
+        ],
+    },
+];
+
+
+/// The locales the reader asked for, most preferred first: `?lang=` in the page address, then the browser's
+/// languages.
+pub fn requested_locales() -> Vec<String>
+{
+    let window = match web_sys::window()
+    {
+        Some(window) => window,
+        None => return Vec::new(),
+    };
+    let mut locales = Vec::new();
+    let search = window.location().search().unwrap_or_default();
+    if let Some(lang) = web_sys::UrlSearchParams::new_with_str(&search).ok().and_then(|params| params.get("lang"))
+    {
+        locales.push(lang);
+    }
+    let navigator = window.navigator();
+    locales.extend(navigator.languages().iter().filter_map(|language| language.as_string()));
+    locales.extend(navigator.language());
+    locales
+}
+
+
+/// Sets `lang` and `dir` on the page, so the browser lays right-to-left languages out as such.
+pub fn apply_to_document(translator: &Translator) -> Result<(), JsValue>
+{
+    let root = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.document_element());
+    if let Some(root) = root
+    {
+        root.set_attribute("lang", translator.locale())?;
+        root.set_attribute("dir", translator.direction())?;
+    }
+    Ok(())
+}
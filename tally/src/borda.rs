@@ -0,0 +1,17 @@
+use crate::{ranked, Ranking};
+
+
+/// Borda count: with `n` options, a ballot gives `n - 1` points to its first choice, `n - 2` to its second
+/// and so on. Unranked options get nothing from that ballot. Points are multiplied by the ballot's weight.
+pub fn borda<B: Ranking>(options: usize, ballots: &[B]) -> Vec<u64>
+{
+    let mut points = vec![0; options];
+    for ballot in ballots
+    {
+        for (position, option) in ranked(options, ballot.ranking()).into_iter().enumerate()
+        {
+            points[option] += (options - 1 - position) as u64 * ballot.weight();
+        }
+    }
+    points
+}
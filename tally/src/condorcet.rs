@@ -0,0 +1,88 @@
+use crate::{ranked, Ranking};
+
+
+/// `pairwise[a][b]` is the weight of the ballots preferring option `a` to option `b`.
+pub fn pairwise_preferences<B: Ranking>(options: usize, ballots: &[B]) -> Vec<Vec<u64>>
+{
+    let mut pairwise = vec![vec![0; options]; options];
+    for ballot in ballots
+    {
+        let weight = ballot.weight();
+        let ranked = ranked(options, ballot.ranking());
+        let mut is_ranked = vec![false; options];
+        for (position, &winner) in ranked.iter().enumerate()
+        {
+            is_ranked[winner] = true;
+            for &loser in &ranked[position + 1..]
+            {
+                pairwise[winner][loser] += weight;
+            }
+        }
+        for &winner in &ranked
+        {
+            for loser in (0..options).filter(|option| !is_ranked[*option])
+            {
+                pairwise[winner][loser] += weight;
+            }
+        }
+    }
+    pairwise
+}
+
+
+/// The option preferred to every other one by a majority of the ballots expressing a preference, if any.
+pub fn condorcet_winner(pairwise: &[Vec<u64>]) -> Option<usize>
+{
+    (0..pairwise.len()).find(|&a| (0..pairwise.len()).all(|b| a == b || pairwise[a][b] > pairwise[b][a]))
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schulze
+{
+    pub pairwise: Vec<Vec<u64>>,
+    /// `strongest_paths[a][b]` is the strength of the strongest beatpath from `a` to `b`.
+    pub strongest_paths: Vec<Vec<u64>>,
+    /// Every option, best first. Options neither beats the other keep their numbering order.
+    pub ranking: Vec<usize>,
+}
+
+
+impl Schulze
+{
+    pub fn winner(&self) -> Option<usize>
+    {
+        self.ranking.first().copied()
+    }
+}
+
+
+/// The Schulze method, which always elects the Condorcet winner when there is one.
+pub fn schulze<B: Ranking>(options: usize, ballots: &[B]) -> Schulze
+{
+    let pairwise = pairwise_preferences(options, ballots);
+    let mut paths = vec![vec![0; options]; options];
+    for a in 0..options
+    {
+        for b in (0..options).filter(|b| *b != a && pairwise[a][*b] > pairwise[*b][a])
+        {
+            paths[a][b] = pairwise[a][b];
+        }
+    }
+    for via in 0..options
+    {
+        for a in (0..options).filter(|a| *a != via)
+        {
+            for b in (0..options).filter(|b| *b != via && *b != a)
+            {
+                paths[a][b] = paths[a][b].max(paths[a][via].min(paths[via][b]));
+            }
+        }
+    }
+
+    // "a beats b" on strongest paths is transitive, so counting the options each one beats orders them.
+    let wins: Vec<u64> = (0..options)
+        .map(|a| (0..options).filter(|&b| paths[a][b] > paths[b][a]).count() as u64)
+        .collect();
+    Schulze { ranking: crate::rank_by_score(&wins), pairwise, strongest_paths: paths }
+}
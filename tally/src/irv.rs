@@ -0,0 +1,90 @@
+use crate::{ranked, Ranking};
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Round
+{
+    /// Weight of the ballots counted for each option; eliminated options count zero.
+    pub counts: Vec<u64>,
+    /// Number of ballots behind each count, which only differs from `counts` for weighted ballots.
+    pub ballots: Vec<u64>,
+    /// Weight of the ballots whose ranked options have all been eliminated.
+    pub exhausted: u64,
+    /// The option dropped at the end of this round, `None` in the last round.
+    pub eliminated: Option<usize>,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Runoff
+{
+    pub rounds: Vec<Round>,
+    pub winner: Option<usize>,
+}
+
+
+/// Picks who to drop among the options tied for fewest votes: the one with fewer votes in the latest earlier
+/// round where they differ, then the highest option number.
+fn break_elimination_tie(mut tied: Vec<usize>, rounds: &[Round]) -> usize
+{
+    for round in rounds.iter().rev()
+    {
+        if tied.len() == 1
+        {
+            break;
+        }
+        let fewest = tied.iter().map(|option| round.counts[*option]).min().unwrap_or(0);
+        tied.retain(|option| round.counts[*option] == fewest);
+    }
+    tied.into_iter().max().unwrap()
+}
+
+
+/// Instant runoff: each round counts every ballot for its highest ranked option still running and drops the
+/// option with fewest votes, until one option holds a majority of the weight counted or is the last one left.
+pub fn instant_runoff<B: Ranking>(options: usize, ballots: &[B]) -> Runoff
+{
+    let ballots: Vec<(Vec<usize>, u64)> = ballots.iter()
+        .map(|ballot| (ranked(options, ballot.ranking()), ballot.weight()))
+        .collect();
+    let mut running = vec![true; options];
+    let mut rounds: Vec<Round> = Vec::new();
+    loop
+    {
+        let mut counts = vec![0; options];
+        let mut counted_ballots = vec![0; options];
+        let mut exhausted = 0;
+        for (ballot, weight) in &ballots
+        {
+            match ballot.iter().find(|option| running[**option])
+            {
+                Some(option) =>
+                    {
+                        counts[*option] += weight;
+                        counted_ballots[*option] += 1;
+                    },
+                None => exhausted += weight,
+            }
+        }
+        let counted: u64 = counts.iter().sum();
+        let remaining: Vec<usize> = (0..options).filter(|option| running[*option]).collect();
+        let leader = remaining.iter().copied().max_by(|a, b| counts[*a].cmp(&counts[*b]).then(b.cmp(a)));
+
+        let winner = match leader
+        {
+            Some(leader) if counted > 0 && (counts[leader] * 2 > counted || remaining.len() == 1) => Some(leader),
+            _ => None,
+        };
+        if winner.is_some() || counted == 0
+        {
+            rounds.push(Round { counts, ballots: counted_ballots, exhausted, eliminated: None });
+            return Runoff { rounds, winner };
+        }
+
+        let fewest = remaining.iter().map(|option| counts[*option]).min().unwrap_or(0);
+        let tied = remaining.into_iter().filter(|option| counts[*option] == fewest).collect();
+        let eliminated = break_elimination_tie(tied, &rounds);
+        running[eliminated] = false;
+        rounds.push(Round { counts, ballots: counted_ballots, exhausted, eliminated: Some(eliminated) });
+    }
+}
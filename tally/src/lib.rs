@@ -0,0 +1,83 @@
+//! Tallying of ranked ballots: instant runoff, Condorcet/Schulze and Borda.
+//!
+//! Options are numbered `0..options` and a ballot lists option numbers from most to least preferred.
+//! Ballots may be partial; unranked options are tied below every ranked one. Numbers out of range and repeats
+//! of an option already ranked are ignored. Every tie is broken the same way for the same ballots, whatever
+//! order the ballots come in, ultimately in favour of the lower option number.
+//!
+//! A ballot is a plain `Vec<usize>`, which counts once, or a `Weighted` ballot counting `weight` times.
+//!
+//! `percentages` and `leaders` present any tally: shares in whole percent that add up to 100, and who is ahead.
+
+mod borda;
+mod condorcet;
+mod irv;
+mod shares;
+
+pub use borda::borda;
+pub use condorcet::{condorcet_winner, pairwise_preferences, schulze, Schulze};
+pub use irv::{instant_runoff, Round, Runoff};
+pub use shares::{largest_remainder, leaders, percentages};
+
+
+pub trait Ranking
+{
+    /// Option numbers, most preferred first.
+    fn ranking(&self) -> &[usize];
+
+    fn weight(&self) -> u64
+    {
+        1
+    }
+}
+
+
+impl Ranking for Vec<usize>
+{
+    fn ranking(&self) -> &[usize]
+    {
+        self
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Weighted
+{
+    pub ranking: Vec<usize>,
+    pub weight: u64,
+}
+
+
+impl Ranking for Weighted
+{
+    fn ranking(&self) -> &[usize]
+    {
+        &self.ranking
+    }
+
+    fn weight(&self) -> u64
+    {
+        self.weight
+    }
+}
+
+
+/// The valid, distinct options of a ballot, in ballot order.
+fn ranked(options: usize, ballot: &[usize]) -> Vec<usize>
+{
+    let mut seen = vec![false; options];
+    ballot.iter()
+        .copied()
+        .filter(|option| *option < options && !std::mem::replace(&mut seen[*option], true))
+        .collect()
+}
+
+
+/// Options ordered by descending score, lower option numbers first among equal scores.
+pub fn rank_by_score(scores: &[u64]) -> Vec<usize>
+{
+    let mut ranking: Vec<usize> = (0..scores.len()).collect();
+    ranking.sort_by(|a, b| scores[*b].cmp(&scores[*a]).then(a.cmp(b)));
+    ranking
+}
@@ -0,0 +1,40 @@
+/// Splits `total` in proportion to `counts` by the largest remainder method: every option gets its exact share
+/// rounded down, and what is left goes one at a time to the largest remainders, lower option numbers first
+/// among equal remainders. The result always adds up to `total`, unless nothing was counted and it is all zeros.
+pub fn largest_remainder(counts: &[u64], total: u64) -> Vec<u64>
+{
+    let counted: u128 = counts.iter().map(|count| u128::from(*count)).sum();
+    if counted == 0
+    {
+        return vec![0; counts.len()];
+    }
+    let exact: Vec<u128> = counts.iter().map(|count| u128::from(*count) * u128::from(total)).collect();
+    let mut shares: Vec<u64> = exact.iter().map(|exact| (exact / counted) as u64).collect();
+    let left = total - shares.iter().sum::<u64>();
+    let mut by_remainder: Vec<usize> = (0..counts.len()).collect();
+    by_remainder.sort_by(|a, b| (exact[*b] % counted).cmp(&(exact[*a] % counted)).then(a.cmp(b)));
+    for option in by_remainder.into_iter().take(left as usize)
+    {
+        shares[option] += 1;
+    }
+    shares
+}
+
+
+/// Whole percentages of `counts` that add up to 100, or all zeros when nothing was counted.
+pub fn percentages(counts: &[u64]) -> Vec<u64>
+{
+    largest_remainder(counts, 100)
+}
+
+
+/// The options with the highest count, lowest number first: one for a clear lead, more for a tie and none when
+/// nothing was counted.
+pub fn leaders(counts: &[u64]) -> Vec<usize>
+{
+    match counts.iter().max()
+    {
+        Some(&max) if max > 0 => (0..counts.len()).filter(|option| counts[*option] == max).collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,102 @@
+use tally::{borda, condorcet_winner, instant_runoff, pairwise_preferences, schulze, Weighted};
+
+
+fn ballots(groups: &[(usize, &[usize])]) -> Vec<Vec<usize>>
+{
+    groups.iter().flat_map(|(voters, ranking)| vec![ranking.to_vec(); *voters]).collect()
+}
+
+
+// The Tennessee capital example: Memphis, Nashville, Chattanooga, Knoxville.
+fn tennessee() -> Vec<Vec<usize>>
+{
+    ballots(&[(42, &[0, 1, 2, 3]), (26, &[1, 2, 3, 0]), (15, &[2, 3, 1, 0]), (17, &[3, 2, 1, 0])])
+}
+
+
+#[test]
+fn instant_runoff_transfers_votes_round_by_round()
+{
+    let runoff = instant_runoff(4, &tennessee());
+    let counts: Vec<Vec<u64>> = runoff.rounds.iter().map(|round| round.counts.clone()).collect();
+    assert_eq!(counts, vec![vec![42, 26, 15, 17], vec![42, 26, 0, 32], vec![42, 0, 0, 58]]);
+    let eliminated: Vec<Option<usize>> = runoff.rounds.iter().map(|round| round.eliminated).collect();
+    assert_eq!(eliminated, vec![Some(2), Some(1), None]);
+    assert_eq!(runoff.winner, Some(3));
+}
+
+
+#[test]
+fn condorcet_and_borda_pick_the_compromise()
+{
+    let ballots = tennessee();
+    assert_eq!(condorcet_winner(&pairwise_preferences(4, &ballots)), Some(1));
+    assert_eq!(schulze(4, &ballots).winner(), Some(1));
+    assert_eq!(borda(4, &ballots), vec![126, 194, 173, 107]);
+}
+
+
+// The 45 voter example from Schulze's paper, which has no Condorcet winner.
+#[test]
+fn schulze_resolves_a_cycle()
+{
+    let ballots = ballots(&[
+        (5, &[0, 2, 1, 4, 3]), (5, &[0, 3, 4, 2, 1]), (8, &[1, 4, 3, 0, 2]), (3, &[2, 0, 1, 4, 3]),
+        (7, &[2, 0, 4, 1, 3]), (2, &[2, 1, 0, 3, 4]), (7, &[3, 2, 4, 1, 0]), (8, &[4, 1, 0, 3, 2]),
+    ]);
+    let result = schulze(5, &ballots);
+    assert_eq!(condorcet_winner(&result.pairwise), None);
+    assert_eq!(result.ranking, vec![4, 0, 2, 1, 3]);
+}
+
+
+#[test]
+fn ties_go_to_the_lower_option_number()
+{
+    let ballots = vec![vec![0], vec![1]];
+    let runoff = instant_runoff(2, &ballots);
+    assert_eq!(runoff.rounds[0].eliminated, Some(1));
+    assert_eq!(runoff.winner, Some(0));
+    assert_eq!(schulze(2, &ballots).ranking, vec![0, 1]);
+}
+
+
+#[test]
+fn earlier_rounds_break_elimination_ties()
+{
+    // After round one drops option 3, options 1 and 2 tie on 3 votes; option 2 had fewer in round one.
+    let ballots = ballots(&[(5, &[0]), (3, &[1]), (2, &[2]), (1, &[3, 2])]);
+    let runoff = instant_runoff(4, &ballots);
+    assert_eq!(runoff.rounds[1].counts, vec![5, 3, 3, 0]);
+    assert_eq!(runoff.rounds[1].eliminated, Some(2));
+}
+
+
+#[test]
+fn partial_and_malformed_ballots_are_tolerated()
+{
+    let ballots = vec![vec![1, 1, 7], vec![], vec![9]];
+    let runoff = instant_runoff(3, &ballots);
+    assert_eq!(runoff.rounds[0].counts, vec![0, 1, 0]);
+    assert_eq!(runoff.rounds[0].exhausted, 2);
+    assert_eq!(runoff.winner, Some(1));
+    assert_eq!(borda(3, &ballots), vec![0, 2, 0]);
+    assert_eq!(instant_runoff::<Vec<usize>>(3, &[]).winner, None);
+}
+
+
+#[test]
+fn weights_outvote_headcount()
+{
+    let ballots = vec![
+        Weighted { ranking: vec![0], weight: 1 },
+        Weighted { ranking: vec![0], weight: 1 },
+        Weighted { ranking: vec![1, 0], weight: 3 },
+    ];
+    let runoff = instant_runoff(2, &ballots);
+    assert_eq!(runoff.rounds[0].counts, vec![2, 3]);
+    assert_eq!(runoff.rounds[0].ballots, vec![2, 1]);
+    assert_eq!(runoff.winner, Some(1));
+    assert_eq!(schulze(2, &ballots).winner(), Some(1));
+    assert_eq!(borda(2, &ballots), vec![2, 3]);
+}
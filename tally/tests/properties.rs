@@ -0,0 +1,173 @@
+use proptest::prelude::*;
+use tally::{borda, condorcet_winner, instant_runoff, pairwise_preferences, percentages, schulze, Weighted};
+
+
+/// Up to 6 options and 40 ballots, including out of range numbers and repeats.
+fn election() -> impl Strategy<Value = (usize, Vec<Vec<usize>>)>
+{
+    (1..=6_usize).prop_flat_map(|options|
+        {
+            let ballot = prop::collection::vec(0..=options, 0..=options + 1);
+            (Just(options), prop::collection::vec(ballot, 0..40))
+        })
+}
+
+
+fn shuffled() -> impl Strategy<Value = (usize, Vec<Vec<usize>>, Vec<Vec<usize>>)>
+{
+    election().prop_flat_map(|(options, ballots)| (Just(options), Just(ballots.clone()), Just(ballots).prop_shuffle()))
+}
+
+
+fn weighted() -> impl Strategy<Value = (usize, Vec<Vec<usize>>, Vec<u64>)>
+{
+    election().prop_flat_map(|(options, ballots)|
+        {
+            let weights = prop::collection::vec(1..5_u64, ballots.len());
+            (Just(options), Just(ballots), weights)
+        })
+}
+
+
+proptest!
+{
+    #[test]
+    fn every_ballot_is_counted_or_exhausted((options, ballots) in election())
+    {
+        for round in instant_runoff(options, &ballots).rounds
+        {
+            prop_assert_eq!(round.counts.iter().sum::<u64>() + round.exhausted, ballots.len() as u64);
+        }
+    }
+
+
+    #[test]
+    fn eliminated_options_stay_out((options, ballots) in election())
+    {
+        let runoff = instant_runoff(options, &ballots);
+        prop_assert!(runoff.rounds.len() <= options);
+        prop_assert_eq!(runoff.rounds.last().unwrap().eliminated, None);
+        let mut eliminated = Vec::new();
+        for round in &runoff.rounds
+        {
+            for option in &eliminated
+            {
+                prop_assert_eq!(round.counts[*option], 0);
+            }
+            if let Some(option) = round.eliminated
+            {
+                prop_assert!(!eliminated.contains(&option));
+                eliminated.push(option);
+            }
+        }
+        if let Some(winner) = runoff.winner
+        {
+            prop_assert!(!eliminated.contains(&winner));
+        }
+    }
+
+
+    #[test]
+    fn runoff_winner_has_a_majority_or_is_last((options, ballots) in election())
+    {
+        let runoff = instant_runoff(options, &ballots);
+        let last = runoff.rounds.last().unwrap();
+        let counted: u64 = last.counts.iter().sum();
+        match runoff.winner
+        {
+            Some(winner) =>
+                {
+                    let running = last.counts.iter().filter(|count| **count > 0).count();
+                    prop_assert!(last.counts[winner] * 2 > counted || running == 1);
+                },
+            None => prop_assert_eq!(counted, 0),
+        }
+    }
+
+
+    #[test]
+    fn ballot_order_does_not_matter((options, ballots, shuffled) in shuffled())
+    {
+        prop_assert_eq!(instant_runoff(options, &ballots), instant_runoff(options, &shuffled));
+        prop_assert_eq!(schulze(options, &ballots), schulze(options, &shuffled));
+        prop_assert_eq!(borda(options, &ballots), borda(options, &shuffled));
+    }
+
+
+    #[test]
+    fn schulze_elects_the_condorcet_winner((options, ballots) in election())
+    {
+        let result = schulze(options, &ballots);
+        if let Some(winner) = condorcet_winner(&pairwise_preferences(options, &ballots))
+        {
+            prop_assert_eq!(result.winner(), Some(winner));
+        }
+        let mut ranking = result.ranking.clone();
+        ranking.sort_unstable();
+        prop_assert_eq!(ranking, (0..options).collect::<Vec<_>>());
+    }
+
+
+    #[test]
+    fn unanimous_ballots_elect_their_first_choice(options in 1..=6_usize, voters in 1..20_usize, seed in any::<u64>())
+    {
+        let mut ranking: Vec<usize> = (0..options).collect();
+        ranking.rotate_left(seed as usize % options);
+        let ballots = vec![ranking.clone(); voters];
+        prop_assert_eq!(instant_runoff(options, &ballots).winner, Some(ranking[0]));
+        prop_assert_eq!(schulze(options, &ballots).winner(), Some(ranking[0]));
+        prop_assert_eq!(tally::rank_by_score(&borda(options, &ballots))[0], ranking[0]);
+    }
+
+
+    #[test]
+    fn borda_hands_out_every_point((options, ballots) in election())
+    {
+        let expected: u64 = ballots.iter()
+            .map(|ballot|
+                {
+                    let mut distinct: Vec<usize> = ballot.iter().copied().filter(|option| *option < options).collect();
+                    distinct.sort_unstable();
+                    distinct.dedup();
+                    (0..distinct.len()).map(|position| (options - 1 - position) as u64).sum::<u64>()
+                })
+            .sum();
+        prop_assert_eq!(borda(options, &ballots).iter().sum::<u64>(), expected);
+    }
+
+
+    #[test]
+    fn a_weight_counts_like_repeated_ballots((options, ballots, weights) in weighted())
+    {
+        let weighted: Vec<Weighted> = ballots.iter().zip(&weights)
+            .map(|(ranking, weight)| Weighted { ranking: ranking.clone(), weight: *weight })
+            .collect();
+        let repeated: Vec<Vec<usize>> = ballots.iter().zip(&weights)
+            .flat_map(|(ranking, weight)| vec![ranking.clone(); *weight as usize])
+            .collect();
+        let (weighted_runoff, repeated_runoff) = (instant_runoff(options, &weighted), instant_runoff(options, &repeated));
+        prop_assert_eq!(weighted_runoff.winner, repeated_runoff.winner);
+        for (weighted_round, repeated_round) in weighted_runoff.rounds.iter().zip(&repeated_runoff.rounds)
+        {
+            prop_assert_eq!(&weighted_round.counts, &repeated_round.counts);
+            prop_assert_eq!(weighted_round.eliminated, repeated_round.eliminated);
+        }
+        prop_assert_eq!(schulze(options, &weighted), schulze(options, &repeated));
+        prop_assert_eq!(borda(options, &weighted), borda(options, &repeated));
+    }
+
+
+    #[test]
+    fn percentages_add_up_to_a_hundred(counts in prop::collection::vec(0..1000_u64, 1..8))
+    {
+        let shares = percentages(&counts);
+        let counted: u64 = counts.iter().sum();
+        prop_assert_eq!(shares.iter().sum::<u64>(), if counted == 0 { 0 } else { 100 });
+        // Each share is its exact percentage rounded down or up.
+        for (share, count) in shares.iter().zip(&counts)
+        {
+            let rounded_down = (count * 100).checked_div(counted).unwrap_or(0);
+            prop_assert!(*share == rounded_down || *share == rounded_down + 1);
+        }
+    }
+}
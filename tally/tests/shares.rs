@@ -0,0 +1,46 @@
+use tally::{largest_remainder, leaders, percentages};
+
+
+#[test]
+fn thirds_add_up_to_a_hundred()
+{
+    assert_eq!(percentages(&[1, 1, 1]), vec![34, 33, 33]);
+    assert_eq!(percentages(&[1, 2]), vec![33, 67]);
+    assert_eq!(percentages(&[2, 1, 0]), vec![67, 33, 0]);
+}
+
+
+#[test]
+fn leftovers_go_to_the_largest_remainders()
+{
+    // Exact shares 14.285..., 28.571... and 57.142...: the second has the largest remainder.
+    assert_eq!(percentages(&[1, 2, 4]), vec![14, 29, 57]);
+    // 7.6, 18.4 and 74.0 of 100 seats: 7 + 18 + 74 leaves one, for the .6.
+    assert_eq!(largest_remainder(&[19, 46, 185], 100), vec![8, 18, 74]);
+}
+
+
+#[test]
+fn nothing_counted_is_all_zeros()
+{
+    assert_eq!(percentages(&[0, 0, 0]), vec![0, 0, 0]);
+    assert_eq!(percentages(&[]), Vec::<u64>::new());
+    assert_eq!(percentages(&[0, 5]), vec![0, 100]);
+}
+
+
+#[test]
+fn huge_counts_do_not_overflow()
+{
+    assert_eq!(percentages(&[u64::MAX, u64::MAX]), vec![50, 50]);
+}
+
+
+#[test]
+fn leaders_are_the_options_with_the_highest_count()
+{
+    assert_eq!(leaders(&[3, 5, 1]), vec![1]);
+    assert_eq!(leaders(&[4, 1, 4]), vec![0, 2]);
+    assert_eq!(leaders(&[0, 0]), Vec::<usize>::new());
+    assert_eq!(leaders(&[]), Vec::<usize>::new());
+}
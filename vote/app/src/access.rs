@@ -0,0 +1,223 @@
+use actix::prelude::*;
+use actix_redis::RedisActor;
+use actix_web::{web, HttpRequest, HttpResponse};
+use redis_async::{resp::RespValue, resp_array};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::audit::AuditEvent;
+use common::connection::ConnectionStatus;
+
+use crate::config::Config;
+use crate::error::VoteError;
+use crate::send_command;
+use crate::session;
+
+
+/// Voter ids admitted to a private poll without an invitation, when voters log in with OpenID Connect.
+const ALLOWLIST_KEY: &str = "voter_allowlist";
+/// Invitation tokens that have been issued.
+const INVITATIONS_KEY: &str = "invitations";
+/// Redeemed tokens and the voter id each one is bound to.
+const BINDINGS_KEY: &str = "invitation_bindings";
+/// Most tokens that one request may generate.
+const MAX_INVITATIONS: usize = 1000;
+
+
+/// Decides whether `voter_id` may vote in a private poll: either it is on the allowlist, or it holds an
+/// invitation token that is unused or already bound to it. The first voter to redeem a token keeps it.
+///
+/// The allowlist is only consulted when voters `login`: otherwise the voter id is the client's own cookie, and
+/// anyone knowing an allowlisted id could vote as that voter. Invitation tokens are then the only way in.
+pub async fn check_voter(
+        voter_id: &str, token: Option<&str>, login: bool,
+        redis: &Addr<RedisActor>, redis_status: &ConnectionStatus,
+    )
+    -> Result<(), VoteError>
+{
+    if login
+    {
+        let allowed = send_command(redis, redis_status, resp_array!["SISMEMBER", ALLOWLIST_KEY, voter_id]).await?;
+        if let RespValue::Integer(1) = allowed
+        {
+            return Ok(());
+        }
+    }
+    let token = match token
+    {
+        Some(token) if !token.is_empty() => token,
+        _ => return Err(VoteError::NotInvited),
+    };
+    match send_command(redis, redis_status, resp_array!["SISMEMBER", INVITATIONS_KEY, token]).await?
+    {
+        RespValue::Integer(1) => (),
+        RespValue::Integer(_) =>
+            {
+                tracing::warn!("unknown invitation token");
+                return Err(VoteError::NotInvited);
+            },
+        _ => return Err(VoteError::BackendUnavailable),
+    }
+    // HSETNX only succeeds for the first voter, every later one has to match the voter it bound.
+    match send_command(redis, redis_status, resp_array!["HSETNX", BINDINGS_KEY, token, voter_id]).await?
+    {
+        RespValue::Integer(1) =>
+            {
+                tracing::info!("invitation token redeemed");
+                return Ok(());
+            },
+        RespValue::Integer(_) => (),
+        _ => return Err(VoteError::BackendUnavailable),
+    }
+    match send_command(redis, redis_status, resp_array!["HGET", BINDINGS_KEY, token]).await?
+    {
+        RespValue::BulkString(bound) if bound == voter_id.as_bytes() => Ok(()),
+        RespValue::BulkString(_) =>
+            {
+                tracing::warn!("invitation token already redeemed by another voter");
+                Err(VoteError::NotInvited)
+            },
+        _ => Err(VoteError::BackendUnavailable),
+    }
+}
+
+
+/// Admin requests carry `Authorization: Bearer <admin_token>`, or come from a session with the admin role when
+/// voters log in with OpenID Connect. Everything else is refused. Returns the actor to name in the audit log.
+async fn check_admin(request: &HttpRequest, redis: &Addr<RedisActor>, redis_status: &ConnectionStatus, config: &Config)
+    -> Result<String, VoteError>
+{
+    let presented = request.headers().get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if let (Some(presented), Some(expected)) = (presented, &config.admin_token)
+    {
+        if constant_time_eq(presented.as_bytes(), expected.as_bytes())
+        {
+            return Ok("admin_token".to_owned());
+        }
+    }
+    if config.oidc.is_some()
+    {
+        if let Some(session) = session::current(request, redis, redis_status).await?
+        {
+            if session.is_admin
+            {
+                return Ok(format!("oidc:{}", session.voter_id));
+            }
+            tracing::warn!(voter_id = %session.voter_id, "admin request without the admin role");
+        }
+    }
+    Err(VoteError::Unauthorized)
+}
+
+
+/// Queues an admin action for the worker to append to the audit log. Called once the action has taken effect;
+/// if this fails the admin gets an error and retries, so an action is never left out of the log.
+async fn record(redis: &Addr<RedisActor>, redis_status: &ConnectionStatus, config: &Config, event: AuditEvent)
+    -> Result<(), VoteError>
+{
+    // Strings and a JSON value always serialize.
+    let event = serde_json::to_string(&event).unwrap();
+    send_command(redis, redis_status, resp_array!["RPUSH", &config.redis_audit_key, event]).await?;
+    Ok(())
+}
+
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool
+{
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct InvitationRequest
+{
+    count: usize,
+}
+
+
+#[derive(Serialize)]
+struct Invitations
+{
+    tokens: Vec<String>,
+    links: Vec<String>,
+}
+
+
+/// `POST /admin/invitations` with `{"count": n}`: issues `n` single-use tokens and the links to hand out.
+pub async fn create_invitations(
+        request: HttpRequest, invitation_request: web::Json<InvitationRequest>,
+        redis: web::Data<Addr<RedisActor>>, redis_status: web::Data<ConnectionStatus>, config: web::Data<Config>,
+    )
+    -> Result<HttpResponse, VoteError>
+{
+    let actor = check_admin(&request, &redis, &redis_status, &config).await?;
+    let count = invitation_request.count;
+    if count == 0 || count > MAX_INVITATIONS
+    {
+        return Err(VoteError::InvalidInput { reason: format!("count must be between 1 and {}", MAX_INVITATIONS) });
+    }
+    let tokens: Vec<String> = (0..count).map(|_| Uuid::new_v4().to_simple().to_string()).collect();
+    let mut command = vec![RespValue::from("SADD"), RespValue::from(INVITATIONS_KEY)];
+    command.extend(tokens.iter().map(|token| RespValue::from(token.as_str())));
+    send_command(&redis, &redis_status, RespValue::Array(command)).await?;
+    tracing::info!(count, "invitation tokens issued");
+    // The tokens themselves stay out of the log: anyone who can read it could vote with them.
+    record(&redis, &redis_status, &config,
+            AuditEvent::new(actor, "invitations_issued", serde_json::json!({ "count": count })))
+        .await?;
+
+    let links = tokens.iter().map(|token| format!("/?token={}", token)).collect();
+    Ok(HttpResponse::Created().json(Invitations { tokens, links }))
+}
+
+
+#[derive(Serialize)]
+struct Allowlist
+{
+    voters: usize,
+}
+
+
+/// `PUT /admin/voters` with a JSON list of voter ids: replaces the allowlist as a whole. Only an empty list is
+/// taken when voters do not log in, since the allowlist would then admit anyone claiming a listed id.
+pub async fn replace_allowlist(
+        request: HttpRequest, voters: web::Json<Vec<String>>,
+        redis: web::Data<Addr<RedisActor>>, redis_status: web::Data<ConnectionStatus>, config: web::Data<Config>,
+    )
+    -> Result<HttpResponse, VoteError>
+{
+    let actor = check_admin(&request, &redis, &redis_status, &config).await?;
+    if voters.iter().any(|voter_id| voter_id.is_empty())
+    {
+        return Err(VoteError::InvalidInput { reason: "voter ids must not be empty".to_owned() });
+    }
+    if config.oidc.is_none() && !voters.is_empty()
+    {
+        return Err(VoteError::InvalidInput
+            {
+                reason: "an allowlist needs oidc_issuer; without login hand out invitations instead".to_owned(),
+            });
+    }
+    if voters.is_empty()
+    {
+        send_command(&redis, &redis_status, resp_array!["DEL", ALLOWLIST_KEY]).await?;
+    }
+    else
+    {
+        // Filled under a temporary key and renamed, so a vote never sees a half written list.
+        let staging_key = format!("{}:{}", ALLOWLIST_KEY, Uuid::new_v4().to_simple());
+        let mut command = vec![RespValue::from("SADD"), RespValue::from(staging_key.as_str())];
+        command.extend(voters.iter().map(|voter_id| RespValue::from(voter_id.as_str())));
+        send_command(&redis, &redis_status, RespValue::Array(command)).await?;
+        send_command(&redis, &redis_status, resp_array!["RENAME", staging_key, ALLOWLIST_KEY]).await?;
+    }
+    let mut unique = voters.into_inner();
+    unique.sort();
+    unique.dedup();
+    tracing::info!(voters = unique.len(), "voter allowlist replaced");
+    record(&redis, &redis_status, &config,
+            AuditEvent::new(actor, "allowlist_replaced", serde_json::json!({ "voters": unique.len() })))
+        .await?;
+    Ok(HttpResponse::Ok().json(Allowlist { voters: unique.len() }))
+}
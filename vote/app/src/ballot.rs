@@ -0,0 +1,58 @@
+use common::ballot::Ballot;
+use common::poll::{Poll, VotingMethod};
+
+use crate::error::VoteError;
+
+
+fn invalid(reason: String) -> Result<(), VoteError>
+{
+    Err(VoteError::InvalidInput { reason })
+}
+
+
+fn check_options<'a>(poll: &Poll, options: impl IntoIterator<Item = &'a str>) -> Result<(), VoteError>
+{
+    let mut seen = Vec::new();
+    for option in options
+    {
+        if !poll.options.iter().any(|known| known == option)
+        {
+            return invalid(format!("unknown vote option '{}'", option));
+        }
+        if seen.contains(&option)
+        {
+            return invalid(format!("vote option '{}' appears more than once", option));
+        }
+        seen.push(option);
+    }
+    if seen.is_empty()
+    {
+        return invalid("the ballot names no option".to_owned());
+    }
+    Ok(())
+}
+
+
+/// Checks that a ballot has the shape the poll's voting method expects and only names known options.
+/// A bare option is accepted by approval and ranked polls as a ballot naming just that option.
+pub fn validate(poll: &Poll, ballot: &Ballot) -> Result<(), VoteError>
+{
+    match (poll.method, ballot)
+    {
+        (VotingMethod::Single, Ballot::Single(option)) => check_options(poll, vec![option.as_str()]),
+        (VotingMethod::Approval, Ballot::Single(_)) | (VotingMethod::Approval, Ballot::List(_))
+            | (VotingMethod::Ranked, Ballot::Single(_)) | (VotingMethod::Ranked, Ballot::List(_)) =>
+            check_options(poll, ballot.choices()),
+        (VotingMethod::Score, Ballot::Scores(scores)) =>
+            {
+                check_options(poll, ballot.choices())?;
+                match scores.iter().find(|(_, score)| **score > poll.max_score)
+                {
+                    Some((option, score)) =>
+                        invalid(format!("score {} for '{}' is above the maximum of {}", score, option, poll.max_score)),
+                    None => Ok(()),
+                }
+            },
+        (method, _) => invalid(format!("this ballot does not fit a {} poll", method)),
+    }
+}
@@ -0,0 +1,98 @@
+use std::net::SocketAddr;
+use common::anonymity::Anonymity;
+use common::config::{ConfigError, ConfigLoader};
+use common::poll::Poll;
+use common::telemetry::TelemetryConfig;
+
+use vote_app::oidc::OidcConfig;
+
+use vote_app::registry::VoterRegistry;
+
+
+pub struct Config
+{
+    pub bind: SocketAddr,
+    /// Names this instance on the vote page and in receipts; the container's hostname unless set.
+    pub instance_id: String,
+    pub redis_addr: String,
+    pub redis_key: String,
+    /// Where admin actions are queued for the worker to append to the audit log.
+    pub redis_audit_key: String,
+    pub vote_rate_limit_ms: u64,
+    pub poll: Poll,
+    /// Only allowlisted voters and holders of an invitation token may vote.
+    pub poll_private: bool,
+    /// Bearer token for the `/admin` endpoints, which are disabled without it.
+    pub admin_token: Option<String>,
+    pub voters: VoterRegistry,
+    /// Voters log in with OpenID Connect when set.
+    pub oidc: Option<OidcConfig>,
+    /// Decides how voters are named in the keys behind `GET /my-vote`, as it does for stored ballots.
+    pub anonymity: Anonymity,
+    pub telemetry: TelemetryConfig,
+}
+
+
+/// Docker sets the hostname to the container id, but does not always export `HOSTNAME` to the process.
+fn hostname() -> String
+{
+    std::env::var("HOSTNAME").ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|hostname| hostname.trim().to_owned())
+        .filter(|hostname| !hostname.is_empty())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+
+impl Config
+{
+    pub fn load() -> Result<Config, ConfigError>
+    {
+        let mut loader = ConfigLoader::load();
+        let bind = loader.optional("bind", SocketAddr::from(([0, 0, 0, 0], 8080)));
+        let instance_id = loader.optional("instance_id", hostname());
+        // RedisActor wants host:port, the worker a redis:// url; accept both so one config file serves both.
+        let redis_addr = loader.required::<String>("redis_addr")
+            .map(|redis_addr| redis_addr.trim_start_matches("redis://").to_owned());
+        let redis_key: String = loader.optional("redis_key", "votes".to_owned());
+        let redis_audit_key: String = loader.optional("redis_audit_key", "audit_events".to_owned());
+        let vote_rate_limit_ms = loader.optional("vote_rate_limit_ms", 1000);
+        let poll = Poll::load(&mut loader);
+        let poll_private = loader.optional("poll_private", false);
+        let admin_token: Option<String> = loader.get("admin_token");
+        let oidc = OidcConfig::load(&mut loader);
+        let voters = VoterRegistry::load(&mut loader, oidc.is_some());
+        let anonymity = Anonymity::load(&mut loader);
+        let telemetry = TelemetryConfig::load(&mut loader);
+
+        if let Some(redis_addr) = &redis_addr
+        {
+            loader.check(!redis_addr.is_empty() && !redis_addr.contains("://"), ||
+                format!("redis_addr must be host:port or a redis:// url, got '{}'", redis_addr));
+        }
+        loader.check(!redis_key.is_empty(), || "redis_key must not be empty".to_owned());
+        loader.check(!redis_audit_key.is_empty() && redis_audit_key != redis_key, ||
+            "redis_audit_key must be set and differ from redis_key".to_owned());
+        loader.check(vote_rate_limit_ms > 0, || "vote_rate_limit_ms must be positive".to_owned());
+        loader.check(!matches!(&admin_token, Some(token) if token.len() < 16), ||
+            "admin_token must be at least 16 characters long".to_owned());
+        loader.finish()?;
+
+        Ok(Config
+        {
+            bind,
+            instance_id,
+            redis_addr: redis_addr.unwrap(),
+            redis_key,
+            redis_audit_key,
+            vote_rate_limit_ms,
+            poll,
+            poll_private,
+            admin_token,
+            voters,
+            oidc,
+            anonymity,
+            telemetry,
+        })
+    }
+}
@@ -0,0 +1,86 @@
+use actix_web::{error, dev::HttpResponseBuilder, http::StatusCode, HttpResponse};
+use derive_more::{Display, Error};
+use serde::Serialize;
+
+
+#[derive(Debug, Display, Error)]
+pub enum VoteError
+{
+    #[display(fmt = "Unauthorized")]
+    Unauthorized,
+    #[display(fmt = "Please log in to vote")]
+    LoginRequired,
+    #[display(fmt = "Login failed")]
+    LoginFailed,
+    #[display(fmt = "This poll is private, a valid invitation is required")]
+    NotInvited,
+    #[display(fmt = "Invalid input: {}", reason)]
+    InvalidInput { reason: String },
+    #[display(fmt = "Unknown poll")]
+    UnknownPoll,
+    #[display(fmt = "Unknown vote, or its status has expired")]
+    UnknownVote,
+    #[display(fmt = "Poll is not open yet")]
+    PollNotOpen,
+    #[display(fmt = "Poll is closed")]
+    PollClosed,
+    #[display(fmt = "Too many votes, try again later")]
+    RateLimited,
+    #[display(fmt = "Vote storage is unavailable")]
+    BackendUnavailable,
+}
+
+
+#[derive(Serialize)]
+struct ErrorResponse
+{
+    code: &'static str,
+    message: String,
+}
+
+
+impl VoteError
+{
+    pub fn code(&self) -> &'static str
+    {
+        match self
+        {
+            VoteError::Unauthorized => "unauthorized",
+            VoteError::LoginRequired => "login_required",
+            VoteError::LoginFailed => "login_failed",
+            VoteError::NotInvited => "not_invited",
+            VoteError::InvalidInput { .. } => "invalid_input",
+            VoteError::UnknownPoll => "unknown_poll",
+            VoteError::UnknownVote => "unknown_vote",
+            VoteError::PollNotOpen => "poll_not_open",
+            VoteError::PollClosed => "poll_closed",
+            VoteError::RateLimited => "rate_limited",
+            VoteError::BackendUnavailable => "backend_unavailable",
+        }
+    }
+}
+
+
+impl error::ResponseError for VoteError
+{
+    fn error_response(&self) -> HttpResponse
+    {
+        HttpResponseBuilder::new(self.status_code())
+            .json(ErrorResponse { code: self.code(), message: self.to_string() })
+    }
+
+    fn status_code(&self) -> StatusCode
+    {
+        match self
+        {
+            VoteError::Unauthorized | VoteError::LoginRequired => StatusCode::UNAUTHORIZED,
+            VoteError::LoginFailed => StatusCode::BAD_REQUEST,
+            VoteError::NotInvited => StatusCode::FORBIDDEN,
+            VoteError::InvalidInput { .. } => StatusCode::BAD_REQUEST,
+            VoteError::UnknownPoll | VoteError::UnknownVote => StatusCode::NOT_FOUND,
+            VoteError::PollNotOpen | VoteError::PollClosed => StatusCode::CONFLICT,
+            VoteError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            VoteError::BackendUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
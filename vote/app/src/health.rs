@@ -0,0 +1,35 @@
+use actix_web::{web, HttpResponse};
+use actix::prelude::*;
+use actix_redis::RedisActor;
+use redis_async::{resp::RespValue, resp_array};
+use serde::Serialize;
+use common::connection::{ConnectionState, ConnectionStatus};
+
+use crate::send_command;
+
+
+#[derive(Serialize)]
+struct HealthReport
+{
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redis: Option<ConnectionState>,
+}
+
+
+pub async fn healthz() -> HttpResponse
+{
+    HttpResponse::Ok().json(HealthReport { status: "ok", redis: None })
+}
+
+
+pub async fn readyz(redis: web::Data<Addr<RedisActor>>, redis_status: web::Data<ConnectionStatus>) -> HttpResponse
+{
+    match send_command(&redis, &redis_status, resp_array!["PING"]).await
+    {
+        Ok(RespValue::SimpleString(ref pong)) if pong == "PONG" =>
+            HttpResponse::Ok().json(HealthReport { status: "ready", redis: Some(redis_status.state()) }),
+        _ => HttpResponse::ServiceUnavailable()
+            .json(HealthReport { status: "unavailable", redis: Some(redis_status.state()) }),
+    }
+}
@@ -0,0 +1,6 @@
+//! Parts of the vote app that stand on their own, built as a library so they can be tested from `tests/`.
+
+pub mod ballot;
+pub mod error;
+pub mod oidc;
+pub mod registry;
@@ -1,95 +1,254 @@
-use actix_web::
-    {
-        HttpServer, App, web, HttpResponse, HttpRequest, HttpMessage, error,
-        dev::HttpResponseBuilder, http::header, http::StatusCode, middleware,
-        Error as AWError,
-    };
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use actix_web::{HttpServer, App, web, HttpResponse, HttpRequest, HttpMessage, middleware, dev::Service};
+use actix_web::http::{HeaderName, HeaderValue};
 use actix_files::Files;
 use serde::{Deserialize, Serialize};
-use derive_more::{Display, Error};
 use actix_redis::{Command, RedisActor};
 use actix::prelude::*;
-use futures::future::join_all;
 use redis_async::{resp::RespValue, resp_array};
-use serde_json;
+use common::backoff::Backoff;
+use common::connection::ConnectionStatus;
+use common::ballot::Ballot;
+use common::bulletin;
+use common::poll::{PollStatus, VotingMethod};
+use tracing::Instrument;
+use uuid::Uuid;
+
+mod access;
+mod config;
+mod health;
+mod metrics;
+mod my_vote;
+mod poll;
+mod session;
+mod status;
+
+use vote_app::{ballot, error};
+use vote_app::oidc::OidcClient;
+
+use config::Config;
+use error::VoteError;
+
+
+/// Returned with every accepted vote; the same id is logged by the worker when it applies the vote.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+
+#[derive(Debug, Deserialize)]
+struct VoteRequest
+{
+    voter_id: String,
+    vote: Ballot,
+    /// Invitation token from the `/?token=` link, needed for private polls.
+    #[serde(default)]
+    token: Option<String>,
+}
+
+
+#[derive(Debug, Serialize)]
+struct QueuedVote
+{
+    request_id: String,
+    voter_id: String,
+    method: VotingMethod,
+    vote: Ballot,
+    /// Resolved from the voter registry, never taken from the request.
+    group: Option<String>,
+    weight: u32,
+    queued_at: u64,
+    poll_id: String,
+    /// Published on the poll's bulletin board; the nonce behind it only goes back to the voter.
+    commitment: String,
+}
 
 
-#[derive(Debug, Display, Error)]
-enum MyError
+/// Lets the voter find their ballot on the bulletin board and check it was not changed.
+#[derive(Debug, Serialize)]
+struct Receipt
 {
-    #[display(fmt = "Unauthorized")]
-    Unauthorized,
+    message: &'static str,
+    request_id: String,
+    poll_id: String,
+    commitment: String,
+    nonce: String,
+    /// The vote app instance that took the vote.
+    instance: String,
 }
 
 
-impl error::ResponseError for MyError
+/// Sends a command and records the outcome in `status`; reconnecting with backoff is left to `RedisActor`.
+async fn send_command(redis: &Addr<RedisActor>, status: &ConnectionStatus, command: RespValue)
+    -> Result<RespValue, VoteError>
 {
-    fn error_response(&self) -> HttpResponse
+    let response = match redis.send(Command(command)).await
     {
-        HttpResponseBuilder::new(self.status_code())
-            .set_header(header::CONTENT_TYPE, "text/html; charset=utf-8")
-            .body(self.to_string())
-    }
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) =>
+            {
+                status.record_failure(&e);
+                return Err(VoteError::BackendUnavailable);
+            },
+        Err(e) =>
+            {
+                status.record_failure(&e);
+                return Err(VoteError::BackendUnavailable);
+            },
+    };
+    status.record_success();
+    Ok(response)
+}
+
+
+async fn vote(
+        request: HttpRequest, vote_request: web::Json<VoteRequest>,
+        redis: web::Data<Addr<RedisActor>>, redis_status: web::Data<ConnectionStatus>, config: web::Data<Config>,
+    )
+    -> Result<HttpResponse, VoteError>
+{
+    let request_id = Uuid::new_v4().to_string();
+    let span = tracing::info_span!("vote", request_id = %request_id, voter_id = %vote_request.voter_id);
+    let result = register_vote(request, vote_request.into_inner(), &request_id, &redis, &redis_status, &config)
+        .instrument(span.clone())
+        .await;
+    let outcome = match &result
+    {
+        Ok(_) => "accepted",
+        Err(e) => e.code(),
+    };
+    span.in_scope(|| tracing::info!(outcome, "vote handled"));
+    metrics::observe_vote(outcome);
+    result.map(|mut response|
+        {
+            response.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), HeaderValue::from_str(&request_id).unwrap());
+            response
+        })
+}
+
 
-    fn status_code(&self) -> StatusCode
+/// The voter behind a request: the logged in user with OpenID Connect, otherwise the `voter_id` cookie.
+async fn authenticate(request: &HttpRequest, redis: &Addr<RedisActor>, redis_status: &ConnectionStatus, config: &Config)
+    -> Result<String, VoteError>
+{
+    if config.oidc.is_some()
+    {
+        match session::current(request, redis, redis_status).await?
+        {
+            Some(session) => Ok(session.voter_id),
+            None => Err(VoteError::LoginRequired),
+        }
+    }
+    else
     {
-        match *self
+        match request.cookie("voter_id")
         {
-            MyError::Unauthorized => StatusCode::UNAUTHORIZED,
+            Some(cookie) => Ok(cookie.value().to_owned()),
+            None => Err(VoteError::Unauthorized),
         }
     }
 }
 
 
-#[derive(Debug, Deserialize, Serialize)]
-struct VoteRequest
+async fn register_vote(
+        request: HttpRequest, vote_request: VoteRequest, request_id: &str,
+        redis: &Addr<RedisActor>, redis_status: &ConnectionStatus, config: &Config,
+    )
+    -> Result<HttpResponse, VoteError>
 {
-    voter_id: String,
-    vote: String,
+    if authenticate(&request, redis, redis_status, config).await? != vote_request.voter_id
+    {
+        return Err(VoteError::Unauthorized);
+    }
+    match config.poll.window.status()
+    {
+        PollStatus::NotOpen => return Err(VoteError::PollNotOpen),
+        PollStatus::Closed => return Err(VoteError::PollClosed),
+        PollStatus::Open => (),
+    }
+    ballot::validate(&config.poll, &vote_request.vote)?;
+    if config.poll_private
+    {
+        access::check_voter(
+                &vote_request.voter_id, vote_request.token.as_deref(), config.oidc.is_some(), redis, redis_status,
+            )
+            .await?;
+    }
+
+    let rate_key = format!("vote_rate:{}", vote_request.voter_id);
+    let throttle = send_command(redis, redis_status, resp_array!["SET", rate_key, "1", "PX", config.vote_rate_limit_ms.to_string(), "NX"]).await?;
+    if let RespValue::Nil = throttle
+    {
+        return Err(VoteError::RateLimited);
+    }
+
+    let queued_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_millis() as u64).unwrap_or(0);
+    let voter = config.voters.resolve(&vote_request.voter_id);
+    let nonce = format!("{}{}", Uuid::new_v4().to_simple(), Uuid::new_v4().to_simple());
+    let commitment = bulletin::commitment(&vote_request.vote, &nonce);
+    // Before the vote is queued, so the worker cannot mark it applied first and be overwritten.
+    my_vote::record_pending(redis, redis_status, config, &vote_request.voter_id, request_id, &vote_request.vote).await?;
+    let queued_vote = QueuedVote
+    {
+        request_id: request_id.to_owned(),
+        voter_id: vote_request.voter_id,
+        method: config.poll.method,
+        vote: vote_request.vote,
+        group: voter.group.map(str::to_owned),
+        weight: voter.weight,
+        queued_at,
+        poll_id: config.poll.id.clone(),
+        commitment: commitment.clone(),
+    };
+    let voter_id = queued_vote.voter_id.clone();
+    let vote = serde_json::to_string(&queued_vote).unwrap();
+    let pushed = match status::record_queued(redis, redis_status, request_id).await
+    {
+        Ok(()) => send_command(redis, redis_status, resp_array!["RPUSH", &config.redis_key, vote]).await,
+        Err(e) => Err(e),
+    };
+    match pushed
+    {
+        Ok(RespValue::Integer(_)) => Ok(HttpResponse::Ok().json(Receipt
+            {
+                message: "Your vote was registered.",
+                request_id: request_id.to_owned(),
+                poll_id: config.poll.id.clone(),
+                commitment,
+                nonce,
+                instance: config.instance_id.clone(),
+            })),
+        pushed =>
+            {
+                // The vote is not in the queue, so `GET /my-vote` must stop reporting it pending.
+                if let Err(e) = my_vote::clear_pending(redis, redis_status, config, &voter_id, request_id).await
+                {
+                    tracing::warn!(error = %e, "could not clear the pending vote");
+                }
+                Err(pushed.err().unwrap_or(VoteError::BackendUnavailable))
+            },
+    }
 }
 
 
-async fn vote(request: HttpRequest, vote_request: web::Json<VoteRequest>, redis: web::Data<Addr<RedisActor>>)
-    -> Result<HttpResponse, MyError>
+/// Reads the identity provider's configuration, retrying until it answers: votes cannot be taken without it.
+async fn discover(oidc_config: &vote_app::oidc::OidcConfig) -> OidcClient
 {
-    if let Ok(cookies) = request.cookies()
+    let mut backoff = Backoff::default();
+    loop
     {
-        for cookie in cookies.iter()
+        match OidcClient::discover(oidc_config.clone()).await
         {
-            if cookie.name() =="voter_id" && cookie.value() == vote_request.voter_id
-            {
-                let vote = serde_json::to_string(&vote_request.into_inner()).unwrap();
-                let cmd = redis.send(Command(resp_array!["RPUSH", "votes", vote]));
-                let res: Vec<Result<RespValue, AWError>> =
-                    join_all(vec![cmd].into_iter())
-                        .await
-                        .into_iter()
-                        .map(|item|
-                            {
-                                item.map_err(AWError::from)
-                                    .and_then(|res| res.map_err(AWError::from))
-                            })
-                        .collect();
-
-                return if !res.iter().all(|res| match res
-                    {
-                        Ok(RespValue::Integer(_)) => true,
-                        _ => false,
-                    })
+            Ok(client) =>
                 {
-                    Err(MyError::Unauthorized)
-                }
-                else
+                    tracing::info!(issuer = %oidc_config.issuer, "identity provider discovered");
+                    return client;
+                },
+            Err(e) =>
                 {
-                    Ok(HttpResponse::Ok().body("Your vote was registered."))
-                }
-            }
+                    let delay = backoff.next_delay();
+                    tracing::warn!(error = %e, retry_in_ms = delay.as_millis() as u64, "identity provider discovery failed");
+                    actix_web::rt::time::delay_for(delay).await;
+                },
         }
-        Err(MyError::Unauthorized)
-    }
-    else
-    {
-        Err(MyError::Unauthorized)
     }
 }
 
@@ -97,25 +256,73 @@ async fn vote(request: HttpRequest, vote_request: web::Json<VoteRequest>, redis:
 #[actix_web::main]
 async fn main() -> std::io::Result<()>
 {
-    std::env::set_var("RUST_LOG", "actix_web=trace,actix_redis=trace");
-    env_logger::init();
-
-    let bind = "0.0.0.0:8080";
-    println!("Starting server at: {}", &bind);
+    let config = match Config::load()
+    {
+        Ok(config) => web::Data::new(config),
+        Err(e) =>
+            {
+                eprintln!("{}", e);
+                std::process::exit(common::config::EXIT_INVALID_CONFIG);
+            },
+    };
+    let _telemetry = common::telemetry::init("vote", &config.telemetry);
+    let bind = config.bind;
+    tracing::info!(%bind, "starting server");
 
-    dotenv::dotenv().ok();
-    let redis_addr = std::env::var("REDIS_ADDR").expect("REDIS_ADDR must be set");
+    let redis_status = web::Data::new(ConnectionStatus::new("redis"));
+    let oidc = match &config.oidc
+    {
+        Some(oidc_config) => Some(web::Data::new(discover(oidc_config).await)),
+        None => None,
+    };
 
     HttpServer::new(move ||
         {
-            let redis_addr = RedisActor::start(&redis_addr);
+            let redis_addr = RedisActor::start(&config.redis_addr);
             App::new()
                 .data(redis_addr)
+                .app_data(redis_status.clone())
+                .app_data(config.clone())
+                .app_data(web::JsonConfig::default().error_handler(|err, _|
+                    {
+                        VoteError::InvalidInput { reason: err.to_string() }.into()
+                    }))
                 .wrap(middleware::Logger::default())
+                .wrap_fn(|request, service|
+                    {
+                        let started = Instant::now();
+                        let response = service.call(request);
+                        async move
+                        {
+                            let response = response.await?;
+                            metrics::observe_request(&response, started);
+                            Ok(response)
+                        }
+                    })
                 .route("/", web::post().to(vote))
+                .route("/poll", web::get().to(poll::definition))
+                .route("/my-vote", web::get().to(my_vote::my_vote))
+                .route("/votes/{request_id}/status", web::get().to(status::vote_status))
+                .configure(|service_config|
+                    {
+                        if let Some(oidc) = &oidc
+                        {
+                            service_config
+                                .app_data(oidc.clone())
+                                .route("/login", web::get().to(session::login))
+                                .route("/auth/callback", web::get().to(session::callback))
+                                .route("/logout", web::post().to(session::logout))
+                                .route("/me", web::get().to(session::me));
+                        }
+                    })
+                .route("/admin/invitations", web::post().to(access::create_invitations))
+                .route("/admin/voters", web::put().to(access::replace_allowlist))
+                .route("/metrics", web::get().to(metrics::metrics))
+                .route("/healthz", web::get().to(health::healthz))
+                .route("/readyz", web::get().to(health::readyz))
                 .service(Files::new("", "./web_layout").index_file("index.html"))
         })
-    .bind(&bind)?
+    .bind(bind)?
     .run()
     .await
 }
@@ -0,0 +1,51 @@
+use std::time::Instant;
+use actix_web::{dev::ServiceResponse, HttpResponse};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, Encoder, HistogramVec, IntCounterVec, TextEncoder,
+};
+
+
+lazy_static!
+{
+    static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "vote_http_requests_total", "HTTP requests handled by the vote app", &["method", "path", "status"]
+    ).unwrap();
+    static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "vote_http_request_duration_seconds", "HTTP request latency of the vote app", &["method", "path"]
+    ).unwrap();
+    static ref VOTES: IntCounterVec = register_int_counter_vec!(
+        "vote_votes_total", "Votes submitted to the vote app by outcome", &["outcome"]
+    ).unwrap();
+}
+
+
+pub fn observe_request<B>(response: &ServiceResponse<B>, started: Instant)
+{
+    let request = response.request();
+    let method = request.method().as_str();
+    let path = request.match_pattern().unwrap_or_else(|| "static".to_owned());
+    HTTP_REQUESTS
+        .with_label_values(&[method, &path, response.status().as_str()])
+        .inc();
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[method, &path])
+        .observe(started.elapsed().as_secs_f64());
+}
+
+
+pub fn observe_vote(outcome: &str)
+{
+    VOTES.with_label_values(&[outcome]).inc();
+}
+
+
+pub async fn metrics() -> HttpResponse
+{
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    encoder.encode(&prometheus::gather(), &mut buffer).unwrap();
+    HttpResponse::Ok()
+        .content_type(encoder.format_type())
+        .body(buffer)
+}
@@ -0,0 +1,137 @@
+use actix::prelude::*;
+use actix_redis::RedisActor;
+use actix_web::{web, HttpRequest, HttpResponse};
+use redis_async::{resp::RespValue, resp_array};
+use serde::{Deserialize, Serialize};
+use common::anonymity::AnonymityMode;
+use common::ballot::Ballot;
+use common::connection::ConnectionStatus;
+use common::poll;
+
+use crate::config::Config;
+use crate::error::VoteError;
+use crate::{authenticate, send_command};
+
+
+/// Notes the vote as queued for `GET /my-vote`. The worker sets `applied_request_id`, and `vote` when the ballot
+/// was stored, once it has dealt with it. Strict anonymity keeps no record of who cast which ballot, so nothing
+/// is written. The hash and its expiry are set together, so the record cannot be left without one.
+pub async fn record_pending(
+        redis: &Addr<RedisActor>, redis_status: &ConnectionStatus, config: &Config,
+        voter_id: &str, request_id: &str, ballot: &Ballot,
+    )
+    -> Result<(), VoteError>
+{
+    if config.anonymity.mode == AnonymityMode::Strict
+    {
+        return Ok(());
+    }
+    let key = poll::choice_key(&config.poll.id, &config.anonymity.voter_label(voter_id));
+    let ballot = serde_json::to_string(ballot).unwrap();
+    let script = "redis.call('HSET', KEYS[1], 'pending_request_id', ARGV[1], 'pending_vote', ARGV[2]) \
+        return redis.call('EXPIRE', KEYS[1], ARGV[3])";
+    send_command(redis, redis_status,
+        resp_array!["EVAL", script, "1", key, request_id, ballot, poll::CHOICE_TTL_SECS.to_string()]).await?;
+    Ok(())
+}
+
+
+/// Takes back `record_pending` for a vote that did not make it into the queue, unless a later vote replaced it.
+pub async fn clear_pending(
+        redis: &Addr<RedisActor>, redis_status: &ConnectionStatus, config: &Config, voter_id: &str, request_id: &str,
+    )
+    -> Result<(), VoteError>
+{
+    if config.anonymity.mode == AnonymityMode::Strict
+    {
+        return Ok(());
+    }
+    let key = poll::choice_key(&config.poll.id, &config.anonymity.voter_label(voter_id));
+    send_command(redis, redis_status, resp_array!["EVAL", poll::CLEAR_PENDING_SCRIPT, "1", key, request_id]).await?;
+    Ok(())
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct MyVoteQuery
+{
+    poll: String,
+}
+
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ChoiceState
+{
+    /// No vote from this voter has been stored or queued.
+    None,
+    /// The latest vote is still in the queue; `vote` is what was queued.
+    Pending,
+    /// `vote` is the ballot stored for the voter.
+    Recorded,
+    /// Under strict anonymity nobody, the voter included, can look up a ballot by voter.
+    Hidden,
+}
+
+
+#[derive(Debug, Serialize)]
+struct MyVote
+{
+    poll_id: String,
+    state: ChoiceState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vote: Option<Ballot>,
+}
+
+
+fn field(fields: &[RespValue], index: usize) -> Option<&[u8]>
+{
+    match fields.get(index)
+    {
+        Some(RespValue::BulkString(value)) => Some(value),
+        _ => None,
+    }
+}
+
+
+/// `GET /my-vote?poll=<id>`: what the voter currently has recorded in the poll, or what is still queued.
+pub async fn my_vote(
+        request: HttpRequest, query: web::Query<MyVoteQuery>,
+        redis: web::Data<Addr<RedisActor>>, redis_status: web::Data<ConnectionStatus>, config: web::Data<Config>,
+    )
+    -> Result<HttpResponse, VoteError>
+{
+    if query.poll != config.poll.id
+    {
+        return Err(VoteError::UnknownPoll);
+    }
+    let voter_id = authenticate(&request, &redis, &redis_status, &config).await?;
+    let poll_id = config.poll.id.clone();
+    if config.anonymity.mode == AnonymityMode::Strict
+    {
+        return Ok(HttpResponse::Ok().json(MyVote { poll_id, state: ChoiceState::Hidden, vote: None }));
+    }
+
+    let key = poll::choice_key(&poll_id, &config.anonymity.voter_label(&voter_id));
+    let fields = match send_command(&redis, &redis_status,
+        resp_array!["HMGET", key, "pending_request_id", "pending_vote", "applied_request_id", "vote"]).await?
+    {
+        RespValue::Array(fields) => fields,
+        _ => return Err(VoteError::BackendUnavailable),
+    };
+    let ballot = |index| field(&fields, index).and_then(|ballot| serde_json::from_slice::<Ballot>(ballot).ok());
+    let pending = field(&fields, 0);
+    let (state, vote) = if pending.is_some() && pending != field(&fields, 2)
+    {
+        (ChoiceState::Pending, ballot(1))
+    }
+    else
+    {
+        match ballot(3)
+        {
+            Some(vote) => (ChoiceState::Recorded, Some(vote)),
+            None => (ChoiceState::None, None),
+        }
+    };
+    Ok(HttpResponse::Ok().json(MyVote { poll_id, state, vote }))
+}
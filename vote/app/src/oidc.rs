@@ -0,0 +1,371 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use common::config::ConfigLoader;
+
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Tokens naming a key the JWKS lacks refetch it at most this often, so they cannot make every request reach
+/// the provider.
+const KEYS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+
+/// The `[oidc]` table. Login is enabled by setting `issuer`; the vote app then takes `voter_id` from the `sub`
+/// claim of the ID token instead of the anonymous cookie.
+#[derive(Debug, Clone)]
+pub struct OidcConfig
+{
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// Where the provider sends the browser back to, i.e. `<public url>/auth/callback`.
+    pub redirect_url: String,
+    pub scopes: String,
+    /// Claim that grants the admin role when it equals, or is a list containing, `admin_value`.
+    pub admin_claim: Option<String>,
+    pub admin_value: String,
+    pub session_ttl_secs: u64,
+}
+
+
+impl OidcConfig
+{
+    pub fn load(loader: &mut ConfigLoader) -> Option<OidcConfig>
+    {
+        let issuer: String = loader.get("oidc_issuer")?;
+        let client_id = loader.required::<String>("oidc_client_id");
+        let client_secret = loader.required::<String>("oidc_client_secret");
+        let redirect_url = loader.required::<String>("oidc_redirect_url");
+        let scopes = loader.optional("oidc_scopes", "openid profile email".to_owned());
+        let admin_claim: Option<String> = loader.get("oidc_admin_claim");
+        let admin_value = loader.optional("oidc_admin_value", "admin".to_owned());
+        let session_ttl_secs = loader.optional("oidc_session_ttl_secs", 8 * 60 * 60);
+
+        loader.check(url::Url::parse(&issuer).is_ok(), || format!("oidc_issuer must be a url, got '{}'", issuer));
+        if let Some(redirect_url) = &redirect_url
+        {
+            loader.check(url::Url::parse(redirect_url).is_ok(), ||
+                format!("oidc_redirect_url must be a url, got '{}'", redirect_url));
+        }
+        loader.check(scopes.split_whitespace().any(|scope| scope == "openid"), ||
+            "oidc_scopes must include 'openid'".to_owned());
+        loader.check(session_ttl_secs > 0, || "oidc_session_ttl_secs must be positive".to_owned());
+
+        Some(OidcConfig
+        {
+            issuer: issuer.trim_end_matches('/').to_owned(),
+            client_id: client_id?,
+            client_secret: client_secret?,
+            redirect_url: redirect_url?,
+            scopes,
+            admin_claim,
+            admin_value,
+            session_ttl_secs,
+        })
+    }
+}
+
+
+#[derive(Debug)]
+pub enum OidcError
+{
+    /// The provider could not be reached or answered with an error status.
+    Provider(String),
+    /// The provider answered with something that is not a valid OpenID Connect response.
+    InvalidResponse(String),
+    /// The ID token failed validation.
+    InvalidToken(String),
+}
+
+
+impl fmt::Display for OidcError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            OidcError::Provider(reason) => write!(f, "identity provider error: {}", reason),
+            OidcError::InvalidResponse(reason) => write!(f, "invalid identity provider response: {}", reason),
+            OidcError::InvalidToken(reason) => write!(f, "invalid ID token: {}", reason),
+        }
+    }
+}
+
+
+impl std::error::Error for OidcError {}
+
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata
+{
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+struct Jwk
+{
+    kty: String,
+    #[serde(default)]
+    kid: Option<String>,
+    #[serde(default)]
+    n: Option<String>,
+    #[serde(default)]
+    e: Option<String>,
+}
+
+
+#[derive(Deserialize)]
+struct JwkSet
+{
+    keys: Vec<Jwk>,
+}
+
+
+#[derive(Deserialize)]
+struct TokenResponse
+{
+    id_token: String,
+}
+
+
+/// What the browser is sent to the provider with, and what has to be kept until it comes back.
+#[derive(Debug, Clone)]
+pub struct AuthorizationRequest
+{
+    pub url: String,
+    pub login: PendingLogin,
+}
+
+
+/// Kept server side between `/login` and the callback, keyed by `state`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize)]
+pub struct PendingLogin
+{
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity
+{
+    pub subject: String,
+    pub is_admin: bool,
+}
+
+
+/// An OpenID Connect relying party using the authorization code flow with PKCE.
+pub struct OidcClient
+{
+    config: OidcConfig,
+    provider: ProviderMetadata,
+    authorization_endpoint: url::Url,
+    keys: RwLock<Vec<Jwk>>,
+    /// When a token last made the client refetch the JWKS.
+    keys_refreshed_at: Mutex<Option<Instant>>,
+}
+
+
+fn random_token() -> String
+{
+    format!("{}{}", Uuid::new_v4().to_simple(), Uuid::new_v4().to_simple())
+}
+
+
+fn code_challenge(code_verifier: &str) -> String
+{
+    base64::encode_config(Sha256::digest(code_verifier.as_bytes()), base64::URL_SAFE_NO_PAD)
+}
+
+
+async fn get_json<T>(url: &str) -> Result<T, OidcError>
+    where T: serde::de::DeserializeOwned
+{
+    let mut response = awc::Client::default().get(url).timeout(REQUEST_TIMEOUT).send().await
+        .map_err(|e| OidcError::Provider(format!("GET {}: {}", url, e)))?;
+    if !response.status().is_success()
+    {
+        return Err(OidcError::Provider(format!("GET {}: status {}", url, response.status())));
+    }
+    response.json::<T>().await.map_err(|e| OidcError::InvalidResponse(format!("GET {}: {}", url, e)))
+}
+
+
+impl OidcClient
+{
+    /// Reads the provider's discovery document and signing keys.
+    pub async fn discover(config: OidcConfig) -> Result<OidcClient, OidcError>
+    {
+        let url = format!("{}/.well-known/openid-configuration", config.issuer);
+        let provider: ProviderMetadata = get_json(&url).await?;
+        if provider.issuer.trim_end_matches('/') != config.issuer
+        {
+            return Err(OidcError::InvalidResponse(
+                format!("discovery document is for issuer '{}', expected '{}'", provider.issuer, config.issuer)));
+        }
+        let authorization_endpoint = url::Url::parse(&provider.authorization_endpoint)
+            .map_err(|e| OidcError::InvalidResponse(format!("authorization_endpoint: {}", e)))?;
+        let keys: JwkSet = get_json(&provider.jwks_uri).await?;
+        Ok(OidcClient { config, provider, authorization_endpoint, keys: RwLock::new(keys.keys), keys_refreshed_at: Mutex::new(None) })
+    }
+
+
+    pub fn config(&self) -> &OidcConfig
+    {
+        &self.config
+    }
+
+
+    pub fn provider(&self) -> &ProviderMetadata
+    {
+        &self.provider
+    }
+
+
+    pub fn authorization_request(&self) -> AuthorizationRequest
+    {
+        let login = PendingLogin { state: random_token(), nonce: random_token(), code_verifier: random_token() };
+        let mut url = self.authorization_endpoint.clone();
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_url)
+            .append_pair("scope", &self.config.scopes)
+            .append_pair("state", &login.state)
+            .append_pair("nonce", &login.nonce)
+            .append_pair("code_challenge", &code_challenge(&login.code_verifier))
+            .append_pair("code_challenge_method", "S256");
+        AuthorizationRequest { url: url.into(), login }
+    }
+
+
+    /// Trades the authorization code for an ID token and validates it against the login it answers.
+    pub async fn exchange(&self, code: &str, login: &PendingLogin) -> Result<Identity, OidcError>
+    {
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.config.redirect_url),
+            ("client_id", &self.config.client_id),
+            ("client_secret", &self.config.client_secret),
+            ("code_verifier", &login.code_verifier),
+        ];
+        let url = &self.provider.token_endpoint;
+        let mut response = awc::Client::default().post(url).timeout(REQUEST_TIMEOUT).send_form(&form).await
+            .map_err(|e| OidcError::Provider(format!("POST {}: {}", url, e)))?;
+        if !response.status().is_success()
+        {
+            let body = response.body().await.unwrap_or_default();
+            return Err(OidcError::Provider(
+                format!("POST {}: status {}: {}", url, response.status(), String::from_utf8_lossy(&body))));
+        }
+        let tokens = response.json::<TokenResponse>().await
+            .map_err(|e| OidcError::InvalidResponse(format!("POST {}: {}", url, e)))?;
+        self.verify(&tokens.id_token, &login.nonce).await
+    }
+
+
+    /// Checks the signature, issuer, audience, expiry and nonce of an ID token and maps its claims.
+    pub async fn verify(&self, id_token: &str, nonce: &str) -> Result<Identity, OidcError>
+    {
+        let header = jsonwebtoken::decode_header(id_token).map_err(|e| OidcError::InvalidToken(e.to_string()))?;
+        let claims = match header.alg
+        {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 =>
+                self.decode(id_token, header.alg, &DecodingKey::from_secret(self.config.client_secret.as_bytes()))?,
+            Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 =>
+                {
+                    let (n, e) = match self.find_key(header.kid.as_deref())
+                    {
+                        Some(key) => key,
+                        None =>
+                            {
+                                // The provider may have rotated its keys since discovery.
+                                if !self.claim_keys_refresh()
+                                {
+                                    return Err(OidcError::InvalidToken("no matching signing key".to_owned()));
+                                }
+                                self.refresh_keys().await?;
+                                self.find_key(header.kid.as_deref())
+                                    .ok_or_else(|| OidcError::InvalidToken("no matching signing key".to_owned()))?
+                            },
+                    };
+                    self.decode(id_token, header.alg, &DecodingKey::from_rsa_components(&n, &e))?
+                },
+            alg => return Err(OidcError::InvalidToken(format!("unsupported algorithm {:?}", alg))),
+        };
+        if claims.get("nonce").and_then(Value::as_str) != Some(nonce)
+        {
+            return Err(OidcError::InvalidToken("nonce does not match the login".to_owned()));
+        }
+        let subject = match claims.get("sub").and_then(Value::as_str)
+        {
+            Some(subject) if !subject.is_empty() => subject.to_owned(),
+            _ => return Err(OidcError::InvalidToken("missing sub claim".to_owned())),
+        };
+        let is_admin = self.config.admin_claim.as_ref()
+            .and_then(|claim| claims.get(claim))
+            .map(|value| match value
+                {
+                    Value::String(value) => *value == self.config.admin_value,
+                    Value::Array(values) => values.iter().any(|value| value.as_str() == Some(&self.config.admin_value)),
+                    _ => false,
+                })
+            .unwrap_or(false);
+        Ok(Identity { subject, is_admin })
+    }
+
+
+    fn decode(&self, id_token: &str, alg: Algorithm, key: &DecodingKey) -> Result<Map<String, Value>, OidcError>
+    {
+        let mut validation = Validation::new(alg);
+        validation.iss = Some(self.provider.issuer.clone());
+        validation.aud = Some(std::iter::once(self.config.client_id.clone()).collect::<HashSet<_>>());
+        validation.leeway = 60;
+        jsonwebtoken::decode::<Map<String, Value>>(id_token, key, &validation)
+            .map(|token| token.claims)
+            .map_err(|e| OidcError::InvalidToken(e.to_string()))
+    }
+
+
+    fn find_key(&self, kid: Option<&str>) -> Option<(String, String)>
+    {
+        let keys = self.keys.read().unwrap();
+        keys.iter()
+            .filter(|key| key.kty == "RSA")
+            .find(|key| kid.is_none() || key.kid.as_deref() == kid)
+            .and_then(|key| Some((key.n.clone()?, key.e.clone()?)))
+    }
+
+
+    /// Whether the JWKS may be refetched now; a refetch that fails still counts.
+    fn claim_keys_refresh(&self) -> bool
+    {
+        let mut refreshed_at = self.keys_refreshed_at.lock().unwrap();
+        if matches!(*refreshed_at, Some(at) if at.elapsed() < KEYS_REFRESH_INTERVAL)
+        {
+            return false;
+        }
+        *refreshed_at = Some(Instant::now());
+        true
+    }
+
+
+    async fn refresh_keys(&self) -> Result<(), OidcError>
+    {
+        let keys: JwkSet = get_json(&self.provider.jwks_uri).await?;
+        *self.keys.write().unwrap() = keys.keys;
+        Ok(())
+    }
+}
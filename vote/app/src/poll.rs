@@ -0,0 +1,61 @@
+use actix_web::{web, HttpResponse};
+use std::collections::BTreeMap;
+use serde::Serialize;
+use common::poll::{PollStatus, PollTranslation, VotingMethod};
+
+use crate::config::Config;
+
+
+#[derive(Serialize)]
+struct PollOption<'a>
+{
+    id: &'a str,
+    label: &'a str,
+}
+
+
+/// Everything the vote page needs to render the poll, so that it carries no options of its own.
+#[derive(Serialize)]
+struct PollDefinition<'a>
+{
+    id: &'a str,
+    title: &'a str,
+    method: VotingMethod,
+    options: Vec<PollOption<'a>>,
+    /// Titles and labels by locale, for the page to pick from by the reader's language.
+    translations: &'a BTreeMap<String, PollTranslation>,
+    max_score: u32,
+    opens_at: Option<String>,
+    closes_at: Option<String>,
+    status: &'static str,
+    private: bool,
+    /// The vote app instance that answered, shown on the page.
+    instance: &'a str,
+}
+
+
+/// `GET /poll`: the poll being voted on.
+pub async fn definition(config: web::Data<Config>) -> HttpResponse
+{
+    let poll = &config.poll;
+    let status = match poll.window.status()
+    {
+        PollStatus::NotOpen => "not_open",
+        PollStatus::Open => "open",
+        PollStatus::Closed => "closed",
+    };
+    HttpResponse::Ok().json(PollDefinition
+    {
+        id: &poll.id,
+        title: &poll.title,
+        method: poll.method,
+        options: poll.options.iter().zip(&poll.labels).map(|(id, label)| PollOption { id, label }).collect(),
+        translations: &poll.translations,
+        max_score: poll.max_score,
+        opens_at: poll.window.opens_at.map(|at| at.to_rfc3339()),
+        closes_at: poll.window.closes_at.map(|at| at.to_rfc3339()),
+        status,
+        private: config.poll_private,
+        instance: &config.instance_id,
+    })
+}
@@ -0,0 +1,66 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::Deserialize;
+use common::config::ConfigLoader;
+
+
+#[derive(Deserialize)]
+struct RegisteredVoter
+{
+    id: String,
+    group: String,
+}
+
+
+/// Voter groups and their weights, read from the `[groups]` table and the `[[voters]]` list of the config file.
+/// Voters that are not listed belong to no group and count once. Voters are looked up by the id they vote
+/// under, which only login vouches for, so the registry needs OpenID Connect.
+#[derive(Debug, Default)]
+pub struct VoterRegistry
+{
+    weights: BTreeMap<String, u32>,
+    groups: HashMap<String, String>,
+}
+
+
+pub struct Voter<'a>
+{
+    pub group: Option<&'a str>,
+    pub weight: u32,
+}
+
+
+impl VoterRegistry
+{
+    /// `login` tells whether voters log in with OpenID Connect. Without it the voter id is whatever the client's
+    /// cookie says, and anyone knowing a listed voter's id could vote with their weight.
+    pub fn load(loader: &mut ConfigLoader, login: bool) -> VoterRegistry
+    {
+        let weights: BTreeMap<String, u32> = loader.section("groups").unwrap_or_default();
+        let voters: Vec<RegisteredVoter> = loader.section("voters").unwrap_or_default();
+        loader.check(login || (weights.is_empty() && voters.is_empty()), ||
+            "[groups] and [[voters]] need oidc_issuer: without login anyone can vote as a listed voter".to_owned());
+        for (group, weight) in &weights
+        {
+            loader.check(*weight > 0, || format!("groups.{} must have a positive weight", group));
+        }
+        let mut groups = HashMap::new();
+        for voter in voters
+        {
+            loader.check(weights.contains_key(&voter.group), ||
+                format!("voter '{}' belongs to unknown group '{}'", voter.id, voter.group));
+            loader.check(!groups.contains_key(&voter.id), || format!("voter '{}' is listed twice", voter.id));
+            groups.insert(voter.id, voter.group);
+        }
+        VoterRegistry { weights, groups }
+    }
+
+
+    pub fn resolve(&self, voter_id: &str) -> Voter<'_>
+    {
+        match self.groups.get(voter_id)
+        {
+            Some(group) => Voter { group: Some(group), weight: self.weights.get(group).copied().unwrap_or(1) },
+            None => Voter { group: None, weight: 1 },
+        }
+    }
+}
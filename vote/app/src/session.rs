@@ -0,0 +1,185 @@
+use actix::prelude::*;
+use actix_redis::RedisActor;
+use actix_web::{cookie::Cookie, http::header, web, HttpMessage, HttpRequest, HttpResponse};
+use redis_async::{resp::RespValue, resp_array};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use common::connection::ConnectionStatus;
+use vote_app::oidc::{OidcClient, PendingLogin};
+
+use crate::error::VoteError;
+use crate::send_command;
+
+
+const SESSION_COOKIE: &str = "session";
+/// Ties a login to the browser that started it, so a callback link from someone else's login is refused.
+const LOGIN_COOKIE: &str = "oidc_state";
+/// How long a user may take to log in at the provider.
+const LOGIN_TTL_SECS: u64 = 10 * 60;
+
+
+/// A logged in user, stored in redis under `session:<id>` with the id in an HttpOnly cookie.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Session
+{
+    pub voter_id: String,
+    pub is_admin: bool,
+}
+
+
+fn session_key(id: &str) -> String
+{
+    format!("session:{}", id)
+}
+
+
+fn login_key(state: &str) -> String
+{
+    format!("oidc_login:{}", state)
+}
+
+
+fn cookie<'c>(name: &'c str, value: String, oidc: &OidcClient, http_only: bool) -> Cookie<'c>
+{
+    Cookie::build(name, value)
+        .path("/")
+        .http_only(http_only)
+        .secure(oidc.config().redirect_url.starts_with("https://"))
+        .same_site(actix_web::cookie::SameSite::Lax)
+        .max_age(time::Duration::seconds(oidc.config().session_ttl_secs as i64))
+        .finish()
+}
+
+
+/// The session of the request, if it has a live one.
+pub async fn current(request: &HttpRequest, redis: &Addr<RedisActor>, redis_status: &ConnectionStatus)
+    -> Result<Option<Session>, VoteError>
+{
+    let id = match request.cookie(SESSION_COOKIE)
+    {
+        Some(cookie) => cookie.value().to_owned(),
+        None => return Ok(None),
+    };
+    match send_command(redis, redis_status, resp_array!["GET", session_key(&id)]).await?
+    {
+        RespValue::BulkString(session) => Ok(serde_json::from_slice(&session).ok()),
+        _ => Ok(None),
+    }
+}
+
+
+/// `GET /login`: sends the browser to the identity provider.
+pub async fn login(
+        oidc: web::Data<OidcClient>, redis: web::Data<Addr<RedisActor>>, redis_status: web::Data<ConnectionStatus>,
+    )
+    -> Result<HttpResponse, VoteError>
+{
+    let request = oidc.authorization_request();
+    let login = serde_json::to_string(&request.login).unwrap();
+    send_command(&redis, &redis_status,
+        resp_array!["SET", login_key(&request.login.state), login, "EX", LOGIN_TTL_SECS.to_string()]).await?;
+    let mut login_cookie = cookie(LOGIN_COOKIE, request.login.state, &oidc, true);
+    login_cookie.set_max_age(time::Duration::seconds(LOGIN_TTL_SECS as i64));
+    Ok(HttpResponse::Found().header(header::LOCATION, request.url).cookie(login_cookie).finish())
+}
+
+
+#[derive(Debug, Deserialize)]
+pub struct Callback
+{
+    state: String,
+    code: Option<String>,
+    error: Option<String>,
+}
+
+
+/// `GET /auth/callback`: redeems the authorization code, opens a session and goes back to the vote page.
+pub async fn callback(
+        request: HttpRequest, query: web::Query<Callback>,
+        oidc: web::Data<OidcClient>, redis: web::Data<Addr<RedisActor>>, redis_status: web::Data<ConnectionStatus>,
+    )
+    -> Result<HttpResponse, VoteError>
+{
+    if !matches!(request.cookie(LOGIN_COOKIE), Some(cookie) if cookie.value() == query.state)
+    {
+        tracing::warn!("login callback from a browser that did not start the login");
+        return Err(VoteError::LoginFailed);
+    }
+    // Read and dropped at once so a state can only be used one time.
+    let key = login_key(&query.state);
+    let login = send_command(&redis, &redis_status, resp_array!["GET", &key]).await?;
+    send_command(&redis, &redis_status, resp_array!["DEL", &key]).await?;
+    let login: PendingLogin = match login
+    {
+        RespValue::BulkString(login) => serde_json::from_slice(&login).map_err(|_| VoteError::LoginFailed)?,
+        _ =>
+            {
+                tracing::warn!("login callback with an unknown or expired state");
+                return Err(VoteError::LoginFailed);
+            },
+    };
+    let code = match (&query.code, &query.error)
+    {
+        (Some(code), None) => code,
+        (_, error) =>
+            {
+                tracing::warn!(error = error.as_deref().unwrap_or("missing code"), "identity provider refused the login");
+                return Err(VoteError::LoginFailed);
+            },
+    };
+    let identity = oidc.exchange(code, &login).await
+        .map_err(|e|
+            {
+                tracing::warn!(error = %e, "login failed");
+                VoteError::LoginFailed
+            })?;
+
+    let id = format!("{}{}", Uuid::new_v4().to_simple(), Uuid::new_v4().to_simple());
+    let session = Session { voter_id: identity.subject, is_admin: identity.is_admin };
+    let ttl = oidc.config().session_ttl_secs.to_string();
+    send_command(&redis, &redis_status,
+        resp_array!["SET", session_key(&id), serde_json::to_string(&session).unwrap(), "EX", ttl]).await?;
+    tracing::info!(voter_id = %session.voter_id, is_admin = session.is_admin, "logged in");
+
+    // The vote page reads its voter id from the `voter_id` cookie, so it follows the login without asking.
+    Ok(HttpResponse::Found()
+        .header(header::LOCATION, "/")
+        .cookie(cookie(SESSION_COOKIE, id, &oidc, true))
+        .cookie(cookie("voter_id", session.voter_id, &oidc, false))
+        .del_cookie(&Cookie::build(LOGIN_COOKIE, "").path("/").finish())
+        .finish())
+}
+
+
+/// `POST /logout`
+pub async fn logout(
+        request: HttpRequest,
+        redis: web::Data<Addr<RedisActor>>, redis_status: web::Data<ConnectionStatus>,
+    )
+    -> Result<HttpResponse, VoteError>
+{
+    if let Some(cookie) = request.cookie(SESSION_COOKIE)
+    {
+        send_command(&redis, &redis_status, resp_array!["DEL", session_key(cookie.value())]).await?;
+    }
+    Ok(HttpResponse::Found()
+        .header(header::LOCATION, "/")
+        .del_cookie(&Cookie::build(SESSION_COOKIE, "").path("/").finish())
+        .del_cookie(&Cookie::build("voter_id", "").path("/").finish())
+        .finish())
+}
+
+
+/// `GET /me`: who the session belongs to.
+pub async fn me(
+        request: HttpRequest,
+        redis: web::Data<Addr<RedisActor>>, redis_status: web::Data<ConnectionStatus>,
+    )
+    -> Result<HttpResponse, VoteError>
+{
+    match current(&request, &redis, &redis_status).await?
+    {
+        Some(session) => Ok(HttpResponse::Ok().json(session)),
+        None => Err(VoteError::LoginRequired),
+    }
+}
@@ -0,0 +1,41 @@
+use actix::prelude::*;
+use actix_redis::RedisActor;
+use actix_web::{web, HttpResponse};
+use redis_async::{resp::RespValue, resp_array};
+use common::connection::ConnectionStatus;
+use common::vote_status::{self, VoteState, VoteStatus};
+
+use crate::error::VoteError;
+use crate::send_command;
+
+
+/// Marks the vote queued; done before it is pushed, so the worker's outcome is never overwritten.
+pub async fn record_queued(redis: &Addr<RedisActor>, redis_status: &ConnectionStatus, request_id: &str)
+    -> Result<(), VoteError>
+{
+    let status = serde_json::to_string(&VoteStatus::new(VoteState::Queued)).unwrap();
+    send_command(redis, redis_status, resp_array![
+            "SET", vote_status::status_key(request_id), status, "EX", vote_status::STATUS_TTL_SECS.to_string()
+        ])
+        .await?;
+    Ok(())
+}
+
+
+/// `GET /votes/{request_id}/status`: whether the vote with this request id was stored yet. Request ids are
+/// random and only handed to the voter who cast the vote, and the status says nothing about the ballot.
+pub async fn vote_status(
+        path: web::Path<String>, redis: web::Data<Addr<RedisActor>>, redis_status: web::Data<ConnectionStatus>,
+    )
+    -> Result<HttpResponse, VoteError>
+{
+    match send_command(&redis, &redis_status, resp_array!["GET", vote_status::status_key(&path.into_inner())]).await?
+    {
+        RespValue::BulkString(status) => match serde_json::from_slice::<VoteStatus>(&status)
+            {
+                Ok(status) => Ok(HttpResponse::Ok().json(status)),
+                Err(_) => Err(VoteError::BackendUnavailable),
+            },
+        _ => Err(VoteError::UnknownVote),
+    }
+}
@@ -0,0 +1,110 @@
+use common::ballot::Ballot;
+use common::config::ConfigLoader;
+use common::poll::Poll;
+use vote_app::ballot::validate;
+use vote_app::error::VoteError;
+
+
+fn poll(method: &str) -> Poll
+{
+    let file = format!("[poll]\nmethod = \"{}\"\noptions = [\"a\", \"b\", \"c\"]\nmax_score = 5\n", method);
+    let mut loader = ConfigLoader::from_sources(Some(&file), Vec::new(), Vec::new());
+    let poll = Poll::load(&mut loader);
+    loader.finish().unwrap();
+    poll
+}
+
+
+fn single(option: &str) -> Ballot
+{
+    Ballot::Single(option.to_owned())
+}
+
+
+fn list(options: &[&str]) -> Ballot
+{
+    Ballot::List(options.iter().map(|option| option.to_string()).collect())
+}
+
+
+fn scores(scores: &[(&str, u32)]) -> Ballot
+{
+    Ballot::Scores(scores.iter().map(|(option, score)| (option.to_string(), *score)).collect())
+}
+
+
+/// The reason of an `InvalidInput`, so the tests show which check refused the ballot.
+fn reason(result: Result<(), VoteError>) -> String
+{
+    match result
+    {
+        Err(VoteError::InvalidInput { reason }) => reason,
+        Err(e) => panic!("expected invalid input, got {}", e),
+        Ok(()) => panic!("expected invalid input, the ballot was accepted"),
+    }
+}
+
+
+#[test]
+fn ballots_of_the_right_shape_are_accepted()
+{
+    assert!(validate(&poll("single"), &single("b")).is_ok());
+    assert!(validate(&poll("approval"), &list(&["a", "c"])).is_ok());
+    assert!(validate(&poll("ranked"), &list(&["c", "a", "b"])).is_ok());
+    assert!(validate(&poll("score"), &scores(&[("a", 0), ("b", 5)])).is_ok());
+}
+
+
+#[test]
+fn a_bare_option_counts_as_a_list_of_one()
+{
+    assert!(validate(&poll("approval"), &single("a")).is_ok());
+    assert!(validate(&poll("ranked"), &single("a")).is_ok());
+}
+
+
+#[test]
+fn unknown_options_are_refused()
+{
+    assert_eq!(reason(validate(&poll("single"), &single("d"))), "unknown vote option 'd'");
+    assert_eq!(reason(validate(&poll("approval"), &list(&["a", "d"]))), "unknown vote option 'd'");
+    assert_eq!(reason(validate(&poll("ranked"), &list(&["d"]))), "unknown vote option 'd'");
+    assert_eq!(reason(validate(&poll("score"), &scores(&[("d", 1)]))), "unknown vote option 'd'");
+}
+
+
+#[test]
+fn an_option_ranked_twice_is_refused()
+{
+    assert_eq!(reason(validate(&poll("ranked"), &list(&["a", "b", "a"]))), "vote option 'a' appears more than once");
+    assert_eq!(reason(validate(&poll("approval"), &list(&["c", "c"]))), "vote option 'c' appears more than once");
+}
+
+
+#[test]
+fn empty_ballots_are_refused()
+{
+    assert_eq!(reason(validate(&poll("approval"), &list(&[]))), "the ballot names no option");
+    assert_eq!(reason(validate(&poll("ranked"), &list(&[]))), "the ballot names no option");
+    assert_eq!(reason(validate(&poll("score"), &scores(&[]))), "the ballot names no option");
+}
+
+
+#[test]
+fn scores_above_the_maximum_are_refused()
+{
+    assert_eq!(reason(validate(&poll("score"), &scores(&[("a", 1), ("b", 6)]))),
+        "score 6 for 'b' is above the maximum of 5");
+}
+
+
+#[test]
+fn ballots_for_another_method_are_refused()
+{
+    assert_eq!(reason(validate(&poll("single"), &list(&["a"]))), "this ballot does not fit a single poll");
+    assert_eq!(reason(validate(&poll("single"), &scores(&[("a", 1)]))), "this ballot does not fit a single poll");
+    assert_eq!(reason(validate(&poll("approval"), &scores(&[("a", 1)]))), "this ballot does not fit a approval poll");
+    assert_eq!(reason(validate(&poll("ranked"), &scores(&[("a", 1)]))), "this ballot does not fit a ranked poll");
+    assert_eq!(reason(validate(&poll("score"), &single("a"))), "this ballot does not fit a score poll");
+    assert_eq!(reason(validate(&poll("score"), &list(&["a"]))), "this ballot does not fit a score poll");
+}
@@ -0,0 +1,242 @@
+//! A minimal OpenID Connect provider for the tests: discovery, JWKS, an authorization endpoint that logs in
+//! whoever is named in `login_hint`, and a token endpoint that checks the client secret and PKCE.
+
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::{dev::Server, http::header, web, App, HttpResponse, HttpServer};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+
+pub const CLIENT_ID: &str = "vote-app";
+pub const CLIENT_SECRET: &str = "vote-app-secret";
+pub const ADMIN_GROUP: &str = "poll-admins";
+
+const SIGNING_KEY: &[u8] = include_bytes!("../fixtures/mock_provider_key.pem");
+const OTHER_KEY: &[u8] = include_bytes!("../fixtures/other_key.pem");
+const JWKS: &str = include_str!("../fixtures/mock_provider_jwks.json");
+
+
+/// Ways the provider can misbehave, set before the step they affect.
+#[derive(Debug, Clone, Default)]
+pub struct Behaviour
+{
+    /// Issuer announced by the discovery document instead of the real one.
+    pub discovery_issuer: Option<String>,
+    /// Audience put in ID tokens instead of the client id.
+    pub audience: Option<String>,
+    pub expired: bool,
+    /// Sign ID tokens with a key that is not in the JWKS, under the published key id.
+    pub sign_with_other_key: bool,
+    /// Sign ID tokens with HS256 and the client secret.
+    pub sign_with_client_secret: bool,
+    /// Key id put in ID token headers instead of the published one.
+    pub key_id: Option<String>,
+}
+
+
+struct Grant
+{
+    user: String,
+    nonce: String,
+    code_challenge: String,
+    redirect_uri: String,
+}
+
+
+struct ProviderState
+{
+    issuer: String,
+    grants: Mutex<HashMap<String, Grant>>,
+    behaviour: Mutex<Behaviour>,
+    jwks_requests: AtomicUsize,
+}
+
+
+pub struct MockProvider
+{
+    pub issuer: String,
+    state: web::Data<ProviderState>,
+    server: Server,
+}
+
+
+impl MockProvider
+{
+    /// Starts the provider on a free local port, in the running actix system.
+    pub fn start() -> MockProvider
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let state = web::Data::new(ProviderState
+        {
+            issuer: issuer.clone(),
+            grants: Mutex::new(HashMap::new()),
+            behaviour: Mutex::new(Behaviour::default()),
+            jwks_requests: AtomicUsize::new(0),
+        });
+        let app_state = state.clone();
+        let server = HttpServer::new(move ||
+            {
+                App::new()
+                    .app_data(app_state.clone())
+                    .route("/.well-known/openid-configuration", web::get().to(discovery))
+                    .route("/jwks", web::get().to(jwks))
+                    .route("/authorize", web::get().to(authorize))
+                    .route("/token", web::post().to(token))
+            })
+            .workers(1)
+            .listen(listener)
+            .unwrap()
+            .run();
+        MockProvider { issuer, state, server }
+    }
+
+
+    pub fn behave(&self, change: impl FnOnce(&mut Behaviour))
+    {
+        change(&mut self.state.behaviour.lock().unwrap());
+    }
+
+
+    /// How many times the JWKS was fetched.
+    pub fn jwks_requests(&self) -> usize
+    {
+        self.state.jwks_requests.load(Ordering::SeqCst)
+    }
+
+
+    pub async fn stop(self)
+    {
+        self.server.stop(true).await;
+    }
+}
+
+
+async fn discovery(state: web::Data<ProviderState>) -> HttpResponse
+{
+    let issuer = state.behaviour.lock().unwrap().discovery_issuer.clone().unwrap_or_else(|| state.issuer.clone());
+    HttpResponse::Ok().json(json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{}/authorize", state.issuer),
+        "token_endpoint": format!("{}/token", state.issuer),
+        "jwks_uri": format!("{}/jwks", state.issuer),
+        "response_types_supported": ["code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": ["RS256"],
+        "code_challenge_methods_supported": ["S256"],
+    }))
+}
+
+
+async fn jwks(state: web::Data<ProviderState>) -> HttpResponse
+{
+    state.jwks_requests.fetch_add(1, Ordering::SeqCst);
+    HttpResponse::Ok().content_type("application/json").body(JWKS)
+}
+
+
+#[derive(Deserialize)]
+struct AuthorizeQuery
+{
+    response_type: String,
+    client_id: String,
+    redirect_uri: String,
+    scope: String,
+    state: String,
+    nonce: String,
+    code_challenge: String,
+    code_challenge_method: String,
+    login_hint: String,
+}
+
+
+async fn authorize(state: web::Data<ProviderState>, query: web::Query<AuthorizeQuery>) -> HttpResponse
+{
+    if query.response_type != "code" || query.client_id != CLIENT_ID || query.code_challenge_method != "S256"
+        || !query.scope.split(' ').any(|scope| scope == "openid")
+    {
+        return HttpResponse::BadRequest().body("invalid authorization request");
+    }
+    let code = Uuid::new_v4().to_simple().to_string();
+    state.grants.lock().unwrap().insert(code.clone(), Grant
+    {
+        user: query.login_hint.clone(),
+        nonce: query.nonce.clone(),
+        code_challenge: query.code_challenge.clone(),
+        redirect_uri: query.redirect_uri.clone(),
+    });
+    let mut location = url::Url::parse(&query.redirect_uri).unwrap();
+    location.query_pairs_mut().append_pair("code", &code).append_pair("state", &query.state);
+    HttpResponse::Found().header(header::LOCATION, location.as_str()).finish()
+}
+
+
+#[derive(Deserialize)]
+struct TokenForm
+{
+    grant_type: String,
+    code: String,
+    redirect_uri: String,
+    client_id: String,
+    client_secret: String,
+    code_verifier: String,
+}
+
+
+async fn token(state: web::Data<ProviderState>, form: web::Form<TokenForm>) -> HttpResponse
+{
+    if form.client_id != CLIENT_ID || form.client_secret != CLIENT_SECRET
+    {
+        return HttpResponse::Unauthorized().json(json!({ "error": "invalid_client" }));
+    }
+    // Codes are single use: taken out before anything else is checked.
+    let grant = match state.grants.lock().unwrap().remove(&form.code)
+    {
+        Some(grant) if form.grant_type == "authorization_code" && grant.redirect_uri == form.redirect_uri => grant,
+        _ => return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" })),
+    };
+    let challenge = base64::encode_config(Sha256::digest(form.code_verifier.as_bytes()), base64::URL_SAFE_NO_PAD);
+    if challenge != grant.code_challenge
+    {
+        return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant", "error_description": "PKCE" }));
+    }
+
+    let behaviour = state.behaviour.lock().unwrap().clone();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let expires_at = if behaviour.expired { now - 3600 } else { now + 300 };
+    let groups = if grant.user == "alice" { vec![ADMIN_GROUP, "staff"] } else { vec!["staff"] };
+    let claims = json!({
+        "iss": state.issuer,
+        "sub": grant.user,
+        "aud": behaviour.audience.unwrap_or_else(|| CLIENT_ID.to_owned()),
+        "iat": now,
+        "exp": expires_at,
+        "nonce": grant.nonce,
+        "groups": groups,
+        "role": if grant.user == "alice" { "admin" } else { "voter" },
+    });
+    let id_token = if behaviour.sign_with_client_secret
+    {
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &EncodingKey::from_secret(CLIENT_SECRET.as_bytes()))
+    }
+    else
+    {
+        let key = if behaviour.sign_with_other_key { OTHER_KEY } else { SIGNING_KEY };
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(behaviour.key_id.clone().unwrap_or_else(|| "mock-1".to_owned()));
+        jsonwebtoken::encode(&header, &claims, &EncodingKey::from_rsa_pem(key).unwrap())
+    };
+    HttpResponse::Ok().json(json!({
+        "access_token": Uuid::new_v4().to_simple().to_string(),
+        "token_type": "Bearer",
+        "expires_in": 300,
+        "id_token": id_token.unwrap(),
+    }))
+}
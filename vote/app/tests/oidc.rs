@@ -0,0 +1,238 @@
+use actix_web::http::header;
+use vote_app::oidc::{Identity, OidcClient, OidcConfig, OidcError, PendingLogin};
+
+mod mock_provider;
+
+use mock_provider::{MockProvider, ADMIN_GROUP, CLIENT_ID, CLIENT_SECRET};
+
+
+const REDIRECT_URL: &str = "http://vote.example/auth/callback";
+
+
+fn config(provider: &MockProvider) -> OidcConfig
+{
+    OidcConfig
+    {
+        issuer: provider.issuer.clone(),
+        client_id: CLIENT_ID.to_owned(),
+        client_secret: CLIENT_SECRET.to_owned(),
+        redirect_url: REDIRECT_URL.to_owned(),
+        scopes: "openid profile".to_owned(),
+        admin_claim: Some("groups".to_owned()),
+        admin_value: ADMIN_GROUP.to_owned(),
+        session_ttl_secs: 3600,
+    }
+}
+
+
+/// Plays the browser: opens the authorization url as `user` and returns the code from the redirect.
+async fn authorize(client: &OidcClient, user: &str) -> (String, PendingLogin)
+{
+    let request = client.authorization_request();
+    let url = format!("{}&login_hint={}", request.url, user);
+    let response = awc::Client::default().get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 302);
+    let location = response.headers().get(header::LOCATION).unwrap().to_str().unwrap();
+    assert!(location.starts_with(REDIRECT_URL), "redirected to {}", location);
+
+    let location = url::Url::parse(location).unwrap();
+    let query: Vec<(String, String)> = location.query_pairs().into_owned().collect();
+    let param = |name: &str| query.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone()).unwrap();
+    assert_eq!(param("state"), request.login.state);
+    (param("code"), request.login)
+}
+
+
+async fn login(client: &OidcClient, user: &str) -> Result<Identity, OidcError>
+{
+    let (code, login) = authorize(client, user).await;
+    client.exchange(&code, &login).await
+}
+
+
+fn assert_invalid_token(result: Result<Identity, OidcError>)
+{
+    match result
+    {
+        Err(OidcError::InvalidToken(_)) => (),
+        other => panic!("expected an invalid token error, got {:?}", other),
+    }
+}
+
+
+#[actix_rt::test]
+async fn discovery_reads_the_provider_metadata()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    assert_eq!(client.provider().issuer, provider.issuer);
+    assert_eq!(client.provider().token_endpoint, format!("{}/token", provider.issuer));
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn discovery_for_another_issuer_is_refused()
+{
+    let provider = MockProvider::start();
+    provider.behave(|behaviour| behaviour.discovery_issuer = Some("https://impostor.example".to_owned()));
+    match OidcClient::discover(config(&provider)).await
+    {
+        Err(OidcError::InvalidResponse(_)) => (),
+        Err(e) => panic!("expected an invalid response error, got {}", e),
+        Ok(_) => panic!("discovery should fail"),
+    }
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn authorization_requests_use_pkce_and_fresh_values()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    let first = client.authorization_request();
+    let second = client.authorization_request();
+    assert!(first.url.starts_with(&format!("{}/authorize?", provider.issuer)));
+    assert!(first.url.contains("code_challenge_method=S256"));
+    assert!(!first.url.contains(&first.login.code_verifier));
+    assert_ne!(first.login.state, second.login.state);
+    assert_ne!(first.login.nonce, second.login.nonce);
+    assert_ne!(first.login.code_verifier, second.login.code_verifier);
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn login_takes_the_voter_from_sub_and_the_admin_role_from_a_claim()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    assert_eq!(login(&client, "alice").await.unwrap(), Identity { subject: "alice".to_owned(), is_admin: true });
+    assert_eq!(login(&client, "bob").await.unwrap(), Identity { subject: "bob".to_owned(), is_admin: false });
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn admin_claim_may_be_a_plain_string()
+{
+    let provider = MockProvider::start();
+    let mut config = config(&provider);
+    config.admin_claim = Some("role".to_owned());
+    config.admin_value = "admin".to_owned();
+    let client = OidcClient::discover(config).await.unwrap();
+    assert!(login(&client, "alice").await.unwrap().is_admin);
+    assert!(!login(&client, "bob").await.unwrap().is_admin);
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn nobody_is_admin_without_an_admin_claim()
+{
+    let provider = MockProvider::start();
+    let mut config = config(&provider);
+    config.admin_claim = None;
+    let client = OidcClient::discover(config).await.unwrap();
+    assert!(!login(&client, "alice").await.unwrap().is_admin);
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn codes_are_single_use()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    let (code, login) = authorize(&client, "alice").await;
+    assert!(client.exchange(&code, &login).await.is_ok());
+    assert!(matches!(client.exchange(&code, &login).await, Err(OidcError::Provider(_))));
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn a_wrong_code_verifier_is_refused()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    let (code, mut login) = authorize(&client, "alice").await;
+    login.code_verifier = client.authorization_request().login.code_verifier;
+    assert!(matches!(client.exchange(&code, &login).await, Err(OidcError::Provider(_))));
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn a_token_for_another_login_is_refused()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    let (code, mut login) = authorize(&client, "alice").await;
+    login.nonce = client.authorization_request().login.nonce;
+    assert_invalid_token(client.exchange(&code, &login).await);
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn a_token_for_another_client_is_refused()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    provider.behave(|behaviour| behaviour.audience = Some("another-app".to_owned()));
+    assert_invalid_token(login(&client, "alice").await);
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn an_expired_token_is_refused()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    provider.behave(|behaviour| behaviour.expired = true);
+    assert_invalid_token(login(&client, "alice").await);
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn a_token_signed_with_an_unpublished_key_is_refused()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    provider.behave(|behaviour| behaviour.sign_with_other_key = true);
+    assert_invalid_token(login(&client, "alice").await);
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn tokens_signed_with_the_client_secret_are_accepted()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    provider.behave(|behaviour| behaviour.sign_with_client_secret = true);
+    assert_eq!(login(&client, "bob").await.unwrap().subject, "bob");
+    provider.stop().await;
+}
+
+
+#[actix_rt::test]
+async fn unknown_key_ids_refetch_the_keys_at_most_once_a_minute()
+{
+    let provider = MockProvider::start();
+    let client = OidcClient::discover(config(&provider)).await.unwrap();
+    assert_eq!(provider.jwks_requests(), 1);
+    provider.behave(|behaviour| behaviour.key_id = Some("rotated".to_owned()));
+    assert_invalid_token(login(&client, "alice").await);
+    assert_eq!(provider.jwks_requests(), 2);
+    assert_invalid_token(login(&client, "alice").await);
+    assert_invalid_token(login(&client, "bob").await);
+    assert_eq!(provider.jwks_requests(), 2);
+    provider.behave(|behaviour| behaviour.key_id = None);
+    assert_eq!(login(&client, "bob").await.unwrap().subject, "bob");
+    provider.stop().await;
+}
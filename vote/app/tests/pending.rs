@@ -0,0 +1,111 @@
+//! Runs the vote app binary against a local redis, so it is ignored by default. Start redis and run
+//! `cargo test -- --ignored`; `REDIS_ADDR` overrides the default.
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use actix::prelude::*;
+use actix_redis::{Command as RedisCommand, RedisActor};
+use actix_web::http::header;
+use redis_async::{resp::RespValue, resp_array};
+use serde_json::{json, Value};
+
+
+const START_TIMEOUT: Duration = Duration::from_secs(20);
+
+
+struct VoteApp
+{
+    url: String,
+    process: Child,
+}
+
+
+impl VoteApp
+{
+    async fn start(redis_addr: &str, name: &str) -> VoteApp
+    {
+        let bind = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let process = Command::new(env!("CARGO_BIN_EXE_vote_app"))
+            .env("REDIS_ADDR", redis_addr)
+            .env("REDIS_KEY", name)
+            .env("POLL_ID", name)
+            .env("BIND", bind.to_string())
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        let app = VoteApp { url: format!("http://{}", bind), process };
+        let started = Instant::now();
+        while awc::Client::default().get(format!("{}/healthz", app.url)).send().await.is_err()
+        {
+            assert!(started.elapsed() < START_TIMEOUT, "vote app did not start");
+            actix_rt::time::delay_for(Duration::from_millis(100)).await;
+        }
+        app
+    }
+
+
+    async fn vote(&self, voter_id: &str, vote: &str) -> u16
+    {
+        let response = awc::Client::default().post(&self.url)
+            .header(header::COOKIE, format!("voter_id={}", voter_id))
+            .send_json(&json!({ "voter_id": voter_id, "vote": vote }))
+            .await
+            .unwrap();
+        response.status().as_u16()
+    }
+
+
+    async fn my_vote(&self, voter_id: &str, poll: &str) -> Value
+    {
+        let mut response = awc::Client::default().get(format!("{}/my-vote?poll={}", self.url, poll))
+            .header(header::COOKIE, format!("voter_id={}", voter_id))
+            .send()
+            .await
+            .unwrap();
+        response.json().await.unwrap()
+    }
+}
+
+
+impl Drop for VoteApp
+{
+    fn drop(&mut self)
+    {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+
+async fn redis(redis: &Addr<RedisActor>, command: RespValue) -> RespValue
+{
+    redis.send(RedisCommand(command)).await.unwrap().unwrap()
+}
+
+
+#[actix_rt::test]
+#[ignore]
+async fn a_vote_that_could_not_be_queued_is_not_pending()
+{
+    let redis_addr = std::env::var("REDIS_ADDR").unwrap_or_else(|_| "127.0.0.1:6379".to_owned());
+    let name = format!("pending_test_{}", std::process::id());
+    let choice_key = common::poll::choice_key(&name, "voter-1");
+    let connection = RedisActor::start(redis_addr.trim_start_matches("redis://").to_owned());
+    // A string where the queue should be makes RPUSH fail.
+    redis(&connection, resp_array!["SET", &name, "not a list"]).await;
+
+    let app = VoteApp::start(&redis_addr, &name).await;
+    assert_eq!(app.vote("voter-1", "a").await, 503);
+    assert_eq!(app.my_vote("voter-1", &name).await["state"], "none");
+
+    redis(&connection, resp_array!["DEL", &name]).await;
+    actix_rt::time::delay_for(Duration::from_millis(1100)).await;
+    assert_eq!(app.vote("voter-1", "b").await, 200);
+    let my_vote = app.my_vote("voter-1", &name).await;
+    assert_eq!(my_vote["state"], "pending");
+    assert_eq!(my_vote["vote"], "b");
+    let ttl = redis(&connection, resp_array!["TTL", &choice_key]).await;
+    assert!(matches!(ttl, RespValue::Integer(ttl) if ttl > 0 && ttl as u64 <= common::poll::CHOICE_TTL_SECS));
+
+    redis(&connection, resp_array!["DEL", &name, &choice_key]).await;
+}
@@ -0,0 +1,85 @@
+use common::config::ConfigLoader;
+use vote_app::registry::VoterRegistry;
+
+
+const FILE: &str = r#"
+[groups]
+member = 1
+lead = 3
+
+[[voters]]
+id = "alice"
+group = "lead"
+
+[[voters]]
+id = "bob"
+group = "member"
+"#;
+
+
+fn load(file: &str, login: bool) -> (VoterRegistry, Vec<String>)
+{
+    let mut loader = ConfigLoader::from_sources(Some(file), Vec::new(), Vec::new());
+    let registry = VoterRegistry::load(&mut loader, login);
+    let problems = loader.finish().err().map(|e| e.problems).unwrap_or_default();
+    (registry, problems)
+}
+
+
+#[test]
+fn listed_voters_get_their_group_weight()
+{
+    let (registry, problems) = load(FILE, true);
+    assert!(problems.is_empty(), "{:?}", problems);
+    let alice = registry.resolve("alice");
+    assert_eq!((alice.group, alice.weight), (Some("lead"), 3));
+    let bob = registry.resolve("bob");
+    assert_eq!((bob.group, bob.weight), (Some("member"), 1));
+    let carol = registry.resolve("carol");
+    assert_eq!((carol.group, carol.weight), (None, 1));
+}
+
+
+#[test]
+fn groups_and_voters_need_login()
+{
+    let (_, problems) = load(FILE, false);
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert!(problems[0].contains("need oidc_issuer"), "{}", problems[0]);
+
+    let (_, problems) = load("[groups]\nlead = 3\n", false);
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+
+    let (registry, problems) = load("", false);
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(registry.resolve("alice").weight, 1);
+}
+
+
+#[test]
+fn broken_registries_are_reported()
+{
+    let file = r#"
+        [groups]
+        lead = 0
+
+        [[voters]]
+        id = "alice"
+        group = "boss"
+
+        [[voters]]
+        id = "alice"
+        group = "lead"
+    "#;
+    let (_, problems) = load(file, true);
+    let expected = [
+        "groups.lead must have a positive weight",
+        "voter 'alice' belongs to unknown group 'boss'",
+        "voter 'alice' is listed twice",
+    ];
+    assert_eq!(problems.len(), expected.len(), "{:?}", problems);
+    for expected in &expected
+    {
+        assert!(problems.iter().any(|problem| problem == expected), "{} not in {:?}", expected, problems);
+    }
+}
@@ -0,0 +1,150 @@
+//! What the voter fills in for approval, ranked and score polls before sending it. Single choice polls need
+//! none of this: one click votes. Kept apart from the markup so it can be tested without a browser.
+
+use serde_json::{Map, Value};
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Draft
+{
+    /// Whether each option is approved, in poll order.
+    Approval(Vec<bool>),
+    /// Indexes of every option, best first.
+    Ranked(Vec<usize>),
+    /// The score of each option, in poll order.
+    Score(Vec<u32>),
+}
+
+
+impl Draft
+{
+    /// A blank draft for a poll of `method` with `count` options: nothing approved, the options ranked in poll
+    /// order, every score zero. `None` for single choice and for methods the page does not know.
+    pub fn new(method: &str, count: usize) -> Option<Draft>
+    {
+        match method
+        {
+            "approval" => Some(Draft::Approval(vec![false; count])),
+            "ranked" => Some(Draft::Ranked((0..count).collect())),
+            "score" => Some(Draft::Score(vec![0; count])),
+            _ => None,
+        }
+    }
+
+
+    /// The draft for a ballot the voter already cast, so a reload shows it. Options the ballot does not rank
+    /// follow the ranked ones in poll order; unknown options are left out. `None` when the ballot does not fit
+    /// the method.
+    pub fn from_ballot(method: &str, ballot: &Value, ids: &[&str]) -> Option<Draft>
+    {
+        let index = |id: &str| ids.iter().position(|known| *known == id);
+        let listed: Vec<usize> = match ballot
+        {
+            Value::String(id) => index(id).into_iter().collect(),
+            Value::Array(items) => items.iter().filter_map(Value::as_str).filter_map(index).collect(),
+            _ => Vec::new(),
+        };
+        match (method, ballot)
+        {
+            ("approval", Value::String(_)) | ("approval", Value::Array(_)) =>
+                Some(Draft::Approval((0..ids.len()).map(|option| listed.contains(&option)).collect())),
+            ("ranked", Value::String(_)) | ("ranked", Value::Array(_)) =>
+                {
+                    let mut ranking = Vec::new();
+                    for option in listed.into_iter().chain(0..ids.len())
+                    {
+                        if !ranking.contains(&option)
+                        {
+                            ranking.push(option);
+                        }
+                    }
+                    Some(Draft::Ranked(ranking))
+                },
+            ("score", Value::Object(scores)) =>
+                {
+                    let score = |id: &str| scores.get(id).and_then(Value::as_u64).unwrap_or(0) as u32;
+                    Some(Draft::Score(ids.iter().map(|id| score(id)).collect()))
+                },
+            _ => None,
+        }
+    }
+
+
+    /// Approves the option at `index`, or takes its approval back.
+    pub fn toggle(&mut self, index: usize)
+    {
+        if let Draft::Approval(approved) = self
+        {
+            if let Some(approved) = approved.get_mut(index)
+            {
+                *approved = !*approved;
+            }
+        }
+    }
+
+
+    /// Swaps the option ranked at `position` with the one above it.
+    pub fn move_up(&mut self, position: usize)
+    {
+        if let Draft::Ranked(ranking) = self
+        {
+            if position > 0 && position < ranking.len()
+            {
+                ranking.swap(position - 1, position);
+            }
+        }
+    }
+
+
+    /// Swaps the option ranked at `position` with the one below it.
+    pub fn move_down(&mut self, position: usize)
+    {
+        if let Draft::Ranked(ranking) = self
+        {
+            if position + 1 < ranking.len()
+            {
+                ranking.swap(position, position + 1);
+            }
+        }
+    }
+
+
+    /// Scores above `max_score` are lowered to it.
+    pub fn set_score(&mut self, index: usize, score: u32, max_score: u32)
+    {
+        if let Draft::Score(scores) = self
+        {
+            if let Some(current) = scores.get_mut(index)
+            {
+                *current = score.min(max_score);
+            }
+        }
+    }
+
+
+    /// The ballot to send, shaped as the vote app expects: the approved or ranked option ids, or the score of
+    /// every option by id. `None` while an approval ballot approves nothing, which the vote app would refuse.
+    pub fn to_ballot(&self, ids: &[&str]) -> Option<Value>
+    {
+        let id = |index: &usize| ids.get(*index).map(|id| Value::String(id.to_string()));
+        match self
+        {
+            Draft::Approval(approved) =>
+                {
+                    let approved: Vec<Value> = approved.iter().enumerate()
+                        .filter(|(_, approved)| **approved)
+                        .filter_map(|(index, _)| id(&index))
+                        .collect();
+                    if approved.is_empty() { None } else { Some(Value::Array(approved)) }
+                },
+            Draft::Ranked(ranking) => Some(Value::Array(ranking.iter().filter_map(id).collect())),
+            Draft::Score(scores) =>
+                {
+                    let scores: Map<String, Value> = ids.iter().zip(scores)
+                        .map(|(id, score)| (id.to_string(), Value::from(*score)))
+                        .collect();
+                    Some(Value::Object(scores))
+                },
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! The options as a radio group: which option is checked, which one is in the tab order and where the keyboard
+//! moves the focus. Kept apart from the markup so it can be tested without a browser.
+
+
+/// What a key does in the group.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key
+{
+    Next,
+    Previous,
+    First,
+    Last,
+    /// Votes for the focused option.
+    Choose,
+}
+
+
+/// The action of `key`, a `KeyboardEvent.key`, or `None` for keys left to the browser. Right to left pages
+/// swap the left and right arrows.
+///
+/// Unlike a form's radio buttons the arrows only move the focus: checking an option casts a vote, which should
+/// not happen on the way to another option.
+pub fn key_action(key: &str, rtl: bool) -> Option<Key>
+{
+    match key
+    {
+        "ArrowDown" => Some(Key::Next),
+        "ArrowUp" => Some(Key::Previous),
+        "ArrowRight" => Some(if rtl { Key::Previous } else { Key::Next }),
+        "ArrowLeft" => Some(if rtl { Key::Next } else { Key::Previous }),
+        "Home" => Some(Key::First),
+        "End" => Some(Key::Last),
+        " " | "Enter" => Some(Key::Choose),
+        _ => None,
+    }
+}
+
+
+/// Where the focus goes from `current` among `count` options; the arrows wrap around at both ends.
+pub fn move_focus(current: usize, count: usize, key: Key) -> usize
+{
+    if count == 0
+    {
+        return 0;
+    }
+    match key
+    {
+        Key::Next => (current + 1) % count,
+        Key::Previous => (current + count - 1) % count,
+        Key::First => 0,
+        Key::Last => count - 1,
+        Key::Choose => current,
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptionState
+{
+    /// The option voted for.
+    pub checked: bool,
+    /// The one option reached with Tab; the arrows reach the others.
+    pub tab_stop: bool,
+    /// Another option is checked.
+    pub dimmed: bool,
+}
+
+
+impl OptionState
+{
+    pub fn aria_checked(&self) -> &'static str
+    {
+        if self.checked { "true" } else { "false" }
+    }
+
+
+    pub fn tabindex(&self) -> &'static str
+    {
+        if self.tab_stop { "0" } else { "-1" }
+    }
+}
+
+
+/// The state of each of `count` options. The tab stop is the focused option, else the checked one, else the
+/// first, so Tab comes back to where the voter left the group.
+pub fn option_states(count: usize, checked: Option<usize>, focused: Option<usize>) -> Vec<OptionState>
+{
+    let tab_stop = focused.filter(|&index| index < count)
+        .or_else(|| checked.filter(|&index| index < count))
+        .unwrap_or(0);
+    (0..count)
+        .map(|index| OptionState
+            {
+                checked: checked == Some(index),
+                tab_stop: index == tab_stop,
+                dimmed: checked.is_some() && checked != Some(index),
+            })
+        .collect()
+}
@@ -1,45 +1,87 @@
 #![recursion_limit="512"]
 
+pub mod ballot;
+pub mod choice;
+mod messages;
+
 use wasm_bindgen::prelude::*;
 use yew::prelude::*;
-use yew::services::fetch::{FetchService, FetchTask, Request, Response, FetchOptions, Credentials};
-use lazy_static;
 use std::collections::HashMap;
-use yew::format::Json;
+use std::time::Duration;
+use yew::services::fetch::{FetchService, FetchTask, Request, Response, FetchOptions, Credentials};
+use yew::services::timeout::{TimeoutService, TimeoutTask};
+use yew::format::{Json, Nothing};
 use anyhow::Error;
-use serde::Serialize;
-use web_sys;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
 use uuid::Uuid;
+use serde_json::Value;
+use i18n::Translator;
+use ballot::Draft;
+use choice::Key;
+
+
+/// How often the status of a queued vote is checked, and for how long.
+const STATUS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_STATUS_CHECKS: u32 = 60;
 
 
-lazy_static::lazy_static!
+#[derive(Deserialize)]
+struct PollOption
 {
-    static ref VOTE_VARIANTS: HashMap<&'static str, &'static str> =
-        {
-            let mut h_m = HashMap::new();
-            h_m.insert("a", "Cats");
-            h_m.insert("b", "Dogs");
-            h_m
-        };
+    id: String,
+    label: String,
 }
 
 
-enum Vote
+/// A poll's title and option labels in one locale; either may be missing.
+#[derive(Deserialize)]
+struct PollTranslation
 {
-    A,
-    B,
+    title: Option<String>,
+    labels: Option<Vec<String>>,
 }
 
 
-impl Vote
+/// What is being voted on, from `GET /poll` on the vote app.
+#[derive(Deserialize)]
+struct PollDefinition
 {
-    pub fn as_str(&self) -> &str
+    id: String,
+    title: String,
+    method: String,
+    options: Vec<PollOption>,
+    /// Highest score a voter may give an option in score polls.
+    #[serde(default)]
+    max_score: u32,
+    /// The vote app instance that answered.
+    instance: String,
+    /// By locale.
+    #[serde(default)]
+    translations: HashMap<String, PollTranslation>,
+}
+
+
+impl PollDefinition
+{
+    /// Takes the title and labels from the translation closest to the `requested` locales, if there is one.
+    /// This need not be the locale of the page: a poll may be translated into languages the page is not.
+    fn localize(&mut self, requested: &[String])
     {
-        match self
+        let locales: Vec<&str> = self.translations.keys().map(String::as_str).collect();
+        let locale = i18n::negotiate(requested, &locales, "").to_owned();
+        let translation = match self.translations.get(&locale)
+        {
+            Some(translation) => translation,
+            None => return,
+        };
+        if let Some(title) = &translation.title
         {
-            Vote::A => "a",
-            Vote::B => "b",
+            self.title = title.clone();
+        }
+        for (option, label) in self.options.iter_mut().zip(translation.labels.iter().flatten())
+        {
+            option.label = label.clone();
         }
     }
 }
@@ -48,7 +90,28 @@ impl Vote
 struct State
 {
     id: String,
-    vote: Option<Vote>,
+    /// Invitation token for private polls, from the `?token=` link.
+    token: Option<String>,
+    poll: Option<PollDefinition>,
+    /// The ballot last cast: an option id in single choice polls.
+    vote: Option<Value>,
+    /// The ballot being filled in, in approval, ranked and score polls.
+    draft: Option<Draft>,
+    /// The vote is queued but not counted yet.
+    pending: bool,
+    /// The vote app instance that served the poll, then the one that took the last vote.
+    instance: Option<String>,
+    error: Option<String>,
+    receipt: Option<Receipt>,
+    /// Where the last vote is, from `GET /votes/{request_id}/status`.
+    status: Option<VoteStatus>,
+    status_checks: u32,
+    /// The poll needs a login with the identity provider before votes are taken.
+    login_required: bool,
+    /// Index of the option with the keyboard focus, once the voter moved it into the options.
+    focused: Option<usize>,
+    /// Read out by screen readers whenever it changes.
+    announcement: String,
 }
 
 
@@ -56,15 +119,47 @@ struct Model
 {
     link: ComponentLink<Self>,
     state: State,
+    translator: Translator,
+    /// The locales the reader asked for, most preferred first.
+    locales: Vec<String>,
+    /// The option buttons, in poll order.
+    option_refs: Vec<NodeRef>,
+    /// The move up and move down buttons of each place in a ranked ballot.
+    move_refs: Vec<(NodeRef, NodeRef)>,
+    /// The element to focus once rendered, after the keyboard or a move took the focus elsewhere.
+    focus_request: Option<NodeRef>,
+    poll_task: Option<FetchTask>,
     fetch_task: Option<FetchTask>,
+    status_task: Option<FetchTask>,
+    status_timeout: Option<TimeoutTask>,
 }
 
 
 enum Msg
 {
-    Vote(&'static str),
+    PollLoaded(PollDefinition),
+    PollNotLoaded,
+    MyVoteLoaded(MyVote),
+    /// The option at this index was clicked or chosen with the keyboard.
+    Choose(usize),
+    Key(usize, Key),
+    Focused(usize),
+    /// Approves the option at this index, or takes the approval back.
+    Toggle(usize),
+    /// Moves the option ranked at this place up or down one place.
+    MoveUp(usize),
+    MoveDown(usize),
+    SetScore(usize, u32),
+    /// Casts the ballot filled in.
+    Submit,
+    Vote(Value),
     VoteSuccessful(Result<String, Error>),
-    VoteNotSuccessful
+    /// With the error the vote app answered, when there was one.
+    VoteNotSuccessful(Option<ErrorResponse>),
+    LoginRequired,
+    CheckStatus,
+    StatusLoaded(Option<VoteStatus>),
+    Retry,
 }
 
 
@@ -72,15 +167,477 @@ enum Msg
 struct VoteRequest
 {
     voter_id: String,
-    vote: String,
+    vote: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+
+/// Returned for every accepted vote. The nonce opens the commitment, which is published on the poll's
+/// bulletin board.
+#[derive(Deserialize)]
+struct Receipt
+{
+    request_id: String,
+    poll_id: String,
+    commitment: String,
+    nonce: String,
+    instance: String,
+}
+
+
+#[derive(Deserialize)]
+struct VoteStatus
+{
+    /// `queued`, `applied`, `rejected` or `failed`.
+    state: String,
+    reason: Option<String>,
+}
+
+
+/// The voter's current choice, from `GET /my-vote` on the vote app.
+#[derive(Deserialize)]
+struct MyVote
+{
+    /// `none`, `pending`, `recorded` or `hidden`.
+    state: String,
+    /// The ballot; an option id in single choice polls.
+    vote: Option<serde_json::Value>,
+}
+
+
+#[derive(Deserialize)]
+struct ErrorResponse
+{
+    code: String,
+    message: String,
+}
+
+
+fn is_login_required(body: &Result<String, Error>) -> bool
+{
+    match body
+    {
+        Ok(body) => matches!(serde_json::from_str::<ErrorResponse>(body), Ok(error) if error.code == "login_required"),
+        Err(_) => false,
+    }
 }
 
 
 impl Model
 {
-    fn make_vote(&self, vote: &str) -> FetchTask
+    fn error_message(&self, error_response: Option<ErrorResponse>) -> String
+    {
+        match error_response
+        {
+            Some(error_response) => match error_response.code.as_str()
+                {
+                    "unauthorized" | "not_invited" | "invalid_input" | "poll_not_open" | "poll_closed"
+                        | "rate_limited" | "backend_unavailable" =>
+                        self.translator.text(&format!("error.{}", error_response.code)),
+                    _ => error_response.message,
+                },
+            None => self.translator.text("error.not_registered"),
+        }
+    }
+
+
+    fn load_poll(&self) -> FetchTask
+    {
+        let callback = self.link.callback(
+            move |response: Response<Json<Result<PollDefinition, Error>>>|
+                {
+                    let (meta, Json(poll)) = response.into_parts();
+                    match poll
+                    {
+                        Ok(poll) if meta.status.is_success() => Msg::PollLoaded(poll),
+                        _ => Msg::PollNotLoaded,
+                    }
+                },
+            );
+        let request = Request::get("/poll").body(Nothing).unwrap();
+        FetchService::fetch(request, callback).unwrap()
+    }
+
+
+    /// Fetches what the voter already voted, so a reload shows their choice. Failures are ignored: the page
+    /// then starts without a choice, as it did before.
+    fn load_my_vote(&self, poll_id: &str) -> FetchTask
+    {
+        let callback = self.link.batch_callback(
+            move |response: Response<Json<Result<MyVote, Error>>>|
+                {
+                    let (meta, Json(my_vote)) = response.into_parts();
+                    match my_vote
+                    {
+                        Ok(my_vote) if meta.status.is_success() => vec![Msg::MyVoteLoaded(my_vote)],
+                        _ => Vec::new(),
+                    }
+                },
+            );
+        let request = Request::get(format!("/my-vote?poll={}", poll_id)).body(Nothing).unwrap();
+        let options = FetchOptions
+            {
+                credentials: Some(Credentials::SameOrigin),
+                ..FetchOptions::default()
+            };
+        FetchService::fetch_with_options(request, options, callback).unwrap()
+    }
+
+
+    fn schedule_status_check(&mut self)
+    {
+        let callback = self.link.callback(|_| Msg::CheckStatus);
+        self.status_timeout = Some(TimeoutService::spawn(STATUS_CHECK_INTERVAL, callback));
+    }
+
+
+    fn check_status(&self, request_id: &str) -> FetchTask
+    {
+        let callback = self.link.callback(
+            move |response: Response<Json<Result<VoteStatus, Error>>>|
+                {
+                    let (meta, Json(status)) = response.into_parts();
+                    Msg::StatusLoaded(status.ok().filter(|_| meta.status.is_success()))
+                },
+            );
+        let request = Request::get(format!("/votes/{}/status", request_id)).body(Nothing).unwrap();
+        FetchService::fetch(request, callback).unwrap()
+    }
+
+
+    fn status_key(&self, status: &VoteStatus) -> &'static str
+    {
+        match (status.state.as_str(), status.reason.as_deref())
+        {
+            ("queued", _) if self.state.status_checks >= MAX_STATUS_CHECKS => "status.still_queued",
+            ("queued", _) => "status.queued",
+            ("applied", _) => "status.applied",
+            ("rejected", Some("poll_closed")) => "status.rejected.poll_closed",
+            ("rejected", Some("poll_not_open")) => "status.rejected.poll_not_open",
+            ("rejected", Some("already_voted")) => "status.rejected.already_voted",
+            ("rejected", _) => "status.rejected",
+            _ => "status.failed",
+        }
+    }
+
+
+    /// Tells screen readers how the vote ended up once the worker has dealt with it, or once the page stops
+    /// waiting. Accepting a vote into the queue is not announced: it has not been counted yet.
+    fn announce_status(&mut self)
+    {
+        let status = match &self.state.status
+        {
+            Some(status) => status,
+            None => return,
+        };
+        let announcement = match status.state.as_str()
+        {
+            "applied" =>
+                {
+                    let option = self.state.vote.as_ref().map(|vote| self.describe(vote)).unwrap_or_default();
+                    self.translator.format("announcement.recorded", &[("option", &option)])
+                },
+            "queued" if self.state.status_checks < MAX_STATUS_CHECKS => return,
+            _ => self.translator.text(self.status_key(status)),
+        };
+        self.state.announcement = announcement;
+    }
+
+
+    fn view_status(&self) -> Html
+    {
+        let status = match &self.state.status
+        {
+            Some(status) => status,
+            None => return html! {},
+        };
+        let message = self.translator.text(self.status_key(status));
+        if status.state == "failed"
+        {
+            html!
+            {
+                <div id="status" class="failed">
+                    { message }
+                    <button id="retry" onclick=self.link.callback(|_| Msg::Retry)>
+                        { self.translator.text("retry") }
+                    </button>
+                </div>
+            }
+        }
+        else
+        {
+            html! { <div id="status" class=status.state.as_str()>{ message }</div> }
+        }
+    }
+
+
+    fn view_options(&self) -> Html
+    {
+        let poll = match &self.state.poll
+        {
+            Some(poll) => poll,
+            None => return html! {},
+        };
+        match (poll.method.as_str(), &self.state.draft)
+        {
+            ("single", _) => self.view_single(poll),
+            (_, Some(Draft::Approval(approved))) => self.view_approval(poll, approved),
+            (_, Some(Draft::Ranked(ranking))) => self.view_ranked(poll, ranking),
+            (_, Some(Draft::Score(scores))) => self.view_score(poll, scores),
+            (_, None) =>
+                html! { <div id="error" role="alert">{ self.translator.text("error.unsupported_method") }</div> },
+        }
+    }
+
+
+    fn view_check(checked: bool) -> Html
+    {
+        if checked
+        {
+            html! { <i class="fa fa-check-circle" aria-hidden="true"></i> }
+        }
+        else
+        {
+            html! {}
+        }
+    }
+
+
+    /// One click votes, so the options are a radio group.
+    fn view_single(&self, poll: &PollDefinition) -> Html
+    {
+        let states = choice::option_states(poll.options.len(), self.checked(), self.state.focused);
+        let rtl = self.translator.direction() == "rtl";
+        let options = poll.options.iter().zip(states).enumerate().map(|(index, (option, state))|
+            {
+                // The stylesheet has two button colours, alternated down the list.
+                let colour = if index % 2 == 0 { "a" } else { "b" };
+                let class = if state.dimmed { format!("{} dimmed", colour) } else { colour.to_owned() };
+                let onkeydown = self.link.batch_callback(move |event: KeyboardEvent|
+                    {
+                        match choice::key_action(&event.key(), rtl)
+                        {
+                            Some(key) =>
+                                {
+                                    // Keeps the arrows from scrolling and Enter from clicking as well.
+                                    event.prevent_default();
+                                    vec![Msg::Key(index, key)]
+                                },
+                            None => Vec::new(),
+                        }
+                    });
+                html!
+                {
+                    <button id=&option.id class=class role="radio" aria-checked=state.aria_checked()
+                        tabindex=state.tabindex() ref=self.option_refs.get(index).cloned().unwrap_or_default()
+                        onclick=self.link.callback(move |_| Msg::Choose(index))
+                        onfocus=self.link.callback(move |_| Msg::Focused(index))
+                        onkeydown=onkeydown>
+                        { &option.label }
+                        { Model::view_check(state.checked) }
+                    </button>
+                }
+            });
+        html! { <div id="choice" role="radiogroup" aria-labelledby="title">{ for options }</div> }
+    }
+
+
+    /// Any number of options can be approved, so the options are checkboxes.
+    fn view_approval(&self, poll: &PollDefinition, approved: &[bool]) -> Html
+    {
+        let options = poll.options.iter().zip(approved).enumerate().map(|(index, (option, &approved))|
+            {
+                let class = if index % 2 == 0 { "a" } else { "b" };
+                let checked = if approved { "true" } else { "false" };
+                html!
+                {
+                    <button id=&option.id class=class role="checkbox" aria-checked=checked
+                        onclick=self.link.callback(move |_| Msg::Toggle(index))>
+                        { &option.label }
+                        { Model::view_check(approved) }
+                    </button>
+                }
+            });
+        html!
+        {
+            <>
+                <div id="hint">{ self.translator.text("hint.approval") }</div>
+                <div id="choice" role="group" aria-labelledby="title" aria-describedby="hint">{ for options }</div>
+                { self.view_submit() }
+            </>
+        }
+    }
+
+
+    fn view_ranked(&self, poll: &PollDefinition, ranking: &[usize]) -> Html
+    {
+        let last = ranking.len().saturating_sub(1);
+        let places = ranking.iter().enumerate().filter_map(|(place, &index)|
+            {
+                let option = poll.options.get(index)?;
+                let (up, down) = self.move_refs.get(place).cloned().unwrap_or_default();
+                let up_label = self.translator.format("ranked.up", &[("option", &option.label)]);
+                let down_label = self.translator.format("ranked.down", &[("option", &option.label)]);
+                Some(html!
+                    {
+                        <li>
+                            <span class="place">{ place + 1 }</span>
+                            <span class="label">{ &option.label }</span>
+                            <button class="move" ref=up disabled={ place == 0 } aria-label=up_label
+                                onclick=self.link.callback(move |_| Msg::MoveUp(place))>
+                                <i class="fa fa-arrow-up" aria-hidden="true"></i>
+                            </button>
+                            <button class="move" ref=down disabled={ place == last } aria-label=down_label
+                                onclick=self.link.callback(move |_| Msg::MoveDown(place))>
+                                <i class="fa fa-arrow-down" aria-hidden="true"></i>
+                            </button>
+                        </li>
+                    })
+            });
+        html!
+        {
+            <>
+                <div id="hint">{ self.translator.text("hint.ranked") }</div>
+                <ol id="ranking" aria-labelledby="title" aria-describedby="hint">{ for places }</ol>
+                { self.view_submit() }
+            </>
+        }
+    }
+
+
+    fn view_score(&self, poll: &PollDefinition, scores: &[u32]) -> Html
+    {
+        let max_score = poll.max_score.to_string();
+        let options = poll.options.iter().zip(scores).enumerate().map(|(index, (option, score))|
+            {
+                let id = format!("score-{}", option.id);
+                // Half typed or out of range numbers are left for the voter to finish or correct.
+                let oninput = self.link.batch_callback(move |input: InputData|
+                    {
+                        input.value.trim().parse::<u32>().ok()
+                            .map(|score| Msg::SetScore(index, score))
+                            .into_iter()
+                            .collect()
+                    });
+                html!
+                {
+                    <li>
+                        <label class="label" for=&id>{ &option.label }</label>
+                        <input id=&id type="number" min="0" max=&max_score step="1" value=score.to_string()
+                            oninput=oninput/>
+                    </li>
+                }
+            });
+        html!
+        {
+            <>
+                <div id="hint">{ self.translator.format("hint.score", &[("max", &max_score)]) }</div>
+                <ul id="scores" aria-labelledby="title" aria-describedby="hint">{ for options }</ul>
+                { self.view_submit() }
+            </>
+        }
+    }
+
+
+    /// Disabled until the ballot can be cast, and while it is the one already cast.
+    fn view_submit(&self) -> Html
+    {
+        let ballot = self.draft_ballot();
+        let disabled = ballot.is_none() || ballot == self.state.vote;
+        html!
+        {
+            <button id="submit" disabled=disabled onclick=self.link.callback(|_| Msg::Submit)>
+                { self.translator.text("submit") }
+            </button>
+        }
+    }
+
+
+    fn draft_ballot(&self) -> Option<Value>
+    {
+        let poll = self.state.poll.as_ref()?;
+        let ids: Vec<&str> = poll.options.iter().map(|option| option.id.as_str()).collect();
+        self.state.draft.as_ref()?.to_ballot(&ids)
+    }
+
+
+    /// Index of the option voted for.
+    fn checked(&self) -> Option<usize>
+    {
+        let poll = self.state.poll.as_ref()?;
+        let vote = self.state.vote.as_ref()?.as_str()?;
+        poll.options.iter().position(|option| option.id == vote)
+    }
+
+
+    fn label(&self, vote: &str) -> String
+    {
+        self.state.poll.iter()
+            .flat_map(|poll| poll.options.iter())
+            .find(|option| option.id == vote)
+            .map_or(vote, |option| &option.label)
+            .to_owned()
+    }
+
+
+    /// The ballot as the voter reads it: the option, the options in ballot order, or each option's score.
+    fn describe(&self, ballot: &Value) -> String
     {
-        let vote_request = VoteRequest { voter_id: self.state.id.to_owned(), vote: vote.to_owned() };
+        let parts: Vec<String> = match ballot
+        {
+            Value::String(id) => vec![self.label(id)],
+            Value::Array(ids) => ids.iter().filter_map(Value::as_str).map(|id| self.label(id)).collect(),
+            Value::Object(scores) => scores.iter().map(|(id, score)| format!("{} {}", self.label(id), score)).collect(),
+            _ => Vec::new(),
+        };
+        parts.join(&self.translator.text("separator"))
+    }
+
+
+    fn view_error(&self) -> Html
+    {
+        match &self.state.error
+        {
+            Some(error) if self.state.login_required =>
+                html!
+                {
+                    <div id="error" role="alert">
+                        { error }{ " " }<a id="login" href="/login">{ self.translator.text("login") }</a>
+                    </div>
+                },
+            Some(error) => html! { <div id="error" role="alert">{ error }</div> },
+            None => html! {},
+        }
+    }
+
+
+    fn view_receipt(&self) -> Html
+    {
+        match &self.state.receipt
+        {
+            Some(receipt) =>
+                html!
+                {
+                    <div id="receipt">
+                        <div>{ self.translator.format("receipt.intro", &[("poll", &receipt.poll_id)]) }</div>
+                        <div>{ self.translator.text("receipt.commitment") }<code>{ &receipt.commitment }</code></div>
+                        <div>{ self.translator.text("receipt.nonce") }<code>{ &receipt.nonce }</code></div>
+                    </div>
+                },
+            None => html! {},
+        }
+    }
+
+
+    fn make_vote(&self, vote: &Value) -> FetchTask
+    {
+        let vote_request = VoteRequest
+            {
+                voter_id: self.state.id.to_owned(),
+                vote: vote.clone(),
+                token: self.state.token.clone(),
+            };
         let callback = self.link.callback(
             move |response: Response<Result<String, Error>>|
                 {
@@ -89,9 +646,13 @@ impl Model
                     {
                         Msg::VoteSuccessful(message)
                     }
+                    else if is_login_required(&message)
+                    {
+                        Msg::LoginRequired
+                    }
                     else
                     {
-                        Msg::VoteNotSuccessful
+                        Msg::VoteNotSuccessful(message.ok().and_then(|body| serde_json::from_str(&body).ok()))
                     }
                 },
             );
@@ -118,7 +679,7 @@ fn create_id() -> Result<String, JsValue>
     let cookies = html_document.cookie()?;
     if !cookies.is_empty()
     {
-        for cookie in cookies.split("; ").into_iter().collect::<Vec<&str>>()
+        for cookie in cookies.split("; ")
         {
             let splitted_cookie = cookie.split("=").collect::<Vec<&str>>();
             if splitted_cookie.len() == 2 && splitted_cookie[0] == "voter_id"
@@ -139,6 +700,30 @@ fn create_id() -> Result<String, JsValue>
 }
 
 
+/// The invitation token from `?token=`, remembered in a cookie so the page still works after a reload
+/// without it.
+fn invitation_token() -> Result<Option<String>, JsValue>
+{
+    let window = web_sys::window().unwrap();
+    let html_document = window.document().unwrap().dyn_into::<web_sys::HtmlDocument>().unwrap();
+    let search = window.location().search()?;
+    if let Some(token) = web_sys::UrlSearchParams::new_with_str(&search)?.get("token")
+    {
+        if !token.is_empty()
+        {
+            html_document.set_cookie(&format!("invitation_token={}", token))?;
+            return Ok(Some(token));
+        }
+    }
+    let cookies = html_document.cookie()?;
+    let token = cookies.split("; ")
+        .filter_map(|cookie| cookie.strip_prefix("invitation_token="))
+        .find(|token| !token.is_empty())
+        .map(str::to_owned);
+    Ok(token)
+}
+
+
 impl Component for Model
 {
     type Message = Msg;
@@ -156,7 +741,44 @@ impl Component for Model
                     Uuid::new_v4().to_string()
                 }
             };
-        Self { link, state: State { id, vote: None } , fetch_task: None }
+        let token = invitation_token().unwrap_or(None);
+        let locales = messages::requested_locales();
+        let translator = Translator::new(&locales, &messages::CATALOGUES);
+        // Without it the page is still readable, only laid out left to right.
+        let _ = messages::apply_to_document(&translator);
+        let state = State
+            {
+                id,
+                token,
+                poll: None,
+                vote: None,
+                draft: None,
+                pending: false,
+                instance: None,
+                error: None,
+                receipt: None,
+                status: None,
+                status_checks: 0,
+                login_required: false,
+                focused: None,
+                announcement: String::new(),
+            };
+        let mut model = Self
+            {
+                link,
+                state,
+                translator,
+                locales,
+                option_refs: Vec::new(),
+                move_refs: Vec::new(),
+                focus_request: None,
+                poll_task: None,
+                fetch_task: None,
+                status_task: None,
+                status_timeout: None,
+            };
+        model.poll_task = Some(model.load_poll());
+        model
     }
 
 
@@ -164,21 +786,165 @@ impl Component for Model
     {
         match msg
         {
-            Msg::Vote(vote) =>
+            Msg::PollLoaded(mut poll) =>
+                {
+                    poll.localize(&self.locales);
+                    if let Some(document) = web_sys::window().and_then(|window| window.document())
+                    {
+                        document.set_title(&poll.title);
+                    }
+                    self.state.instance = Some(poll.instance.clone());
+                    self.poll_task = Some(self.load_my_vote(&poll.id));
+                    self.option_refs = poll.options.iter().map(|_| NodeRef::default()).collect();
+                    self.move_refs = poll.options.iter().map(|_| Default::default()).collect();
+                    self.state.draft = Draft::new(&poll.method, poll.options.len());
+                    self.state.poll = Some(poll);
+                },
+            Msg::MyVoteLoaded(my_vote) =>
+                {
+                    self.poll_task = None;
+                    // A vote made while this was loading is newer than what the server had.
+                    if self.state.vote.is_none() && (my_vote.state == "pending" || my_vote.state == "recorded")
+                    {
+                        self.state.pending = my_vote.state == "pending";
+                        self.state.vote = my_vote.vote;
+                        if let (Some(poll), Some(vote)) = (&self.state.poll, &self.state.vote)
+                        {
+                            let ids: Vec<&str> = poll.options.iter().map(|option| option.id.as_str()).collect();
+                            if let Some(draft) = Draft::from_ballot(&poll.method, vote, &ids)
+                            {
+                                self.state.draft = Some(draft);
+                            }
+                        }
+                    }
+                },
+            Msg::PollNotLoaded =>
+                {
+                    self.state.error = Some(self.translator.text("error.poll_not_loaded"));
+                    self.poll_task = None;
+                },
+            Msg::Choose(index) =>
                 {
-                    if vote == Vote::A.as_str()
+                    self.state.focused = Some(index);
+                    let id = match self.state.poll.as_ref().and_then(|poll| poll.options.get(index))
+                    {
+                        Some(option) => Value::String(option.id.clone()),
+                        None => return false,
+                    };
+                    // Voting again for the same option would change nothing.
+                    if self.state.vote.as_ref() == Some(&id)
                     {
-                        self.state.vote = Some(Vote::A);
+                        return false;
                     }
-                    if vote == Vote::B.as_str()
+                    return self.update(Msg::Vote(id));
+                },
+            Msg::Key(index, Key::Choose) => return self.update(Msg::Choose(index)),
+            Msg::Key(index, key) =>
+                {
+                    let focused = choice::move_focus(index, self.option_refs.len(), key);
+                    self.state.focused = Some(focused);
+                    self.focus_request = self.option_refs.get(focused).cloned();
+                },
+            Msg::Focused(index) =>
+                {
+                    if self.state.focused == Some(index)
                     {
-                        self.state.vote = Some(Vote::B);
+                        return false;
                     }
-                    let task = self.make_vote(vote);
+                    self.state.focused = Some(index);
+                },
+            Msg::Toggle(index) => self.state.draft.iter_mut().for_each(|draft| draft.toggle(index)),
+            Msg::MoveUp(place) =>
+                {
+                    self.state.draft.iter_mut().for_each(|draft| draft.move_up(place));
+                    // The focus follows the option, to the other button once it reaches the top.
+                    let place = place.saturating_sub(1);
+                    self.focus_request = self.move_refs.get(place)
+                        .map(|(up, down)| if place == 0 { down.clone() } else { up.clone() });
+                },
+            Msg::MoveDown(place) =>
+                {
+                    self.state.draft.iter_mut().for_each(|draft| draft.move_down(place));
+                    let place = (place + 1).min(self.move_refs.len().saturating_sub(1));
+                    self.focus_request = self.move_refs.get(place)
+                        .map(|(up, down)| if place + 1 == self.move_refs.len() { up.clone() } else { down.clone() });
+                },
+            Msg::SetScore(index, score) =>
+                {
+                    let max_score = self.state.poll.as_ref().map_or(0, |poll| poll.max_score);
+                    self.state.draft.iter_mut().for_each(|draft| draft.set_score(index, score, max_score));
+                },
+            Msg::Submit =>
+                {
+                    match self.draft_ballot()
+                    {
+                        Some(ballot) if Some(&ballot) != self.state.vote.as_ref() =>
+                            return self.update(Msg::Vote(ballot)),
+                        _ => return false,
+                    }
+                },
+            Msg::Vote(vote) =>
+                {
+                    let task = self.make_vote(&vote);
+                    self.state.vote = Some(vote);
+                    self.state.announcement.clear();
+                    self.state.pending = false;
+                    self.state.status = None;
+                    self.status_timeout = None;
+                    self.status_task = None;
                     self.fetch_task = Some(task);
                 },
-            Msg::VoteSuccessful(_message) => (),
-            Msg::VoteNotSuccessful => return false,
+            Msg::VoteSuccessful(message) =>
+                {
+                    self.state.error = None;
+                    self.state.receipt = message.ok().and_then(|body| serde_json::from_str::<Receipt>(&body).ok());
+                    if let Some(receipt) = &self.state.receipt
+                    {
+                        self.state.instance = Some(receipt.instance.clone());
+                        self.state.status = Some(VoteStatus { state: "queued".to_owned(), reason: None });
+                        self.state.status_checks = 0;
+                        self.schedule_status_check();
+                    }
+                },
+            Msg::VoteNotSuccessful(error) => self.state.error = Some(self.error_message(error)),
+            Msg::LoginRequired =>
+                {
+                    self.state.vote = None;
+                    self.state.login_required = true;
+                    self.state.error = Some(self.translator.text("error.login_required"));
+                },
+            Msg::CheckStatus =>
+                {
+                    self.status_timeout = None;
+                    if let Some(receipt) = &self.state.receipt
+                    {
+                        self.status_task = Some(self.check_status(&receipt.request_id));
+                    }
+                    return false;
+                },
+            Msg::StatusLoaded(status) =>
+                {
+                    self.status_task = None;
+                    self.state.status_checks += 1;
+                    // A failed check leaves the last known status; the next one may get through.
+                    if let Some(status) = status
+                    {
+                        self.state.status = Some(status);
+                    }
+                    let queued = matches!(&self.state.status, Some(status) if status.state == "queued");
+                    if queued && self.state.status_checks < MAX_STATUS_CHECKS
+                    {
+                        self.schedule_status_check();
+                    }
+                    self.announce_status();
+                },
+            Msg::Retry =>
+                {
+                    if let Some(vote) = self.state.vote.clone()
+                    {
+                        return self.update(Msg::Vote(vote));
+                    }
+                },
         }
         true
     }
@@ -190,72 +956,36 @@ impl Component for Model
     }
 
 
+    fn rendered(&mut self, _first_render: bool)
+    {
+        let element = self.focus_request.take().and_then(|node| node.cast::<web_sys::HtmlElement>());
+        if let Some(element) = element
+        {
+            let _ = element.focus();
+        }
+    }
+
+
     fn view(&self) -> Html
     {
+        let instance = self.state.instance.as_deref().unwrap_or("-");
         html!
         {
             <div id="content-container">
                 <div id="content-container-center">
-                    <h3>{ VOTE_VARIANTS.get("a").unwrap() }{ " vs " }{ VOTE_VARIANTS.get("b").unwrap() }</h3>
-                    {
-                        if let Some(vote) = &self.state.vote
-                        {
-                            match vote
-                            {
-                                Vote::A =>
-                                    {
-                                        html!
-                                        {
-                                            <div id="choice">
-                                                <button id="a" class="a" disabled=true>
-                                                    { VOTE_VARIANTS.get("a").unwrap() }
-                                                    <i class="fa fa-check-circle"></i>
-                                                </button>
-                                                <button id="b" class="b" style="opacity: 0.5;"
-                                                    onclick=self.link.callback(|_| Msg::Vote("b"))>
-                                                    { VOTE_VARIANTS.get("b").unwrap() }
-                                                </button>
-                                            </div>
-                                        }
-                                    },
-                                Vote::B =>
-                                    {
-                                        html!
-                                        {
-                                            <div id="choice">
-                                                <button id="a" class="a" style="opacity: 0.5;"
-                                                onclick=self.link.callback(|_| Msg::Vote("a"))>
-                                                    { VOTE_VARIANTS.get("a").unwrap() }
-                                                </button>
-                                                <button id="b" class="b" disabled=true>
-                                                    { VOTE_VARIANTS.get("b").unwrap() }
-                                                    <i class="fa fa-check-circle"></i>
-                                                </button>
-                                            </div>
-                                        }
-                                    },
-                            }
-                        }
-                        else
-                        {
-                            html!
-                            {
-                                <div id="choice">
-                                    <button id="a" class="a" onclick=self.link.callback(|_| Msg::Vote("a"))>
-                                        { VOTE_VARIANTS.get("a").unwrap() }
-                                    </button>
-                                    <button id="b" class="b" onclick=self.link.callback(|_| Msg::Vote("b"))>
-                                        { VOTE_VARIANTS.get("b").unwrap() }
-                                    </button>
-                                </div>
-                            }
-                        }
-                    }
+                    <h3 id="title">{ self.state.poll.as_ref().map_or("", |poll| poll.title.as_str()) }</h3>
+                    { self.view_options() }
+                    { self.view_error() }
+                    { self.view_status() }
+                    { self.view_receipt() }
                     <div id="tip">
-                        { "(Tip: you can change your vote)" }
+                        { self.translator.text(if self.state.pending { "tip.pending" } else { "tip.change" }) }
                     </div>
                     <div id="hostname">
-                        { "Processed by container ID " }{ &self.state.id }
+                        { self.translator.format("instance", &[("instance", instance)]) }
+                    </div>
+                    <div id="announcement" class="visually-hidden" role="status" aria-live="polite">
+                        { &self.state.announcement }
                     </div>
                 </div>
             </div>
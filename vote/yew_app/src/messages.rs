@@ -0,0 +1,167 @@
+use i18n::{Catalogue, Translator};
+use wasm_bindgen::JsValue;
+
+
+/// The page's messages; English is the default and fills in anything another catalogue lacks.
+pub static CATALOGUES: [Catalogue; 3] = [
+    Catalogue
+    {
+        locale: "en",
+        messages: &[
+            ("error.unauthorized", "Your voter id was not recognized, please reload the page."),
+            ("error.not_invited", "This poll is private, please open the invitation link you were sent."),
+            ("error.invalid_input", "This vote option is not available."),
+            ("error.poll_not_open", "Voting has not started yet."),
+            ("error.poll_closed", "Voting is closed."),
+            ("error.rate_limited", "You are voting too fast, please wait a moment."),
+            ("error.backend_unavailable", "Your vote could not be stored, please try again later."),
+            ("error.not_registered", "Your vote could not be registered."),
+            ("error.poll_not_loaded", "The poll could not be loaded, please reload the page."),
+            ("error.login_required", "Please log in to vote."),
+            ("error.unsupported_method", "This page does not support this poll's voting method."),
+            ("submit", "Vote"),
+            ("hint.approval", "Choose every option you approve of."),
+            ("hint.ranked", "Order the options from best to worst."),
+            ("hint.score", "Give each option a score from 0 to {max}."),
+            ("ranked.up", "Move {option} up"),
+            ("ranked.down", "Move {option} down"),
+            ("separator", ", "),
+            ("login", "Log in"),
+            ("status.queued", "Your vote is queued..."),
+            ("status.still_queued", "Your vote is still queued, it will be counted as soon as possible."),
+            ("status.applied", "Your vote was counted."),
+            ("status.rejected.poll_closed", "Your vote arrived after the poll closed and was not counted."),
+            ("status.rejected.poll_not_open", "Your vote arrived before the poll opened and was not counted."),
+            ("status.rejected.already_voted", "You have already voted, and votes cannot be changed in this poll."),
+            ("status.rejected", "Your vote was not counted."),
+            ("status.failed", "Your vote could not be stored."),
+            ("retry", "Retry"),
+            ("receipt.intro", "Keep this receipt to check your ballot on the bulletin board of poll {poll}"),
+            ("receipt.commitment", "Commitment: "),
+            ("receipt.nonce", "Nonce: "),
+            ("tip.pending", "(Your vote is queued and will be counted shortly)"),
+            ("tip.change", "(Tip: you can change your vote)"),
+            ("instance", "Processed by container ID {instance}"),
+            ("announcement.recorded", "Vote recorded: {option}"),
+        ],
+    },
+    Catalogue
+    {
+        locale: "de",
+        messages: &[
+            ("error.unauthorized", "Ihre Wähler-ID wurde nicht erkannt, bitte laden Sie die Seite neu."),
+            ("error.not_invited", "Diese Umfrage ist privat, bitte öffnen Sie den Einladungslink, den Sie erhalten haben."),
+            ("error.invalid_input", "Diese Option steht nicht zur Wahl."),
+            ("error.poll_not_open", "Die Abstimmung hat noch nicht begonnen."),
+            ("error.poll_closed", "Die Abstimmung ist beendet."),
+            ("error.rate_limited", "Sie stimmen zu schnell ab, bitte warten Sie einen Moment."),
+            ("error.backend_unavailable", "Ihre Stimme konnte nicht gespeichert werden, bitte versuchen Sie es später erneut."),
+            ("error.not_registered", "Ihre Stimme konnte nicht registriert werden."),
+            ("error.poll_not_loaded", "Die Umfrage konnte nicht geladen werden, bitte laden Sie die Seite neu."),
+            ("error.login_required", "Bitte melden Sie sich an, um abzustimmen."),
+            ("error.unsupported_method", "Diese Seite unterstützt das Abstimmungsverfahren dieser Umfrage nicht."),
+            ("submit", "Abstimmen"),
+            ("hint.approval", "Wählen Sie jede Option, der Sie zustimmen."),
+            ("hint.ranked", "Ordnen Sie die Optionen von der besten zur schlechtesten."),
+            ("hint.score", "Geben Sie jeder Option eine Punktzahl von 0 bis {max}."),
+            ("ranked.up", "{option} nach oben verschieben"),
+            ("ranked.down", "{option} nach unten verschieben"),
+            ("separator", ", "),
+            ("login", "Anmelden"),
+            ("status.queued", "Ihre Stimme ist in der Warteschlange..."),
+            ("status.still_queued", "Ihre Stimme ist noch in der Warteschlange und wird so bald wie möglich gezählt."),
+            ("status.applied", "Ihre Stimme wurde gezählt."),
+            ("status.rejected.poll_closed", "Ihre Stimme kam nach dem Ende der Umfrage an und wurde nicht gezählt."),
+            ("status.rejected.poll_not_open", "Ihre Stimme kam vor dem Beginn der Umfrage an und wurde nicht gezählt."),
+            ("status.rejected.already_voted", "Sie haben bereits abgestimmt, und in dieser Umfrage lassen sich Stimmen nicht ändern."),
+            ("status.rejected", "Ihre Stimme wurde nicht gezählt."),
+            ("status.failed", "Ihre Stimme konnte nicht gespeichert werden."),
+            ("retry", "Erneut versuchen"),
+            ("receipt.intro", "Bewahren Sie diese Quittung auf, um Ihren Stimmzettel auf der Anschlagtafel der Umfrage {poll} zu prüfen"),
+            ("receipt.commitment", "Commitment: "),
+            ("receipt.nonce", "Nonce: "),
+            ("tip.pending", "(Ihre Stimme ist in der Warteschlange und wird in Kürze gezählt)"),
+            ("tip.change", "(Tipp: Sie können Ihre Stimme ändern)"),
+            ("instance", "Verarbeitet von Container-ID {instance}"),
+            ("announcement.recorded", "Stimme erfasst: {option}"),
+        ],
+    },
+    Catalogue
+    {
+        locale: "ar",
+        messages: &[
+            ("error.unauthorized", "لم يتم التعرف على معرّف الناخب الخاص بك، يرجى إعادة تحميل الصفحة."),
+            ("error.not_invited", "هذا الاستطلاع خاص، يرجى فتح رابط الدعوة الذي أُرسل إليك."),
+            ("error.invalid_input", "خيار التصويت هذا غير متاح."),
+            ("error.poll_not_open", "لم يبدأ التصويت بعد."),
+            ("error.poll_closed", "التصويت مغلق."),
+            ("error.rate_limited", "أنت تصوّت بسرعة كبيرة، يرجى الانتظار قليلاً."),
+            ("error.backend_unavailable", "تعذّر حفظ صوتك، يرجى المحاولة لاحقاً."),
+            ("error.not_registered", "تعذّر تسجيل صوتك."),
+            ("error.poll_not_loaded", "تعذّر تحميل الاستطلاع، يرجى إعادة تحميل الصفحة."),
+            ("error.login_required", "يرجى تسجيل الدخول للتصويت."),
+            ("error.unsupported_method", "لا تدعم هذه الصفحة طريقة التصويت في هذا الاستطلاع."),
+            ("submit", "تصويت"),
+            ("hint.approval", "اختر كل خيار توافق عليه."),
+            ("hint.ranked", "رتّب الخيارات من الأفضل إلى الأسوأ."),
+            ("hint.score", "امنح كل خيار درجة من 0 إلى {max}."),
+            ("ranked.up", "انقل {option} إلى الأعلى"),
+            ("ranked.down", "انقل {option} إلى الأسفل"),
+            ("separator", "، "),
+            ("login", "تسجيل الدخول"),
+            ("status.queued", "صوتك في قائمة الانتظار..."),
+            ("status.still_queued", "لا يزال صوتك في قائمة الانتظار، وسيُحتسب في أقرب وقت ممكن."),
+            ("status.applied", "تم احتساب صوتك."),
+            ("status.rejected.poll_closed", "وصل صوتك بعد إغلاق الاستطلاع ولم يُحتسب."),
+            ("status.rejected.poll_not_open", "وصل صوتك قبل فتح الاستطلاع ولم يُحتسب."),
+            ("status.rejected.already_voted", "لقد صوّتَّ بالفعل، ولا يمكن تغيير الأصوات في هذا الاستطلاع."),
+            ("status.rejected", "لم يُحتسب صوتك."),
+            ("status.failed", "تعذّر حفظ صوتك."),
+            ("retry", "إعادة المحاولة"),
+            ("receipt.intro", "احتفظ بهذا الإيصال للتحقق من بطاقة اقتراعك على لوحة إعلانات الاستطلاع {poll}"),
+            ("receipt.commitment", "الالتزام: "),
+            ("receipt.nonce", "الرقم العشوائي: "),
+            ("tip.pending", "(صوتك في قائمة الانتظار وسيُحتسب قريباً)"),
+            ("tip.change", "(تلميح: يمكنك تغيير صوتك)"),
+            ("instance", "عولج بواسطة الحاوية {instance}"),
+            ("announcement.recorded", "تم تسجيل الصوت: {option}"),
+        ],
+    },
+];
+
+
+/// The locales the reader asked for, most preferred first: `?lang=` in the page address, then the browser's
+/// languages.
+pub fn requested_locales() -> Vec<String>
+{
+    let window = match web_sys::window()
+    {
+        Some(window) => window,
+        None => return Vec::new(),
+    };
+    let mut locales = Vec::new();
+    let search = window.location().search().unwrap_or_default();
+    if let Some(lang) = web_sys::UrlSearchParams::new_with_str(&search).ok().and_then(|params| params.get("lang"))
+    {
+        locales.push(lang);
+    }
+    let navigator = window.navigator();
+    locales.extend(navigator.languages().iter().filter_map(|language| language.as_string()));
+    locales.extend(navigator.language());
+    locales
+}
+
+
+/// Sets `lang` and `dir` on the page, so the browser lays right-to-left languages out as such.
+pub fn apply_to_document(translator: &Translator) -> Result<(), JsValue>
+{
+    let root = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.document_element());
+    if let Some(root) = root
+    {
+        root.set_attribute("lang", translator.locale())?;
+        root.set_attribute("dir", translator.direction())?;
+    }
+    Ok(())
+}
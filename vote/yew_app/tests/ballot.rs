@@ -0,0 +1,80 @@
+use serde_json::json;
+use vote_yew_app::ballot::Draft;
+
+
+const IDS: [&str; 3] = ["a", "b", "c"];
+
+
+#[test]
+fn blank_drafts_depend_on_the_method()
+{
+    assert_eq!(Draft::new("approval", 3), Some(Draft::Approval(vec![false, false, false])));
+    assert_eq!(Draft::new("ranked", 3), Some(Draft::Ranked(vec![0, 1, 2])));
+    assert_eq!(Draft::new("score", 3), Some(Draft::Score(vec![0, 0, 0])));
+    assert_eq!(Draft::new("single", 3), None);
+    assert_eq!(Draft::new("condorcet", 3), None);
+}
+
+
+#[test]
+fn an_approval_ballot_needs_an_approved_option()
+{
+    let mut draft = Draft::new("approval", 3).unwrap();
+    assert_eq!(draft.to_ballot(&IDS), None);
+    draft.toggle(2);
+    draft.toggle(0);
+    draft.toggle(7);
+    assert_eq!(draft.to_ballot(&IDS), Some(json!(["a", "c"])));
+    draft.toggle(0);
+    assert_eq!(draft.to_ballot(&IDS), Some(json!(["c"])));
+}
+
+
+#[test]
+fn options_move_one_place_and_stop_at_the_ends()
+{
+    let mut draft = Draft::new("ranked", 3).unwrap();
+    draft.move_up(2);
+    assert_eq!(draft.to_ballot(&IDS), Some(json!(["a", "c", "b"])));
+    draft.move_up(0);
+    draft.move_down(2);
+    draft.move_down(5);
+    assert_eq!(draft.to_ballot(&IDS), Some(json!(["a", "c", "b"])));
+    draft.move_down(0);
+    assert_eq!(draft.to_ballot(&IDS), Some(json!(["c", "a", "b"])));
+}
+
+
+#[test]
+fn scores_are_capped_and_sent_for_every_option()
+{
+    let mut draft = Draft::new("score", 3).unwrap();
+    draft.set_score(1, 9, 5);
+    draft.set_score(2, 3, 5);
+    assert_eq!(draft, Draft::Score(vec![0, 5, 3]));
+    assert_eq!(draft.to_ballot(&IDS), Some(json!({ "a": 0, "b": 5, "c": 3 })));
+}
+
+
+#[test]
+fn edits_for_another_method_are_ignored()
+{
+    let mut draft = Draft::new("approval", 3).unwrap();
+    draft.move_up(1);
+    draft.set_score(0, 3, 5);
+    assert_eq!(draft, Draft::Approval(vec![false, false, false]));
+}
+
+
+#[test]
+fn a_cast_ballot_fills_the_draft()
+{
+    assert_eq!(Draft::from_ballot("approval", &json!(["c", "x"]), &IDS),
+        Some(Draft::Approval(vec![false, false, true])));
+    assert_eq!(Draft::from_ballot("approval", &json!("b"), &IDS), Some(Draft::Approval(vec![false, true, false])));
+    assert_eq!(Draft::from_ballot("ranked", &json!(["c", "a"]), &IDS), Some(Draft::Ranked(vec![2, 0, 1])));
+    assert_eq!(Draft::from_ballot("score", &json!({ "b": 4 }), &IDS), Some(Draft::Score(vec![0, 4, 0])));
+    assert_eq!(Draft::from_ballot("score", &json!(["a"]), &IDS), None);
+    assert_eq!(Draft::from_ballot("ranked", &json!({ "a": 1 }), &IDS), None);
+    assert_eq!(Draft::from_ballot("single", &json!("a"), &IDS), None);
+}
@@ -0,0 +1,83 @@
+use vote_yew_app::choice::{key_action, move_focus, option_states, Key, OptionState};
+
+
+#[test]
+fn nothing_checked_puts_the_first_option_in_the_tab_order()
+{
+    let states = option_states(3, None, None);
+    assert_eq!(states, vec![
+        OptionState { checked: false, tab_stop: true, dimmed: false },
+        OptionState { checked: false, tab_stop: false, dimmed: false },
+        OptionState { checked: false, tab_stop: false, dimmed: false },
+    ]);
+    assert_eq!(states.iter().map(OptionState::tabindex).collect::<Vec<_>>(), vec!["0", "-1", "-1"]);
+}
+
+
+#[test]
+fn the_checked_option_is_the_tab_stop_and_dims_the_others()
+{
+    let states = option_states(3, Some(1), None);
+    assert_eq!(states.iter().map(OptionState::aria_checked).collect::<Vec<_>>(), vec!["false", "true", "false"]);
+    assert_eq!(states.iter().map(|state| state.tab_stop).collect::<Vec<_>>(), vec![false, true, false]);
+    assert_eq!(states.iter().map(|state| state.dimmed).collect::<Vec<_>>(), vec![true, false, true]);
+}
+
+
+#[test]
+fn the_focused_option_wins_the_tab_stop()
+{
+    let states = option_states(3, Some(0), Some(2));
+    assert_eq!(states.iter().map(|state| state.tab_stop).collect::<Vec<_>>(), vec![false, false, true]);
+    assert!(states[0].checked);
+}
+
+
+#[test]
+fn indexes_past_the_options_are_ignored()
+{
+    let states = option_states(2, Some(1), Some(5));
+    assert_eq!(states.iter().map(|state| state.tab_stop).collect::<Vec<_>>(), vec![false, true]);
+    let states = option_states(2, Some(7), Some(5));
+    assert_eq!(states.iter().map(|state| state.tab_stop).collect::<Vec<_>>(), vec![true, false]);
+    assert!(states.iter().all(|state| !state.checked));
+    assert!(option_states(0, Some(0), Some(0)).is_empty());
+}
+
+
+#[test]
+fn arrows_follow_the_reading_direction()
+{
+    assert_eq!(key_action("ArrowDown", false), Some(Key::Next));
+    assert_eq!(key_action("ArrowUp", false), Some(Key::Previous));
+    assert_eq!(key_action("ArrowRight", false), Some(Key::Next));
+    assert_eq!(key_action("ArrowLeft", false), Some(Key::Previous));
+    assert_eq!(key_action("ArrowRight", true), Some(Key::Previous));
+    assert_eq!(key_action("ArrowLeft", true), Some(Key::Next));
+    assert_eq!(key_action("ArrowDown", true), Some(Key::Next));
+}
+
+
+#[test]
+fn space_and_enter_choose_and_other_keys_are_left_alone()
+{
+    assert_eq!(key_action(" ", false), Some(Key::Choose));
+    assert_eq!(key_action("Enter", false), Some(Key::Choose));
+    assert_eq!(key_action("Home", false), Some(Key::First));
+    assert_eq!(key_action("End", false), Some(Key::Last));
+    assert_eq!(key_action("Tab", false), None);
+    assert_eq!(key_action("a", false), None);
+}
+
+
+#[test]
+fn focus_wraps_around_both_ends()
+{
+    assert_eq!(move_focus(0, 3, Key::Next), 1);
+    assert_eq!(move_focus(2, 3, Key::Next), 0);
+    assert_eq!(move_focus(0, 3, Key::Previous), 2);
+    assert_eq!(move_focus(1, 3, Key::First), 0);
+    assert_eq!(move_focus(1, 3, Key::Last), 2);
+    assert_eq!(move_focus(1, 3, Key::Choose), 1);
+    assert_eq!(move_focus(0, 0, Key::Next), 0);
+}
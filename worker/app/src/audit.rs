@@ -0,0 +1,58 @@
+use common::audit::{Append, AppendStep, AuditEntry, AuditEvent, Inserted, DUPLICATE_KEY_CODE};
+
+
+/// The hash-chained audit log, stored next to the votes. Entries are documents with their number as `_id`.
+pub struct AuditLog
+{
+    entries: mongodb::Collection,
+}
+
+
+fn inserted(result: mongodb::error::Result<mongodb::results::InsertOneResult>) -> Inserted<mongodb::error::Error>
+{
+    match result
+    {
+        Ok(_) => Inserted::Stored,
+        Err(e) if matches!(e.kind.as_ref(),
+                mongodb::error::ErrorKind::WriteError(mongodb::error::WriteFailure::WriteError(e))
+                    if e.code == DUPLICATE_KEY_CODE) => Inserted::NumberTaken,
+        Err(e) => Inserted::Failed(e),
+    }
+}
+
+
+impl AuditLog
+{
+    pub fn new(entries: mongodb::Collection) -> AuditLog
+    {
+        AuditLog { entries }
+    }
+
+
+    async fn last(&self) -> mongodb::error::Result<Option<AuditEntry>>
+    {
+        let options = mongodb::options::FindOneOptions::builder().sort(mongodb::bson::doc! { "_id": -1 }).build();
+        let last = self.entries.find_one(None, options).await?;
+        Ok(last.map(mongodb::bson::from_document).transpose()?)
+    }
+
+
+    /// Chains the event onto the latest entry; `common::audit::Append` decides when another writer got there
+    /// first. The result app appends to the same log.
+    pub async fn append(&self, event: &AuditEvent) -> mongodb::error::Result<AuditEntry>
+    {
+        let append = Append::new(event);
+        loop
+        {
+            let entry = append.entry(self.last().await?.as_ref());
+            // Strings and an i64 map directly to BSON, so this cannot fail.
+            let document = mongodb::bson::to_document(&entry).unwrap();
+            match append.inserted(entry, inserted(self.entries.insert_one(document, None).await))
+            {
+                AppendStep::Done(entry) => return Ok(entry),
+                AppendStep::Retry => continue,
+                AppendStep::Failed(e) => return Err(e),
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Moves every dead-lettered vote back to the head of the vote queue, in the order they were dead-lettered, and
+//! records the replay in the audit log. Run it once whatever made the votes fail is fixed; a vote that fails
+//! again `vote_max_attempts` times is dead-lettered again.
+//!
+//! The audit entry names the operator from `--audit-actor`, or `$USER` without it.
+//! Exits with 0 when the queue was replayed and 2 when redis failed before it was done.
+use redis::Commands;
+use common::audit::AuditEvent;
+use common::config::{ConfigError, ConfigLoader};
+use common::vote_status::{self, VoteState, VoteStatus};
+
+
+const EXIT_REPLAYED: i32 = 0;
+const EXIT_UNREACHABLE: i32 = 2;
+
+
+struct Config
+{
+    redis_addr: String,
+    redis_key: String,
+    redis_dead_letter_key: String,
+    redis_audit_key: String,
+    audit_actor: String,
+}
+
+
+impl Config
+{
+    fn load() -> Result<Config, ConfigError>
+    {
+        let mut loader = ConfigLoader::load();
+        let redis_addr: Option<String> = loader.required("redis_addr");
+        let redis_key: Option<String> = loader.required("redis_key");
+        let redis_dead_letter_key = loader.optional("redis_dead_letter_key", "votes_dead_letter".to_owned());
+        let redis_audit_key = loader.optional("redis_audit_key", "audit_events".to_owned());
+        let user = std::env::var("USER").unwrap_or_else(|_| "operator".to_owned());
+        let audit_actor = loader.optional("audit_actor", user);
+        loader.check(!audit_actor.is_empty(), || "audit_actor must not be empty".to_owned());
+        loader.finish()?;
+
+        Ok(Config
+        {
+            redis_addr: redis_addr.unwrap(),
+            redis_key: redis_key.unwrap(),
+            redis_dead_letter_key,
+            redis_audit_key,
+            audit_actor,
+        })
+    }
+}
+
+
+#[derive(Default)]
+struct Replayed
+{
+    count: usize,
+    /// Of the replayed votes that have one, oldest first.
+    request_ids: Vec<String>,
+}
+
+
+fn replay(config: &Config, connection: &mut redis::Connection, replayed: &mut Replayed) -> redis::RedisResult<()>
+{
+    // Each move takes the newest dead letter to the head of the queue, so the oldest ends up first in line.
+    let (dead_letters, queue) = (&config.redis_dead_letter_key, &config.redis_key);
+    while let Some(data) = connection.rpoplpush::<_, _, Option<String>>(dead_letters, queue)?
+    {
+        replayed.count += 1;
+        let request_id = serde_json::from_str::<serde_json::Value>(&data).ok()
+            .and_then(|vote| vote.get("request_id").and_then(|id| id.as_str()).map(str::to_owned));
+        if let Some(request_id) = request_id
+        {
+            replayed.request_ids.insert(0, request_id.clone());
+            let status = serde_json::to_string(&VoteStatus::new(VoteState::Queued)).unwrap();
+            connection.set_ex::<_, _, ()>(
+                vote_status::status_key(&request_id), status, vote_status::STATUS_TTL_SECS as usize)?;
+        }
+    }
+    Ok(())
+}
+
+
+fn main()
+{
+    let config = match Config::load()
+    {
+        Ok(config) => config,
+        Err(e) =>
+            {
+                eprintln!("{}", e);
+                std::process::exit(common::config::EXIT_INVALID_CONFIG);
+            },
+    };
+
+    let connected = redis::Client::open(config.redis_addr.as_str()).and_then(|client| client.get_connection());
+    let mut connection = match connected
+    {
+        Ok(connection) => connection,
+        Err(e) =>
+            {
+                eprintln!("could not connect to redis: {}", e);
+                std::process::exit(EXIT_UNREACHABLE);
+            },
+    };
+    let mut replayed = Replayed::default();
+    let mut exit_code = EXIT_REPLAYED;
+    if let Err(e) = replay(&config, &mut connection, &mut replayed)
+    {
+        // Votes moved before the failure are back in the queue and the rest are still dead-lettered, so running
+        // this again finishes the job.
+        eprintln!("replay interrupted: {}", e);
+        exit_code = EXIT_UNREACHABLE;
+    }
+    println!("{} dead-lettered votes replayed", replayed.count);
+
+    if replayed.count > 0
+    {
+        let details = serde_json::json!({ "count": replayed.count, "request_ids": replayed.request_ids });
+        let event = AuditEvent::new(format!("cli:{}", config.audit_actor), "dead_letters_replayed", details);
+        let queued: redis::RedisResult<u64> =
+            connection.rpush(&config.redis_audit_key, serde_json::to_string(&event).unwrap());
+        if let Err(e) = queued
+        {
+            eprintln!("could not record the replay in the audit log: {}", e);
+            exit_code = EXIT_UNREACHABLE;
+        }
+    }
+    std::process::exit(exit_code);
+}
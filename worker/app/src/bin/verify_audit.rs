@@ -0,0 +1,95 @@
+//! Reads the whole audit log and checks its hash chain: every entry must be numbered one past the last, point
+//! at the last entry's hash and still match its own. Pass `--audit-expected-head <hash>`, the head printed by an
+//! earlier run, to also catch entries cut off the end since then.
+//!
+//! Exits with 0 when the chain is intact, 1 when it is not and 2 when the log cannot be read.
+use common::audit::{self, AuditEntry};
+use common::config::{ConfigError, ConfigLoader};
+use tokio::stream::StreamExt;
+
+
+const EXIT_INTACT: i32 = 0;
+const EXIT_TAMPERED: i32 = 1;
+const EXIT_UNREADABLE: i32 = 2;
+
+
+struct Config
+{
+    mongodb_addr: String,
+    mongodb_db_name: String,
+    mongodb_audit_collection_name: String,
+    audit_expected_head: Option<String>,
+}
+
+
+impl Config
+{
+    fn load() -> Result<Config, ConfigError>
+    {
+        let mut loader = ConfigLoader::load();
+        let mongodb_addr: Option<String> = loader.required("mongodb_addr");
+        let mongodb_db_name: Option<String> = loader.required("mongodb_db_name");
+        let mongodb_audit_collection_name = loader.optional("mongodb_audit_collection_name", "audit_log".to_owned());
+        let audit_expected_head = loader.get("audit_expected_head");
+        loader.finish()?;
+
+        Ok(Config
+        {
+            mongodb_addr: mongodb_addr.unwrap(),
+            mongodb_db_name: mongodb_db_name.unwrap(),
+            mongodb_audit_collection_name,
+            audit_expected_head,
+        })
+    }
+}
+
+
+async fn read_entries(config: &Config) -> mongodb::error::Result<Vec<AuditEntry>>
+{
+    let client = mongodb::Client::with_uri_str(&config.mongodb_addr).await?;
+    let entries = client.database(&config.mongodb_db_name).collection(&config.mongodb_audit_collection_name);
+    let options = mongodb::options::FindOptions::builder().sort(mongodb::bson::doc! { "_id": 1 }).build();
+    let mut cursor = entries.find(None, options).await?;
+    let mut read = Vec::new();
+    while let Some(entry) = cursor.next().await
+    {
+        read.push(mongodb::bson::from_document(entry?)?);
+    }
+    Ok(read)
+}
+
+
+#[tokio::main]
+async fn main()
+{
+    let config = match Config::load()
+    {
+        Ok(config) => config,
+        Err(e) =>
+            {
+                eprintln!("{}", e);
+                std::process::exit(common::config::EXIT_INVALID_CONFIG);
+            },
+    };
+
+    let entries = match read_entries(&config).await
+    {
+        Ok(entries) => entries,
+        Err(e) =>
+            {
+                eprintln!("could not read the audit log: {}", e);
+                std::process::exit(EXIT_UNREADABLE);
+            },
+    };
+    let verification = audit::verify(entries, config.audit_expected_head.as_deref());
+    for problem in &verification.problems
+    {
+        println!("{}", problem);
+    }
+    match &verification.head
+    {
+        Some(head) => println!("{} entries, head {} {}", verification.entries, head.seq, head.hash),
+        None => println!("the audit log is empty"),
+    }
+    std::process::exit(if verification.problems.is_empty() { EXIT_INTACT } else { EXIT_TAMPERED });
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use common::bulletin::MerkleTree;
+
+
+/// The bulletin board of every poll: one document per commitment, `{ _id: commitment, poll_id, index }`, and
+/// one per poll in the roots collection, `{ _id: poll_id, size, root }`, rewritten after each append.
+///
+/// Leaf indexes come from the tree kept here, so only one worker may append to a poll's board.
+pub struct BulletinBoard
+{
+    leaves: mongodb::Collection,
+    roots: mongodb::Collection,
+    trees: HashMap<String, MerkleTree>,
+}
+
+
+impl BulletinBoard
+{
+    pub fn new(leaves: mongodb::Collection, roots: mongodb::Collection) -> BulletinBoard
+    {
+        BulletinBoard { leaves, roots, trees: HashMap::new() }
+    }
+
+
+    async fn load(&self, poll_id: &str) -> mongodb::error::Result<MerkleTree>
+    {
+        use tokio::stream::StreamExt;
+
+        let options = mongodb::options::FindOptions::builder().sort(mongodb::bson::doc! { "index": 1 }).build();
+        let mut cursor = self.leaves.find(mongodb::bson::doc! { "poll_id": poll_id }, options).await?;
+        let mut tree = MerkleTree::new();
+        while let Some(leaf) = cursor.next().await
+        {
+            if let Ok(commitment) = leaf?.get_str("_id")
+            {
+                tree.push(commitment);
+            }
+        }
+        Ok(tree)
+    }
+
+
+    /// Adds a commitment and publishes the new root. Appending one that is already on the board only
+    /// republishes the root, so a requeued vote can go through this again.
+    pub async fn append(&mut self, poll_id: &str, commitment: &str) -> mongodb::error::Result<()>
+    {
+        let mut tree = match self.trees.remove(poll_id)
+        {
+            Some(tree) => tree,
+            None => self.load(poll_id).await?,
+        };
+        let existing = self.leaves.find_one(mongodb::bson::doc! { "_id": commitment }, None).await?;
+        if existing.is_none()
+        {
+            let leaf = mongodb::bson::doc! { "_id": commitment, "poll_id": poll_id, "index": tree.len() as i64 };
+            // On failure the tree is dropped and read again next time, in case the insert did land.
+            self.leaves.insert_one(leaf, None).await?;
+            tree.push(commitment);
+        }
+        let root = mongodb::bson::doc! { "$set": { "size": tree.len() as i64, "root": tree.root() } };
+        let options = mongodb::options::UpdateOptions::builder().upsert(true).build();
+        let published = self.roots.update_one(
+                mongodb::bson::doc! { "_id": poll_id },
+                mongodb::options::UpdateModifications::Document(root),
+                options,
+            )
+            .await;
+        self.trees.insert(poll_id.to_owned(), tree);
+        published?;
+        tracing::debug!(poll_id, "bulletin board root published");
+        Ok(())
+    }
+}
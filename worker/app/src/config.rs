@@ -0,0 +1,113 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+use common::anonymity::Anonymity;
+use common::config::{ConfigError, ConfigLoader};
+use common::poll::PollWindow;
+use common::telemetry::TelemetryConfig;
+
+
+pub struct Config
+{
+    pub health_bind: SocketAddr,
+    pub redis_addr: String,
+    pub redis_key: String,
+    /// Audit events queued by the vote app, which the worker appends to the audit log.
+    pub redis_audit_key: String,
+    /// Votes that failed `vote_max_attempts` times, or could not be read, wait here for `replay_dead_letters`.
+    pub redis_dead_letter_key: String,
+    /// Counts only attempts MongoDB refused while it and Redis were connected, so an outage dead-letters nothing.
+    pub vote_max_attempts: u32,
+    pub mongodb_addr: String,
+    pub mongodb_db_name: String,
+    pub mongodb_collection_name: String,
+    /// Who has voted, kept apart from the ballots in strict anonymity mode.
+    pub mongodb_participation_collection_name: String,
+    /// Commitments of every ballot, per poll; the roots go in the same name with `_roots` appended.
+    pub mongodb_bulletin_board_collection_name: String,
+    pub mongodb_audit_collection_name: String,
+    pub poll_interval: Duration,
+    pub drain_timeout: Duration,
+    pub poll: PollWindow,
+    pub anonymity: Anonymity,
+    pub telemetry: TelemetryConfig,
+}
+
+
+impl Config
+{
+    pub fn load() -> Result<Config, ConfigError>
+    {
+        let mut loader = ConfigLoader::load();
+        let health_bind = loader.optional("health_bind", SocketAddr::from(([0, 0, 0, 0], 8080)));
+        let redis_addr: Option<String> = loader.required("redis_addr");
+        let redis_key: Option<String> = loader.required("redis_key");
+        let redis_audit_key: String = loader.optional("redis_audit_key", "audit_events".to_owned());
+        let redis_dead_letter_key: String = loader.optional("redis_dead_letter_key", "votes_dead_letter".to_owned());
+        let vote_max_attempts = loader.optional("vote_max_attempts", 10);
+        let mongodb_addr: Option<String> = loader.required("mongodb_addr");
+        let mongodb_db_name: Option<String> = loader.required("mongodb_db_name");
+        let mongodb_collection_name: Option<String> = loader.required("mongodb_collection_name");
+        let mongodb_participation_collection_name: String =
+            loader.optional("mongodb_participation_collection_name", "participation".to_owned());
+        let mongodb_bulletin_board_collection_name: String =
+            loader.optional("mongodb_bulletin_board_collection_name", "bulletin_board".to_owned());
+        let mongodb_audit_collection_name: String =
+            loader.optional("mongodb_audit_collection_name", "audit_log".to_owned());
+        let poll_interval_ms = loader.optional("poll_interval_ms", 500);
+        let drain_timeout_secs = loader.optional("drain_timeout_secs", 10);
+        let poll = PollWindow::load(&mut loader);
+        let anonymity = Anonymity::load(&mut loader);
+        let telemetry = TelemetryConfig::load(&mut loader);
+
+        if let Some(redis_addr) = &redis_addr
+        {
+            loader.check(redis_addr.starts_with("redis://"), ||
+                format!("redis_addr must be a redis:// url, got '{}'", redis_addr));
+        }
+        if let Some(mongodb_addr) = &mongodb_addr
+        {
+            loader.check(mongodb_addr.starts_with("mongodb://") || mongodb_addr.starts_with("mongodb+srv://"), ||
+                format!("mongodb_addr must be a mongodb:// url, got '{}'", mongodb_addr));
+        }
+        for (key, value) in &[("redis_key", &redis_key), ("mongodb_db_name", &mongodb_db_name),
+            ("mongodb_collection_name", &mongodb_collection_name)]
+        {
+            loader.check(!matches!(value, Some(value) if value.is_empty()), || format!("{} must not be empty", key));
+        }
+        loader.check(!mongodb_participation_collection_name.is_empty() &&
+            Some(&mongodb_participation_collection_name) != mongodb_collection_name.as_ref(), ||
+            "mongodb_participation_collection_name must be set and differ from mongodb_collection_name".to_owned());
+        loader.check(!mongodb_bulletin_board_collection_name.is_empty(), ||
+            "mongodb_bulletin_board_collection_name must not be empty".to_owned());
+        loader.check(!redis_audit_key.is_empty() && Some(&redis_audit_key) != redis_key.as_ref(), ||
+            "redis_audit_key must be set and differ from redis_key".to_owned());
+        loader.check(!redis_dead_letter_key.is_empty() && Some(&redis_dead_letter_key) != redis_key.as_ref(), ||
+            "redis_dead_letter_key must be set and differ from redis_key".to_owned());
+        loader.check(vote_max_attempts > 0, || "vote_max_attempts must be positive".to_owned());
+        loader.check(!mongodb_audit_collection_name.is_empty(), ||
+            "mongodb_audit_collection_name must not be empty".to_owned());
+        loader.check(poll_interval_ms > 0, || "poll_interval_ms must be positive".to_owned());
+        loader.finish()?;
+
+        Ok(Config
+        {
+            health_bind,
+            redis_addr: redis_addr.unwrap(),
+            redis_key: redis_key.unwrap(),
+            redis_audit_key,
+            redis_dead_letter_key,
+            vote_max_attempts,
+            mongodb_addr: mongodb_addr.unwrap(),
+            mongodb_db_name: mongodb_db_name.unwrap(),
+            mongodb_collection_name: mongodb_collection_name.unwrap(),
+            mongodb_participation_collection_name,
+            mongodb_bulletin_board_collection_name,
+            mongodb_audit_collection_name,
+            poll_interval: Duration::from_millis(poll_interval_ms),
+            drain_timeout: Duration::from_secs(drain_timeout_secs),
+            poll,
+            anonymity,
+            telemetry,
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use common::connection::{ConnectionState, ConnectionStatus};
+
+use crate::metrics;
+
+
+/// Worker progress shared between the processing loop and the health listener.
+pub struct Health
+{
+    pub redis: ConnectionStatus,
+    pub mongodb: ConnectionStatus,
+    last_successful_pop: AtomicU64,
+    queue_depth: AtomicU64,
+}
+
+
+#[derive(Serialize)]
+struct HealthReport
+{
+    status: &'static str,
+    redis: ConnectionState,
+    mongodb: ConnectionState,
+    last_successful_pop: Option<u64>,
+    queue_depth: u64,
+}
+
+
+fn unix_timestamp() -> u64
+{
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
+
+
+impl Default for Health
+{
+    fn default() -> Health
+    {
+        Health
+        {
+            redis: ConnectionStatus::new("redis"),
+            mongodb: ConnectionStatus::new("mongodb"),
+            last_successful_pop: AtomicU64::new(0),
+            queue_depth: AtomicU64::new(0),
+        }
+    }
+}
+
+
+impl Health
+{
+    pub fn record_pop(&self)
+    {
+        self.last_successful_pop.store(unix_timestamp(), Ordering::Relaxed);
+    }
+
+
+    pub fn record_queue_depth(&self, queue_depth: u64)
+    {
+        self.queue_depth.store(queue_depth, Ordering::Relaxed);
+        metrics::observe_queue_depth(queue_depth);
+    }
+
+
+    fn report(&self) -> HealthReport
+    {
+        let redis = self.redis.state();
+        let mongodb = self.mongodb.state();
+        let last_successful_pop = match self.last_successful_pop.load(Ordering::Relaxed)
+        {
+            0 => None,
+            timestamp => Some(timestamp),
+        };
+        HealthReport
+        {
+            status: if redis == ConnectionState::Reconnecting || mongodb == ConnectionState::Reconnecting
+                {
+                    "unavailable"
+                }
+                else
+                {
+                    "ok"
+                },
+            redis,
+            mongodb,
+            last_successful_pop,
+            queue_depth: self.queue_depth.load(Ordering::Relaxed),
+        }
+    }
+}
+
+
+async fn respond(mut stream: TcpStream, health: &Health) -> std::io::Result<()>
+{
+    let mut buffer = [0; 1024];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+
+    let report = health.report();
+    let json = "application/json".to_owned();
+    let (status_line, content_type, body) = match path
+    {
+        "/healthz" => ("200 OK", json, serde_json::to_string(&report).unwrap()),
+        "/readyz" if report.status == "ok" => ("200 OK", json, serde_json::to_string(&report).unwrap()),
+        "/readyz" => ("503 Service Unavailable", json, serde_json::to_string(&report).unwrap()),
+        "/metrics" =>
+            {
+                let (content_type, body) = metrics::encode();
+                ("200 OK", content_type, body)
+            },
+        _ => ("404 Not Found", json, String::new()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status_line, content_type, body.len(), body);
+    stream.write_all(response.as_bytes()).await
+}
+
+
+/// Serves `/healthz`, `/readyz` and `/metrics` so orchestrators can see whether votes are being drained.
+pub async fn serve(bind: SocketAddr, health: Arc<Health>) -> std::io::Result<()>
+{
+    let mut listener = TcpListener::bind(bind).await?;
+    tracing::info!(%bind, "health listener started");
+    loop
+    {
+        let (stream, _) = listener.accept().await?;
+        let health = health.clone();
+        tokio::spawn(async move
+            {
+                if let Err(e) = respond(stream, &health).await
+                {
+                    tracing::warn!(error = %e, "health request failed");
+                }
+            });
+    }
+}
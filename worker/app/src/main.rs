@@ -1,85 +1,676 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use serde::Deserialize;
-use serde_json;
 use redis::AsyncCommands;
-use mongodb;
+use common::anonymity::{self, Anonymity, AnonymityMode};
+use common::audit::AuditEvent;
+use common::connection::{ConnectionState, ConnectionStatus};
+use common::ballot::Ballot;
+use common::poll::{self, PollStatus, VotingMethod};
+use common::vote_status::{self, VoteState, VoteStatus};
+use tracing::Instrument;
+
+mod audit;
+mod board;
+mod config;
+mod health;
+mod metrics;
+mod shutdown;
+
+use audit::AuditLog;
+use board::BulletinBoard;
+use config::Config;
+use shutdown::Shutdown;
 
 
 #[derive(Debug, Deserialize)]
 struct Vote
 {
+    /// Set by the vote app; votes queued before request ids existed have none.
+    request_id: Option<String>,
     voter_id: String,
-    vote: String,
+    /// Missing on votes queued before polls declared a method; those are single choice.
+    method: Option<VotingMethod>,
+    vote: Ballot,
+    group: Option<String>,
+    /// Missing on votes queued before voter groups existed; those count once.
+    weight: Option<u32>,
+    queued_at: Option<u64>,
+    /// Both missing on votes queued before the bulletin board existed; those are not published.
+    poll_id: Option<String>,
+    commitment: Option<String>,
+}
+
+
+/// What the vote app is told about a vote once it is dealt with, so `GET /my-vote` stops reporting it pending.
+struct Choice
+{
+    key: String,
+    request_id: String,
+    ballot: String,
+}
+
+
+impl Choice
+{
+    /// Nothing is recorded under strict anonymity, nor for votes queued before the vote app kept track.
+    fn of(anonymity: &Anonymity, vote: &Vote) -> Option<Choice>
+    {
+        if anonymity.mode == AnonymityMode::Strict
+        {
+            return None;
+        }
+        let (poll_id, request_id) = (vote.poll_id.as_ref()?, vote.request_id.as_ref()?);
+        Some(Choice
+        {
+            key: poll::choice_key(poll_id, &anonymity.voter_label(&vote.voter_id)),
+            request_id: request_id.clone(),
+            ballot: serde_json::to_string(&vote.vote).unwrap(),
+        })
+    }
+
+
+    /// Marks the vote applied, and when it was stored rather than rejected, makes it the voter's recorded ballot.
+    async fn record_applied(&self, connection: &mut redis::aio::Connection, stored: bool) -> redis::RedisResult<()>
+    {
+        let mut command = redis::cmd("HSET");
+        command.arg(&self.key).arg("applied_request_id").arg(&self.request_id);
+        if stored
+        {
+            command.arg("vote").arg(&self.ballot);
+        }
+        redis::pipe()
+            .atomic()
+            .add_command(command).ignore()
+            .cmd("EXPIRE").arg(&self.key).arg(poll::CHOICE_TTL_SECS).ignore()
+            .query_async(connection)
+            .await
+    }
+
+
+    /// Drops the vote from the voter's pending one once it is dead-lettered, so `GET /my-vote` shows what they
+    /// had recorded before it instead of a vote that will not be stored.
+    async fn clear_pending(&self, connection: &mut redis::aio::Connection) -> redis::RedisResult<()>
+    {
+        redis::cmd("EVAL")
+            .arg(poll::CLEAR_PENDING_SCRIPT)
+            .arg(1)
+            .arg(&self.key)
+            .arg(&self.request_id)
+            .query_async(connection)
+            .await
+    }
 }
 
 
-async fn connect_to_redis() -> redis::RedisResult<redis::aio::Connection>
+enum Processed
+{
+    Stored,
+    /// Queued outside the poll window, or a second vote when ballots are final, so it is dropped.
+    Rejected { reason: &'static str },
+    /// Put back in the queue. Only failures of the vote itself `count` toward dead-lettering it, not outages.
+    Requeued { retry_in: Duration, counts: bool },
+    Lost,
+}
+
+
+async fn connect_to_redis(redis_addr: &str) -> redis::RedisResult<redis::aio::Connection>
 {
-    dotenv::dotenv().ok();
-    let redis_addr = std::env::var("REDIS_ADDR").expect("REDIS_ADDR must be set");
     let client = redis::Client::open(redis_addr)?;
     let connection = client.get_async_connection().await?;
     Ok(connection)
 }
 
 
-async fn connect_to_mongodb() -> mongodb::error::Result<mongodb::Client>
+async fn connect_to_mongodb(mongodb_addr: &str) -> mongodb::error::Result<mongodb::Client>
 {
-    dotenv::dotenv().ok();
-    let mongodb_addr = std::env::var("MONGODB_ADDR").expect("MONGODB_ADDR must be set");
-    let client = mongodb::Client::with_uri_str(&mongodb_addr).await?;
+    let client = mongodb::Client::with_uri_str(mongodb_addr).await?;
+    client.database("admin").run_command(mongodb::bson::doc! { "ping": 1 }, None).await?;
     Ok(client)
 }
 
 
-#[tokio::main]
-async fn main()
+/// Calls `connect` until it succeeds, waiting for the backoff delay handed out by `status` between attempts.
+/// Gives up only when shutdown is requested.
+async fn connect_with_retry<T, E, F, Fut>(status: &ConnectionStatus, shutdown: &mut Shutdown, mut connect: F)
+    -> Option<T>
+    where E: fmt::Display, F: FnMut() -> Fut, Fut: Future<Output = Result<T, E>>
 {
-    dotenv::dotenv().ok();
-    let redis_key = std::env::var("REDIS_KEY").expect("REDIS_KEY must be set");
-    let mongodb_db_name = std::env::var("MONGODB_DB_NAME").expect("MONGODB_DB_NAME must be set");
-    let mongodb_collection_name = std::env::var("MONGODB_COLLECTION_NAME").expect("MONGODB_COLLECTION_NAME must be set");
-    if let Ok(mut connection) = connect_to_redis().await
+    loop
     {
-        if let Ok(client) = connect_to_mongodb().await
+        match connect().await
         {
-            let database = client.database(&mongodb_db_name);
-            let collection = database.collection(&mongodb_collection_name);
-            loop
-            {
-                if let Ok(data) = connection.lpop::<_, String>(&redis_key).await
+            Ok(connection) =>
+                {
+                    status.record_success();
+                    return Some(connection);
+                },
+            Err(e) =>
                 {
-                    let vote: Vote = serde_json::from_str(&data).unwrap();
+                    let delay = status.record_failure(&e);
+                    tokio::select!
+                    {
+                        _ = tokio::time::delay_for(delay) => (),
+                        _ = shutdown.requested() => return None,
+                    }
+                },
+        }
+    }
+}
+
+
+enum Stored
+{
+    Registered,
+    Updated,
+    /// Strict anonymity keeps no link to the earlier ballot, so a second vote cannot replace it.
+    AlreadyVoted,
+}
+
+
+/// Where votes end up: the ballots, in strict anonymity mode the separate record of who has voted, and the
+/// commitments published on the bulletin board.
+struct Collections
+{
+    ballots: mongodb::Collection,
+    participation: mongodb::Collection,
+    board: BulletinBoard,
+}
+
 
-                    let filter = mongodb::bson::doc! { "voter_id": vote.voter_id };
-                    let updated_document = mongodb::bson::doc! { "$set": { "vote": vote.vote } };
-                    let update_modifications = mongodb::options::UpdateModifications::Document(updated_document);
-                    let find_one_and_update_options = mongodb::options::FindOneAndUpdateOptions::builder().upsert(true).build();
-                    if let Ok(doc) = collection.find_one_and_update(filter, update_modifications, find_one_and_update_options).await
+async fn store_vote(collections: &Collections, anonymity: &Anonymity, vote: Vote) -> mongodb::error::Result<Stored>
+{
+    // Strings, arrays and documents map directly to BSON, so this cannot fail.
+    let ballot = mongodb::bson::to_bson(&vote.vote).unwrap();
+    let method = vote.method.unwrap_or(VotingMethod::Single).to_string();
+    let weight = i64::from(vote.weight.unwrap_or(1));
+    let (filter, updated_document) = match anonymity.mode
+    {
+        AnonymityMode::Off =>
+            {
+                let updated_document = mongodb::bson::doc!
+                {
+                    "$set":
                     {
-                        if let Some(_) = doc
-                        {
-                            println!("Vote was updated.");
-                        }
-                        else
-                        {
-                            println!("New vote was registered.")
-                        }
+                        "vote": ballot, "method": method, "group": vote.group, "weight": weight,
+                        "request_id": vote.request_id, "commitment": vote.commitment
                     }
-                }
-                else
+                };
+                (mongodb::bson::doc! { "voter_id": vote.voter_id }, updated_document)
+            },
+        // The request id is left out as well: it links the ballot to the vote app's logs, which name the voter.
+        AnonymityMode::Hashed =>
+            {
+                let updated_document = mongodb::bson::doc!
+                {
+                    "$set":
+                    {
+                        "vote": ballot, "method": method, "group": vote.group, "weight": weight,
+                        "commitment": vote.commitment
+                    }
+                };
+                (mongodb::bson::doc! { "voter_hash": anonymity.voter_hash(&vote.voter_id) }, updated_document)
+            },
+        AnonymityMode::Strict =>
+            {
+                let ballot = mongodb::bson::doc!
+                {
+                    "vote": ballot, "method": method, "weight": weight, "commitment": vote.commitment
+                };
+                let poll_id = vote.poll_id.unwrap_or_default();
+                return store_separately(collections, anonymity, &vote.voter_id, &poll_id, ballot).await;
+            },
+    };
+    let update_modifications = mongodb::options::UpdateModifications::Document(updated_document);
+    let find_one_and_update_options = mongodb::options::FindOneAndUpdateOptions::builder().upsert(true).build();
+    let previous = collections.ballots.find_one_and_update(filter, update_modifications, find_one_and_update_options).await?;
+    Ok(if previous.is_some() { Stored::Updated } else { Stored::Registered })
+}
+
+
+/// Records the voter as having voted in the poll, keyed by the poll and the hash of their id, and stores the
+/// ballot under a random id with nothing that points back to the voter. The group is dropped too, since small
+/// groups would narrow it down.
+///
+/// The participation record is marked counted only after the ballot is in, so a vote requeued between the two
+/// writes is stored on its next attempt instead of being refused as a duplicate. The ballot id is drawn when
+/// the record is created and kept there until then, so that attempt replaces the ballot rather than adding a
+/// second one; it is dropped as the record is marked counted, leaving nothing that leads to the ballot.
+async fn store_separately(
+        collections: &Collections, anonymity: &Anonymity, voter_id: &str, poll_id: &str,
+        mut ballot: mongodb::bson::Document,
+    )
+    -> mongodb::error::Result<Stored>
+{
+    let participation_id = mongodb::bson::doc! { "poll_id": poll_id, "voter_hash": anonymity.voter_hash(voter_id) };
+    let options = mongodb::options::FindOneAndUpdateOptions::builder()
+        .upsert(true)
+        .return_document(mongodb::options::ReturnDocument::After)
+        .build();
+    let participation = collections.participation.find_one_and_update(
+            mongodb::bson::doc! { "_id": participation_id.clone() },
+            mongodb::options::UpdateModifications::Document(
+                mongodb::bson::doc! { "$setOnInsert": { "counted": false, "ballot_id": anonymity::random_id() } }),
+            options,
+        )
+        .await?;
+    let ballot_id = match &participation
+    {
+        Some(record) if record.get_bool("counted").unwrap_or(false) => return Ok(Stored::AlreadyVoted),
+        Some(record) => record.get_str("ballot_id").map(str::to_owned).ok(),
+        None => None,
+    };
+    // Records left by a worker that did not keep ballot ids have none; a fresh one does for them.
+    let ballot_id = ballot_id.unwrap_or_else(anonymity::random_id);
+    ballot.insert("_id", ballot_id.clone());
+    let options = mongodb::options::ReplaceOptions::builder().upsert(true).build();
+    collections.ballots.replace_one(mongodb::bson::doc! { "_id": ballot_id }, ballot, options).await?;
+    collections.participation.update_one(
+            mongodb::bson::doc! { "_id": participation_id },
+            mongodb::options::UpdateModifications::Document(
+                mongodb::bson::doc! { "$set": { "counted": true }, "$unset": { "ballot_id": "" } }),
+            None,
+        )
+        .await?;
+    Ok(Stored::Registered)
+}
+
+
+/// Puts the vote's commitment on the bulletin board before storing the ballot, so every stored ballot can be
+/// proven; a commitment whose ballot is then refused or replaced stays on the board but is not counted.
+async fn publish_and_store(collections: &mut Collections, anonymity: &Anonymity, vote: Vote)
+    -> mongodb::error::Result<Stored>
+{
+    if let (Some(poll_id), Some(commitment)) = (&vote.poll_id, &vote.commitment)
+    {
+        collections.board.append(poll_id, commitment).await?;
+    }
+    store_vote(collections, anonymity, vote).await
+}
+
+
+/// Server codes for a document MongoDB will not take however often it is sent: BadValue, TypeMismatch,
+/// DollarPrefixedFieldName and DocumentValidationFailure.
+const REFUSED_DOCUMENT_CODES: [i32; 4] = [2, 14, 52, 121];
+
+
+/// Errors that would come back however often the vote is retried: MongoDB rejected the document itself, or
+/// the ballot could not be encoded for it. `findAndModify`, which the off and hashed modes write with, reports
+/// a refused document as a command error rather than a write error.
+fn is_refused_vote(e: &mongodb::error::Error) -> bool
+{
+    match e.kind.as_ref()
+    {
+        mongodb::error::ErrorKind::WriteError(mongodb::error::WriteFailure::WriteError(_))
+        | mongodb::error::ErrorKind::BsonEncode(_) => true,
+        mongodb::error::ErrorKind::CommandError(e) => REFUSED_DOCUMENT_CODES.contains(&e.code),
+        _ => false,
+    }
+}
+
+
+/// The raw vote names the voter next to the ballot, so it is only logged when that is not a secret.
+fn log_lost_vote(config: &Config, data: &str, message: &str)
+{
+    if config.anonymity.mode == AnonymityMode::Off
+    {
+        tracing::error!(vote = data, "{}", message);
+    }
+    else
+    {
+        tracing::error!("{}", message);
+    }
+}
+
+
+/// Stores a popped vote. Once shutdown is requested the store gets `drain_timeout` to finish, after which
+/// the raw vote goes back to the head of the queue. Storing it again is harmless if it did land: the other
+/// modes re-apply a `$set` and strict mode replaces the ballot under the id its participation record keeps.
+async fn process_vote(
+        connection: &mut redis::aio::Connection, config: &Config, collections: &mut Collections,
+        health: &health::Health, data: &str, vote: Vote, mut shutdown: Shutdown,
+    )
+    -> Processed
+{
+    let queued_at = vote.queued_at;
+    let started = Instant::now();
+    if let Some(status) = queued_at.map(|queued_at| config.poll.status_at_millis(queued_at))
+    {
+        if status != PollStatus::Open
+        {
+            tracing::warn!(?status, "vote was queued outside the poll window, dropping it");
+            metrics::observe_vote("rejected", started.elapsed());
+            let reason = if status == PollStatus::NotOpen { "poll_not_open" } else { "poll_closed" };
+            return Processed::Rejected { reason };
+        }
+    }
+    let stored = tokio::select!
+    {
+        stored = publish_and_store(collections, &config.anonymity, vote) => Some(stored),
+        _ = async { shutdown.requested().await; tokio::time::delay_for(config.drain_timeout).await } => None,
+    };
+    let (outcome, processed) = match stored
+    {
+        Some(Ok(stored)) =>
+            {
+                health.mongodb.record_success();
+                match stored
                 {
-                    println!("No votes in redis.");
+                    Stored::Registered =>
+                        {
+                            tracing::info!("new vote was registered");
+                            ("registered", Processed::Stored)
+                        },
+                    Stored::Updated =>
+                        {
+                            tracing::info!("vote was updated");
+                            ("updated", Processed::Stored)
+                        },
+                    Stored::AlreadyVoted =>
+                        {
+                            tracing::warn!("voter has already voted and ballots are final, dropping the vote");
+                            ("rejected", Processed::Rejected { reason: "already_voted" })
+                        },
                 }
-                tokio::time::delay_for(tokio::time::Duration::from_millis(500)).await;
-            }
+            },
+        Some(Err(e)) if is_refused_vote(&e) =>
+            {
+                // MongoDB answered, so the connection is fine; the vote is what it refused. While either backend
+                // is unsettled the refusal may still be a side effect of the outage, so it does not count.
+                tracing::warn!(error = %e, "MongoDB refused the vote");
+                let counts = health.redis.state() == ConnectionState::Connected
+                    && health.mongodb.state() == ConnectionState::Connected;
+                ("requeued", Processed::Requeued { retry_in: config.poll_interval, counts })
+            },
+        Some(Err(e)) =>
+            ("requeued", Processed::Requeued { retry_in: health.mongodb.record_failure(&e), counts: false }),
+        None => ("requeued", Processed::Requeued { retry_in: Duration::from_secs(0), counts: false }),
+    };
+    if let Processed::Requeued { .. } = processed
+    {
+        if let Err(e) = connection.lpush::<_, _, u64>(&config.redis_key, data).await
+        {
+            health.redis.record_failure(&e);
+            log_lost_vote(config, data, "could not store or re-queue vote");
+            metrics::observe_vote("lost", started.elapsed());
+            return Processed::Lost;
         }
-        else
+        tracing::warn!("could not store vote, it was put back in the queue");
+    }
+    metrics::observe_vote(outcome, started.elapsed());
+    if let Some(queued_at) = queued_at
+    {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        metrics::observe_queue_lag(now.checked_sub(Duration::from_millis(queued_at)).unwrap_or_default());
+    }
+    processed
+}
+
+
+async fn set_status(connection: &mut redis::aio::Connection, request_id: &str, status: &VoteStatus)
+    -> redis::RedisResult<()>
+{
+    let status = serde_json::to_string(status).unwrap();
+    connection.set_ex(vote_status::status_key(request_id), status, vote_status::STATUS_TTL_SECS as usize).await
+}
+
+
+/// Tells the vote app how a vote ended, for its status and for `GET /my-vote`.
+async fn report(
+        connection: &mut redis::aio::Connection, request_id: Option<&str>, choice: Option<&Choice>, status: VoteStatus,
+    )
+    -> redis::RedisResult<()>
+{
+    if let Some(request_id) = request_id
+    {
+        set_status(connection, request_id, &status).await?;
+    }
+    if let Some(choice) = choice
+    {
+        choice.record_applied(connection, status.state == VoteState::Applied).await?;
+    }
+    Ok(())
+}
+
+
+/// Moves a vote that cannot be stored to the dead-letter queue, where it waits for `replay_dead_letters`.
+async fn dead_letter(
+        connection: &mut redis::aio::Connection, config: &Config, data: &str, request_id: Option<&str>,
+        choice: Option<&Choice>,
+    )
+    -> redis::RedisResult<()>
+{
+    connection.rpush::<_, _, u64>(&config.redis_dead_letter_key, data).await?;
+    metrics::observe_vote("dead_lettered", Duration::from_secs(0));
+    if let Some(request_id) = request_id
+    {
+        set_status(connection, request_id, &VoteStatus::with_reason(VoteState::Failed, "dead_lettered")).await?;
+    }
+    if let Some(choice) = choice
+    {
+        choice.clear_pending(connection).await?;
+    }
+    Ok(())
+}
+
+
+/// Appends the audit events the vote app queued. An event that cannot be appended goes back to the head of its
+/// queue and is tried again on the next pass.
+async fn forward_audit_events(
+        connection: &mut redis::aio::Connection, config: &Config, audit_log: &AuditLog, health: &health::Health,
+    )
+    -> redis::RedisResult<()>
+{
+    while let Some(data) = connection.lpop::<_, Option<String>>(&config.redis_audit_key).await?
+    {
+        let event: AuditEvent = match serde_json::from_str(&data)
+        {
+            Ok(event) => event,
+            Err(e) =>
+                {
+                    tracing::error!(error = %e, event = %data, "dropping malformed audit event");
+                    continue;
+                },
+        };
+        match audit_log.append(&event).await
         {
-            println!("Could not connect to mongodb!!!");
+            Ok(_) => health.mongodb.record_success(),
+            Err(e) =>
+                {
+                    health.mongodb.record_failure(&e);
+                    if let Err(e) = connection.lpush::<_, _, u64>(&config.redis_audit_key, &data).await
+                    {
+                        tracing::error!(event = %data, "could not append or re-queue audit event");
+                        return Err(e);
+                    }
+                    break;
+                },
         }
     }
-    else
+    Ok(())
+}
+
+
+async fn process_votes(
+        config: &Config, mut collections: Collections, audit_log: &AuditLog, health: &health::Health,
+        mut shutdown: Shutdown,
+    )
+    -> i32
+{
+    let mut exit_code = shutdown::EXIT_DRAINED;
+    let mut connection = None;
+    // Attempts per vote still in the queue that MongoDB refused while both backends were connected; past
+    // `vote_max_attempts` a vote is dead-lettered. Requeues during an outage or on shutdown are not counted.
+    let mut attempts: HashMap<String, u32> = HashMap::new();
+    while !shutdown.is_requested()
     {
-        println!("Could not connect to redis!!!");
+        if connection.is_none()
+        {
+            connection = connect_with_retry(&health.redis, &mut shutdown, || connect_to_redis(&config.redis_addr)).await;
+        }
+        let redis = match connection.as_mut()
+        {
+            Some(redis) => redis,
+            None => break,
+        };
+
+        let mut wait = config.poll_interval;
+        if let Err(e) = forward_audit_events(redis, config, audit_log, health).await
+        {
+            health.redis.record_failure(&e);
+        }
+        match redis.lpop::<_, Option<String>>(&config.redis_key).await
+        {
+            Ok(popped) =>
+                {
+                    health.redis.record_success();
+                    if let Ok(queue_depth) = redis.llen::<_, u64>(&config.redis_key).await
+                    {
+                        health.record_queue_depth(queue_depth);
+                    }
+                    match popped
+                    {
+                        Some(data) =>
+                            {
+                                health.record_pop();
+                                let vote: Option<Vote> = serde_json::from_str(&data)
+                                    .map_err(|e| tracing::error!(error = %e, "malformed vote"))
+                                    .ok();
+                                // Votes queued before request ids existed are told apart by their contents.
+                                let attempt_key = vote.as_ref().and_then(|vote| vote.request_id.clone())
+                                    .unwrap_or_else(|| data.clone());
+                                let request_id = vote.as_ref().and_then(|vote| vote.request_id.clone());
+                                let logged_id = request_id.as_deref().unwrap_or("-");
+                                let exhausted = attempts.get(&attempt_key)
+                                    .map_or(false, |failed| *failed >= config.vote_max_attempts);
+                                let choice = vote.as_ref().and_then(|vote| Choice::of(&config.anonymity, vote));
+                                let vote = match vote
+                                {
+                                    Some(vote) if !exhausted => vote,
+                                    _ =>
+                                        {
+                                            attempts.remove(&attempt_key);
+                                            tracing::error!(request_id = logged_id, "dead-lettering vote");
+                                            let dead_lettered = dead_letter(
+                                                    redis, config, &data, request_id.as_deref(), choice.as_ref(),
+                                                )
+                                                .await;
+                                            if let Err(e) = dead_lettered
+                                            {
+                                                health.redis.record_failure(&e);
+                                                log_lost_vote(config, &data, "could not dead-letter vote");
+                                                exit_code = shutdown::EXIT_VOTE_LOST;
+                                                connection = None;
+                                            }
+                                            continue;
+                                        },
+                                };
+                                let span = tracing::info_span!("apply_vote",
+                                    request_id = logged_id,
+                                    voter = %config.anonymity.voter_label(&vote.voter_id));
+                                let processed =
+                                    process_vote(redis, config, &mut collections, health, &data, vote, shutdown.clone())
+                                        .instrument(span)
+                                        .await;
+                                let status = match processed
+                                {
+                                    Processed::Stored => VoteStatus::new(VoteState::Applied),
+                                    Processed::Rejected { reason } =>
+                                        VoteStatus::with_reason(VoteState::Rejected, reason),
+                                    Processed::Requeued { retry_in, counts } =>
+                                        {
+                                            if counts
+                                            {
+                                                *attempts.entry(attempt_key.clone()).or_insert(0) += 1;
+                                            }
+                                            wait = retry_in;
+                                            VoteStatus::new(VoteState::Queued)
+                                        },
+                                    Processed::Lost =>
+                                        {
+                                            exit_code = shutdown::EXIT_VOTE_LOST;
+                                            connection = None;
+                                            continue;
+                                        },
+                                };
+                                if status.state != VoteState::Queued
+                                {
+                                    attempts.remove(&attempt_key);
+                                    if let Err(e) = report(redis, request_id.as_deref(), choice.as_ref(), status).await
+                                    {
+                                        health.redis.record_failure(&e);
+                                    }
+                                    continue;
+                                }
+                            },
+                        None => tracing::debug!("no votes in redis"),
+                    }
+                },
+            Err(e) =>
+                {
+                    wait = health.redis.record_failure(&e);
+                    connection = None;
+                },
+        }
+        tokio::select!
+        {
+            _ = tokio::time::delay_for(wait) => (),
+            _ = shutdown.requested() => (),
+        }
     }
+    tracing::info!("worker stopped taking votes");
+    exit_code
+}
+
+
+#[tokio::main]
+async fn main()
+{
+    let config = match Config::load()
+    {
+        Ok(config) => config,
+        Err(e) =>
+            {
+                eprintln!("{}", e);
+                std::process::exit(common::config::EXIT_INVALID_CONFIG);
+            },
+    };
+
+    let _telemetry = common::telemetry::init("worker", &config.telemetry);
+    let mut shutdown = shutdown::listen();
+    let health = Arc::new(health::Health::default());
+    let health_listener = health.clone();
+    let health_bind = config.health_bind;
+    tokio::spawn(async move
+        {
+            if let Err(e) = health::serve(health_bind, health_listener).await
+            {
+                tracing::error!(error = %e, "health listener stopped");
+            }
+        });
+
+    let client = connect_with_retry(&health.mongodb, &mut shutdown, || connect_to_mongodb(&config.mongodb_addr)).await;
+    let exit_code = match client
+    {
+        Some(client) =>
+            {
+                let database = client.database(&config.mongodb_db_name);
+                let board_name = &config.mongodb_bulletin_board_collection_name;
+                let collections = Collections
+                {
+                    ballots: database.collection(&config.mongodb_collection_name),
+                    participation: database.collection(&config.mongodb_participation_collection_name),
+                    board: BulletinBoard::new(
+                        database.collection(board_name), database.collection(&format!("{}_roots", board_name))),
+                };
+                let audit_log = AuditLog::new(database.collection(&config.mongodb_audit_collection_name));
+                tracing::info!(anonymity = %config.anonymity.mode, "storing votes");
+                process_votes(&config, collections, &audit_log, &health, shutdown).await
+            },
+        None => shutdown::EXIT_UNAVAILABLE,
+    };
+    std::process::exit(exit_code);
 }
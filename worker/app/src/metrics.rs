@@ -0,0 +1,51 @@
+use std::time::Duration;
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram, register_int_counter_vec, register_int_gauge, Encoder, Histogram, IntCounterVec, IntGauge,
+    TextEncoder,
+};
+
+
+lazy_static!
+{
+    static ref QUEUE_DEPTH: IntGauge = register_int_gauge!(
+        "worker_queue_depth", "Votes waiting in the redis queue"
+    ).unwrap();
+    static ref VOTES_PROCESSED: IntCounterVec = register_int_counter_vec!(
+        "worker_votes_processed_total", "Votes taken from the queue by outcome", &["outcome"]
+    ).unwrap();
+    static ref PROCESSING_DURATION: Histogram = register_histogram!(
+        "worker_vote_processing_seconds", "Time spent storing a single vote in mongodb"
+    ).unwrap();
+    static ref QUEUE_LAG: Histogram = register_histogram!(
+        "worker_vote_queue_lag_seconds", "Time between a vote being queued and being stored"
+    ).unwrap();
+}
+
+
+pub fn observe_queue_depth(queue_depth: u64)
+{
+    QUEUE_DEPTH.set(queue_depth as i64);
+}
+
+
+pub fn observe_vote(outcome: &str, processing: Duration)
+{
+    VOTES_PROCESSED.with_label_values(&[outcome]).inc();
+    PROCESSING_DURATION.observe(processing.as_secs_f64());
+}
+
+
+pub fn observe_queue_lag(lag: Duration)
+{
+    QUEUE_LAG.observe(lag.as_secs_f64());
+}
+
+
+pub fn encode() -> (String, String)
+{
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    encoder.encode(&prometheus::gather(), &mut buffer).unwrap();
+    (encoder.format_type().to_owned(), String::from_utf8(buffer).unwrap())
+}
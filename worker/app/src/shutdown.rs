@@ -0,0 +1,58 @@
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+
+
+/// Exit status when the queue was drained without losing a vote.
+pub const EXIT_DRAINED: i32 = 0;
+/// Exit status when shutdown was requested before mongodb could be reached.
+pub const EXIT_UNAVAILABLE: i32 = 1;
+/// Exit status when an in-flight vote could be neither stored nor put back in the queue.
+pub const EXIT_VOTE_LOST: i32 = 2;
+
+
+/// Resolves once SIGTERM or SIGINT has been received.
+#[derive(Clone)]
+pub struct Shutdown
+{
+    receiver: watch::Receiver<bool>,
+}
+
+
+impl Shutdown
+{
+    pub fn is_requested(&self) -> bool
+    {
+        *self.receiver.borrow()
+    }
+
+
+    pub async fn requested(&mut self)
+    {
+        while !self.is_requested()
+        {
+            if self.receiver.recv().await.is_none()
+            {
+                return;
+            }
+        }
+    }
+}
+
+
+pub fn listen() -> Shutdown
+{
+    let (sender, receiver) = watch::channel(false);
+    tokio::spawn(async move
+        {
+            let mut terminate = signal(SignalKind::terminate()).expect("Could not listen for SIGTERM");
+            let mut interrupt = signal(SignalKind::interrupt()).expect("Could not listen for SIGINT");
+            tokio::select!
+            {
+                _ = terminate.recv() => (),
+                _ = interrupt.recv() => (),
+            }
+            tracing::info!("shutdown requested, draining in-flight votes");
+            let _ = sender.broadcast(true);
+        });
+    Shutdown { receiver }
+}
@@ -0,0 +1,69 @@
+//! Runs the worker binary against a local redis and a mongodb collection that refuses every document, so it is
+//! ignored by default. Start both servers and run `cargo test -- --ignored`; `REDIS_ADDR` and `MONGODB_ADDR`
+//! override the defaults.
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use redis::Commands;
+
+
+const DEAD_LETTER_TIMEOUT: Duration = Duration::from_secs(20);
+
+
+#[tokio::test]
+#[ignore]
+async fn a_dead_lettered_vote_is_no_longer_pending()
+{
+    let redis_addr = std::env::var("REDIS_ADDR").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_owned());
+    let mongodb_addr = std::env::var("MONGODB_ADDR").unwrap_or_else(|_| "mongodb://127.0.0.1:27017".to_owned());
+    let name = format!("dead_letter_test_{}", std::process::id());
+    let dead_letter_key = format!("{}_dead_letter", name);
+    let choice_key = common::poll::choice_key(&name, "voter-1");
+
+    let client = mongodb::Client::with_uri_str(&mongodb_addr).await.unwrap();
+    let database = client.database(&name);
+    let refuse_all = mongodb::bson::doc!
+    {
+        "create": &name,
+        "validator": { "$jsonSchema": { "required": ["never_set"] } },
+        "validationAction": "error",
+    };
+    database.run_command(refuse_all, None).await.unwrap();
+
+    let mut connection = redis::Client::open(redis_addr.as_str()).unwrap().get_connection().unwrap();
+    // What the vote app writes before queueing the vote.
+    let _: () = connection.hset_multiple(&choice_key, &[("pending_request_id", "request-1"), ("pending_vote", "\"a\"")])
+        .unwrap();
+    let vote = format!(r#"{{"request_id":"request-1","voter_id":"voter-1","vote":"a","poll_id":"{}"}}"#, name);
+    let _: u64 = connection.rpush(&name, vote).unwrap();
+
+    let mut worker = Command::new(env!("CARGO_BIN_EXE_worker"))
+        .env("REDIS_ADDR", &redis_addr)
+        .env("REDIS_KEY", &name)
+        .env("REDIS_DEAD_LETTER_KEY", &dead_letter_key)
+        .env("VOTE_MAX_ATTEMPTS", "2")
+        .env("MONGODB_ADDR", &mongodb_addr)
+        .env("MONGODB_DB_NAME", &name)
+        .env("MONGODB_COLLECTION_NAME", &name)
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let started = Instant::now();
+    while connection.llen::<_, u64>(&dead_letter_key).unwrap() == 0 && started.elapsed() < DEAD_LETTER_TIMEOUT
+    {
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    let sent = Command::new("kill").args(&["-TERM", &worker.id().to_string()]).status().unwrap();
+    assert!(sent.success());
+    assert_eq!(worker.wait().unwrap().code(), Some(0));
+
+    let dead_lettered: u64 = connection.llen(&dead_letter_key).unwrap();
+    let pending: Option<String> = connection.hget(&choice_key, "pending_request_id").unwrap();
+    let status: Option<String> = connection.get(common::vote_status::status_key("request-1")).unwrap();
+    database.drop(None).await.unwrap();
+    let _: () = connection.del(&[&name, &dead_letter_key, &choice_key]).unwrap();
+    let _: () = connection.del(common::vote_status::status_key("request-1")).unwrap();
+
+    assert_eq!(dead_lettered, 1);
+    assert_eq!(pending, None);
+    assert!(status.unwrap_or_default().contains("dead_lettered"));
+}
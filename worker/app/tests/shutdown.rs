@@ -0,0 +1,64 @@
+//! Runs the worker binary against a local redis and mongodb, so it is ignored by default.
+//! Start both servers and run `cargo test -- --ignored`; `REDIS_ADDR` and `MONGODB_ADDR` override the defaults.
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use redis::Commands;
+
+
+const VOTES: u64 = 500;
+const EXIT_TIMEOUT: Duration = Duration::from_secs(20);
+
+
+#[tokio::test]
+#[ignore]
+async fn sigterm_drains_without_losing_votes()
+{
+    let redis_addr = std::env::var("REDIS_ADDR").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_owned());
+    let mongodb_addr = std::env::var("MONGODB_ADDR").unwrap_or_else(|_| "mongodb://127.0.0.1:27017".to_owned());
+    let name = format!("shutdown_test_{}", std::process::id());
+
+    let mut connection = redis::Client::open(redis_addr.as_str()).unwrap().get_connection().unwrap();
+    for voter in 0..VOTES
+    {
+        let vote = format!(r#"{{"voter_id":"voter-{}","vote":"a"}}"#, voter);
+        let _: u64 = connection.rpush(&name, vote).unwrap();
+    }
+
+    let mut worker = Command::new(env!("CARGO_BIN_EXE_worker"))
+        .env("REDIS_ADDR", &redis_addr)
+        .env("REDIS_KEY", &name)
+        .env("MONGODB_ADDR", &mongodb_addr)
+        .env("MONGODB_DB_NAME", &name)
+        .env("MONGODB_COLLECTION_NAME", &name)
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    tokio::time::delay_for(Duration::from_millis(1500)).await;
+
+    let sent = Command::new("kill").args(&["-TERM", &worker.id().to_string()]).status().unwrap();
+    assert!(sent.success());
+    let started = Instant::now();
+    let status = loop
+    {
+        if let Some(status) = worker.try_wait().unwrap()
+        {
+            break status;
+        }
+        if started.elapsed() > EXIT_TIMEOUT
+        {
+            worker.kill().unwrap();
+            panic!("worker did not exit after SIGTERM");
+        }
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    };
+    assert_eq!(status.code(), Some(0));
+
+    let queued: u64 = connection.llen(&name).unwrap();
+    let client = mongodb::Client::with_uri_str(&mongodb_addr).await.unwrap();
+    let database = client.database(&name);
+    let stored = database.collection(&name).count_documents(mongodb::bson::doc! {}, None).await.unwrap();
+    database.drop(None).await.unwrap();
+    let _: () = connection.del(&name).unwrap();
+
+    assert_eq!(queued + stored as u64, VOTES);
+}
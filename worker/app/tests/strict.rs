@@ -0,0 +1,67 @@
+//! Runs the worker binary in strict anonymity mode against a local redis and mongodb, so it is ignored by
+//! default. Start both servers and run `cargo test -- --ignored`; `REDIS_ADDR` and `MONGODB_ADDR` override the
+//! defaults.
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use redis::Commands;
+
+
+const STORE_TIMEOUT: Duration = Duration::from_secs(20);
+
+
+#[tokio::test]
+#[ignore]
+async fn one_voter_can_vote_once_in_each_poll()
+{
+    let redis_addr = std::env::var("REDIS_ADDR").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_owned());
+    let mongodb_addr = std::env::var("MONGODB_ADDR").unwrap_or_else(|_| "mongodb://127.0.0.1:27017".to_owned());
+    let name = format!("strict_test_{}", std::process::id());
+    let participation = format!("{}_participation", name);
+
+    let mut connection = redis::Client::open(redis_addr.as_str()).unwrap().get_connection().unwrap();
+    for (poll, vote) in &[("poll-1", "a"), ("poll-2", "b"), ("poll-1", "b")]
+    {
+        let vote = format!(r#"{{"voter_id":"voter-1","vote":"{}","poll_id":"{}"}}"#, vote, poll);
+        let _: u64 = connection.rpush(&name, vote).unwrap();
+    }
+
+    let mut worker = Command::new(env!("CARGO_BIN_EXE_worker"))
+        .env("REDIS_ADDR", &redis_addr)
+        .env("REDIS_KEY", &name)
+        .env("MONGODB_ADDR", &mongodb_addr)
+        .env("MONGODB_DB_NAME", &name)
+        .env("MONGODB_COLLECTION_NAME", &name)
+        .env("MONGODB_PARTICIPATION_COLLECTION_NAME", &participation)
+        .env("ANONYMITY_MODE", "strict")
+        .env("ANONYMITY_KEY", "0123456789abcdef0123456789abcdef")
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let started = Instant::now();
+    while connection.llen::<_, u64>(&name).unwrap() > 0 && started.elapsed() < STORE_TIMEOUT
+    {
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+    }
+    let sent = Command::new("kill").args(&["-TERM", &worker.id().to_string()]).status().unwrap();
+    assert!(sent.success());
+    assert_eq!(worker.wait().unwrap().code(), Some(0));
+
+    let client = mongodb::Client::with_uri_str(&mongodb_addr).await.unwrap();
+    let database = client.database(&name);
+    let ballots = database.collection(&name).count_documents(mongodb::bson::doc! {}, None).await.unwrap();
+    let counted = database.collection(&participation)
+        .count_documents(mongodb::bson::doc! { "counted": true, "ballot_id": { "$exists": false } }, None)
+        .await
+        .unwrap();
+    let with_voter = database.collection(&name)
+        .count_documents(mongodb::bson::doc! { "voter_id": { "$exists": true } }, None)
+        .await
+        .unwrap();
+    database.drop(None).await.unwrap();
+    let _: () = connection.del(&name).unwrap();
+
+    // The second vote in poll-1 is refused: ballots are final in strict mode.
+    assert_eq!(ballots, 2);
+    assert_eq!(counted, 2);
+    assert_eq!(with_voter, 0);
+}